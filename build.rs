@@ -0,0 +1,12 @@
+use std::error::Error;
+use vergen::EmitBuilder;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // Embeds VERGEN_* variables used by `utas version`
+    EmitBuilder::builder()
+        .build_date()
+        .git_sha(true)
+        .rustc_semver()
+        .emit()?;
+    Ok(())
+}
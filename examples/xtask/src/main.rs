@@ -0,0 +1,42 @@
+//! Generates gettext files of the project from twine files with utas library.
+//!
+//! `cargo xtask gen-po <twine file> <output dir>` writes `messages.pot` and `<lang>.po`
+//! of every language with `Plural-Forms` headers, `gettext-rs` loads them after compiling
+//! with `msgfmt --check`.
+
+use std::env;
+use std::error::Error;
+use std::path::Path;
+use std::process::ExitCode;
+
+use utas::parse::parse;
+use utas::po::{write_po_all, write_pot};
+use utas::sink::FsSink;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.as_slice() {
+        [task, input, output_dir] if task == "gen-po" => {
+            gen_po(Path::new(input), Path::new(output_dir))
+        }
+        _ => {
+            eprintln!("Usage: cargo xtask gen-po <twine file> <output dir>");
+            return ExitCode::FAILURE;
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn gen_po(input: &Path, output_dir: &Path) -> Result<(), Box<dyn Error>> {
+    let file = parse(input)?;
+    let mut sink = FsSink::default();
+    write_pot(&file, &output_dir.join("messages.pot"), &mut sink)?;
+    write_po_all(&file, output_dir, &mut sink)?;
+    Ok(())
+}
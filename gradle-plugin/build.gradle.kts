@@ -0,0 +1,20 @@
+plugins {
+    `kotlin-dsl`
+    `java-gradle-plugin`
+}
+
+group = "com.github.truewarg.utas"
+version = "0.1.0"
+
+dependencies {
+    compileOnly("com.android.tools.build:gradle-api:8.2.0")
+}
+
+gradlePlugin {
+    plugins {
+        create("utas") {
+            id = "com.github.truewarg.utas"
+            implementationClass = "com.github.truewarg.utas.UtasPlugin"
+        }
+    }
+}
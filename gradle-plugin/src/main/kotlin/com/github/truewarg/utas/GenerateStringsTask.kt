@@ -0,0 +1,70 @@
+package com.github.truewarg.utas
+
+import org.gradle.api.DefaultTask
+import org.gradle.api.file.DirectoryProperty
+import org.gradle.api.provider.ListProperty
+import org.gradle.api.provider.Property
+import org.gradle.api.tasks.CacheableTask
+import org.gradle.api.tasks.Input
+import org.gradle.api.tasks.InputDirectory
+import org.gradle.api.tasks.Optional
+import org.gradle.api.tasks.OutputDirectory
+import org.gradle.api.tasks.PathSensitive
+import org.gradle.api.tasks.PathSensitivity
+import org.gradle.api.tasks.TaskAction
+import org.gradle.process.ExecOperations
+import javax.inject.Inject
+
+/**
+ * Generates Android string resources from twine files with utas. Gradle reruns the task
+ * only if the twine files or the options change.
+ */
+@CacheableTask
+abstract class GenerateStringsTask @Inject constructor(
+    private val execOperations: ExecOperations,
+) : DefaultTask() {
+    @get:InputDirectory
+    @get:PathSensitive(PathSensitivity.RELATIVE)
+    abstract val inputDir: DirectoryProperty
+
+    @get:Input
+    abstract val executable: Property<String>
+
+    @get:Input
+    @get:Optional
+    abstract val defaultLanguage: Property<String>
+
+    @get:Input
+    abstract val onlyLanguages: ListProperty<String>
+
+    @get:Input
+    abstract val extraArgs: ListProperty<String>
+
+    @get:OutputDirectory
+    abstract val outputDir: DirectoryProperty
+
+    @TaskAction
+    fun generate() {
+        val output = outputDir.get().asFile
+        // Resources of removed keys and languages must not be left behind
+        output.deleteRecursively()
+        output.mkdirs()
+
+        val arguments = mutableListOf(
+            "android",
+            inputDir.get().asFile.absolutePath,
+            output.absolutePath,
+        )
+        defaultLanguage.orNull?.let { arguments += it }
+        val languages = onlyLanguages.get()
+        if (languages.isNotEmpty()) {
+            arguments += "--only-languages=${languages.joinToString(",")}"
+        }
+        arguments += extraArgs.get()
+
+        execOperations.exec {
+            executable(this@GenerateStringsTask.executable.get())
+            args(arguments)
+        }
+    }
+}
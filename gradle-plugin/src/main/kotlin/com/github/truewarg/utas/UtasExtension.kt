@@ -0,0 +1,25 @@
+package com.github.truewarg.utas
+
+import org.gradle.api.file.DirectoryProperty
+import org.gradle.api.provider.ListProperty
+import org.gradle.api.provider.Property
+
+/**
+ * Configuration of the `utas` block.
+ */
+abstract class UtasExtension {
+    /** Directory with twine files, every file in it is generated. */
+    abstract val inputDir: DirectoryProperty
+
+    /** Path or name on PATH of the utas binary, `utas` by default. */
+    abstract val executable: Property<String>
+
+    /** Language written to `values/` without a qualifier. */
+    abstract val defaultLanguage: Property<String>
+
+    /** Generate only these languages, all languages are generated if empty. */
+    abstract val onlyLanguages: ListProperty<String>
+
+    /** Extra arguments passed to utas as is, e.g. `--android-flatten-plurals`. */
+    abstract val extraArgs: ListProperty<String>
+}
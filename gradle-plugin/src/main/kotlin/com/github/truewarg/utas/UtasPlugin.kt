@@ -0,0 +1,42 @@
+package com.github.truewarg.utas
+
+import com.android.build.api.variant.AndroidComponentsExtension
+import org.gradle.api.Plugin
+import org.gradle.api.Project
+
+/**
+ * Adds `generateStrings` task and registers its output as generated resources of every
+ * variant, so the task runs before resources are merged.
+ */
+class UtasPlugin : Plugin<Project> {
+    override fun apply(project: Project) {
+        val extension = project.extensions.create("utas", UtasExtension::class.java)
+        extension.executable.convention("utas")
+        extension.inputDir.convention(project.layout.projectDirectory.dir("twine"))
+
+        val generateStrings = project.tasks.register(
+            "generateStrings",
+            GenerateStringsTask::class.java,
+        ) {
+            group = "utas"
+            description = "Generates Android string resources from twine files"
+            inputDir.set(extension.inputDir)
+            executable.set(extension.executable)
+            defaultLanguage.set(extension.defaultLanguage)
+            onlyLanguages.set(extension.onlyLanguages)
+            extraArgs.set(extension.extraArgs)
+            outputDir.set(project.layout.buildDirectory.dir("generated/utas/res"))
+        }
+
+        project.plugins.withId("com.android.base") {
+            val androidComponents =
+                project.extensions.getByType(AndroidComponentsExtension::class.java)
+            androidComponents.onVariants { variant ->
+                variant.sources.res?.addGeneratedSourceDirectory(
+                    generateStrings,
+                    GenerateStringsTask::outputDir,
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,21 @@
+// swift-tools-version:5.7
+import PackageDescription
+
+let package = Package(
+    name: "UtasPlugin",
+    products: [
+        .plugin(name: "UtasPlugin", targets: ["UtasPlugin"]),
+    ],
+    targets: [
+        .plugin(
+            name: "UtasPlugin",
+            capability: .buildTool(),
+            dependencies: ["utas"]
+        ),
+        // Release archive of utas repacked as an artifact bundle, see README.md
+        .binaryTarget(
+            name: "utas",
+            path: "utas.artifactbundle"
+        ),
+    ]
+)
@@ -0,0 +1,94 @@
+import Foundation
+import PackagePlugin
+
+/// Generates `<lang>.lproj/Localizable.strings` and `Localizable.stringsdict` from twine
+/// files in `Twine` directory of the target. Options are read from `utas.json` next to it.
+@main
+struct UtasPlugin: BuildToolPlugin {
+    func createBuildCommands(context: PluginContext, target: Target) throws -> [Command] {
+        guard let target = target as? SourceModuleTarget else {
+            return []
+        }
+        let twineDirectory = target.directory.appending("Twine")
+        let twineFiles = target.sourceFiles
+            .map(\.path)
+            .filter { $0.extension == "twine" && $0.removingLastComponent() == twineDirectory }
+        if twineFiles.isEmpty {
+            return []
+        }
+
+        let configPath = target.directory.appending("utas.json")
+        let config = try Config.load(from: configPath)
+        var inputFiles = twineFiles
+        if FileManager.default.fileExists(atPath: configPath.string) {
+            inputFiles.append(configPath)
+        }
+
+        let outputDirectory = context.pluginWorkDirectory.appending("Localization")
+        try FileManager.default.createDirectory(
+            atPath: outputDirectory.string,
+            withIntermediateDirectories: true
+        )
+
+        var arguments = ["ios", twineDirectory.string, outputDirectory.string]
+        var languages = try twineFiles.flatMap(languageCodes(in:)).uniqued()
+        if let onlyLanguages = config.onlyLanguages, !onlyLanguages.isEmpty {
+            arguments.append("--only-languages=\(onlyLanguages.joined(separator: ","))")
+            languages = languages.filter(onlyLanguages.contains)
+        }
+
+        // Declared outputs let Xcode rerun the command only when the inputs change
+        let outputFiles = languages.flatMap { language in
+            ["Localizable.strings", "Localizable.stringsdict"].map {
+                outputDirectory.appending("\(language).lproj", $0)
+            }
+        }
+        return [
+            .buildCommand(
+                displayName: "Generating localized strings of \(target.name) with utas",
+                executable: try context.tool(named: "utas").path,
+                arguments: arguments,
+                inputFiles: inputFiles,
+                outputFiles: outputFiles
+            ),
+        ]
+    }
+}
+
+/// Contents of `utas.json`, SwiftPM doesn't pass options from the manifest to plugins
+struct Config: Decodable {
+    /// Generate only these languages, all languages are generated if missing
+    var onlyLanguages: [String]?
+
+    static func load(from path: Path) throws -> Config {
+        guard let data = FileManager.default.contents(atPath: path.string) else {
+            return Config()
+        }
+        return try JSONDecoder().decode(Config.self, from: data)
+    }
+}
+
+/// Key attributes which look like localizations in twine files
+private let keyAttributes: Set<String> = ["comment", "tags", "max_length", "auto_translated"]
+
+/// Language codes of localizations in the twine file, e.g. `en` for `en = …` and `en:one = …`
+private func languageCodes(in path: Path) throws -> [String] {
+    let content = try String(contentsOfFile: path.string, encoding: .utf8)
+    return content.split(whereSeparator: \.isNewline).compactMap { line in
+        let line = line.trimmingCharacters(in: .whitespaces)
+        guard !line.hasPrefix("["), !line.hasPrefix("#"), !line.hasPrefix(";"),
+              let separator = line.firstIndex(of: "=") else {
+            return nil
+        }
+        let name = line[..<separator].trimmingCharacters(in: .whitespaces)
+        let language = String(name.split(separator: ":").first ?? "")
+        return language.isEmpty || keyAttributes.contains(language) ? nil : language
+    }
+}
+
+private extension Array where Element == String {
+    func uniqued() -> [String] {
+        var seen = Set<String>()
+        return filter { seen.insert($0).inserted }
+    }
+}
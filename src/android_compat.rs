@@ -0,0 +1,242 @@
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashSet;
+
+const PLURAL_QUANTITIES: &[&str] = &["zero", "one", "two", "few", "many", "other"];
+
+/// Names aapt2 rejects since they can't become fields of generated `R` class
+const RESERVED_NAMES: &[&str] = &[
+    "abstract",
+    "assert",
+    "boolean",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extends",
+    "false",
+    "final",
+    "finally",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "implements",
+    "import",
+    "instanceof",
+    "int",
+    "interface",
+    "long",
+    "native",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "static",
+    "strictfp",
+    "super",
+    "switch",
+    "synchronized",
+    "this",
+    "throw",
+    "throws",
+    "transient",
+    "true",
+    "try",
+    "void",
+    "volatile",
+    "while",
+];
+
+/// Checks generated strings.xml content against the subset of aapt2 rules,
+/// returns a message for every violation
+pub fn check_android_xml(xml: &str) -> Vec<String> {
+    let mut violations: Vec<String> = Vec::new();
+    let mut names: HashSet<(String, String)> = HashSet::new();
+    let mut reader = Reader::from_str(xml);
+    // (resource name, quantity) of the value which text is being collected
+    let mut current: Option<(String, Option<String>)> = None;
+    let mut text = String::new();
+    let mut resource_name = String::new();
+    loop {
+        let event = match reader.read_event() {
+            Ok(event) => event,
+            Err(err) => {
+                violations.push(format!(
+                    "invalid XML at position {}: {}",
+                    reader.error_position(),
+                    err
+                ));
+                return violations;
+            }
+        };
+        match event {
+            Event::Start(element) | Event::Empty(element) if current.is_none() => {
+                match element.name().as_ref() {
+                    tag @ (b"string" | b"plurals") => {
+                        let tag = String::from_utf8_lossy(tag).to_string();
+                        resource_name = attribute(&element, b"name").unwrap_or_default();
+                        check_name(&tag, &resource_name, &mut names, &mut violations);
+                        if tag == "string" {
+                            current = Some((resource_name.clone(), None));
+                            text.clear();
+                        }
+                    }
+                    b"item" => {
+                        let quantity = attribute(&element, b"quantity").unwrap_or_default();
+                        if !PLURAL_QUANTITIES.contains(&quantity.as_str()) {
+                            violations.push(format!(
+                                "\"{}\" has invalid plural quantity \"{}\"",
+                                resource_name, quantity
+                            ));
+                        }
+                        current = Some((resource_name.clone(), Some(quantity)));
+                        text.clear();
+                    }
+                    _ => {}
+                }
+            }
+            Event::Text(value) if current.is_some() => match value.unescape() {
+                Ok(value) => text.push_str(&value),
+                Err(err) => violations.push(format!("invalid XML text \"{:?}\": {}", value, err)),
+            },
+            Event::CData(value) if current.is_some() => {
+                text.push_str(&String::from_utf8_lossy(&value));
+            }
+            Event::End(element) if matches!(element.name().as_ref(), b"string" | b"item") => {
+                if let Some((name, quantity)) = current.take() {
+                    let name = match quantity {
+                        Some(quantity) => format!("{}:{}", name, quantity),
+                        None => name,
+                    };
+                    check_text(&name, &text, &mut violations);
+                }
+            }
+            Event::Eof => return violations,
+            _ => {}
+        }
+    }
+}
+
+fn check_name(
+    tag: &str,
+    name: &str,
+    names: &mut HashSet<(String, String)>,
+    violations: &mut Vec<String>,
+) {
+    if name.is_empty() {
+        violations.push(format!("<{}> has no name", tag));
+        return;
+    }
+    if !names.insert((tag.to_string(), name.to_string())) {
+        violations.push(format!("duplicate <{}> resource \"{}\"", tag, name));
+    }
+    if name.starts_with(|char: char| char.is_ascii_digit()) {
+        violations.push(format!("resource name \"{}\" starts with a digit", name));
+    } else if RESERVED_NAMES.contains(&name) {
+        violations.push(format!("resource name \"{}\" is reserved", name));
+    } else if !name
+        .chars()
+        .all(|char| char.is_ascii_alphanumeric() || char == '_' || char == '.')
+    {
+        violations.push(format!("resource name \"{}\" has invalid characters", name));
+    }
+}
+
+fn check_text(name: &str, text: &str, violations: &mut Vec<String>) {
+    if text.starts_with(['@', '?']) {
+        violations.push(format!(
+            "\"{}\" starts with unescaped \"{}\" and is treated as a reference",
+            name,
+            &text[..1]
+        ));
+    }
+    // Apostrophes don't need escaping only inside a string wrapped in double quotes
+    let quoted = text.len() > 1 && text.starts_with('"') && text.ends_with('"');
+    let mut escaped = false;
+    for char in text.chars() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match char {
+            '\\' => escaped = true,
+            '\'' if !quoted => {
+                violations.push(format!("\"{}\" has apostrophe not preceded by \\", name));
+                return;
+            }
+            _ => {}
+        }
+    }
+    if escaped {
+        violations.push(format!("\"{}\" ends with unescaped \\", name));
+    }
+}
+
+fn attribute(element: &BytesStart, name: &[u8]) -> Option<String> {
+    element
+        .try_get_attribute(name)
+        .ok()
+        .flatten()
+        .and_then(|attribute| attribute.unescape_value().ok())
+        .map(|value| value.to_string())
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn accepts_generated_resources() {
+    let xml = "<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<resources>
+    <string name=\"greeting\">Hello, %1$s! You\\'ve got &lt;b>coins&lt;/b></string>
+    <string name=\"quoted\">\"Don't\"</string>
+    <plurals name=\"greeting\">
+        <item quantity=\"one\">%d day</item>
+        <item quantity=\"other\">%d days</item>
+    </plurals>
+</resources>
+";
+
+    assert_eq!(check_android_xml(xml), Vec::<String>::new());
+}
+
+#[test]
+fn reports_aapt2_violations() {
+    let xml = "<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<resources>
+    <string name=\"1st\">First</string>
+    <string name=\"class\">Class</string>
+    <string name=\"login\">Login</string>
+    <string name=\"login\">Don't</string>
+    <string name=\"mention\">@user</string>
+    <plurals name=\"days\">
+        <item quantity=\"several\">%d days</item>
+    </plurals>
+</resources>
+";
+
+    assert_eq!(
+        check_android_xml(xml),
+        vec![
+            "resource name \"1st\" starts with a digit".to_string(),
+            "resource name \"class\" is reserved".to_string(),
+            "duplicate <string> resource \"login\"".to_string(),
+            "\"login\" has apostrophe not preceded by \\".to_string(),
+            "\"mention\" starts with unescaped \"@\" and is treated as a reference".to_string(),
+            "\"days\" has invalid plural quantity \"several\"".to_string(),
+        ]
+    );
+    assert_eq!(check_android_xml("<resources><string>").len(), 1);
+}
@@ -1,11 +1,25 @@
-use anyhow::{anyhow, Ok, Result};
+use anyhow::Result;
 use lazy_static::lazy_static;
+use quick_xml::escape::escape;
 use regex::{Captures, Match, Regex};
-use std::{collections::HashMap, io::Write, path::Path};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fs,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
 
-use std::fs;
+use crate::gen_error::GenError;
+use crate::locale::locale_qualifier;
+use crate::parse::{File, Key, PluralValue, StringValue, PLACEHOLDER_OR_PERCENT_RE};
+#[cfg(test)]
+use crate::parse::{LocalizedString, Section};
+use crate::parse_error::ParseError;
+use crate::sink::WriteSink;
+#[cfg(test)]
+use crate::test_tools::{key, plain_str, plurals};
 
-use crate::parse::{File, Key, LocalizedString, PluralValue, Section, StringValue};
+const XLIFF_NAMESPACE: &str = "urn:oasis:names:tc:xliff:document:1.2";
 
 #[derive(PartialEq, Eq, Hash, Debug, PartialOrd, Ord, Clone)]
 pub struct Locale {
@@ -21,17 +35,135 @@ pub struct StrLines {
 pub struct Line {
     name: String,
     value: StringValue,
+    /// `translatable="false"` lines are only written for the default language
+    translatable: bool,
 }
 
 impl Line {
-    fn format(&self) -> Vec<String> {
-        match &self.value {
-            StringValue::Single(text) => vec![generate_str_value(&self.name, text)],
-            StringValue::Plural { quantities } => generate_plural_value(&self.name, quantities),
+    fn format(&self, options: &Options, source: Option<&Line>) -> Vec<String> {
+        let mut result = Vec::new();
+        if let Some(package_name) = &options.package_name {
+            result.push(generate_r_reference_comment(
+                package_name,
+                options.resource_type,
+                self,
+            ));
+        }
+        if let (Some(source_lang), Some(source)) = (&options.source_comments_lang, source) {
+            result.extend(generate_source_comments(source_lang, &source.value));
+        }
+        let value = match &self.value {
+            StringValue::Single(text) if options.xliff_placeholders => {
+                StringValue::Single(wrap_placeholders(&self.name, text, options))
+            }
+            StringValue::Array(items) if options.xliff_placeholders => StringValue::Array(
+                items
+                    .iter()
+                    .map(|item| wrap_placeholders(&self.name, item, options))
+                    .collect(),
+            ),
+            StringValue::Plural { quantities } if options.xliff_placeholders => {
+                StringValue::Plural {
+                    quantities: quantities
+                        .iter()
+                        .map(|item| {
+                            let text = wrap_placeholders(&self.name, &item.text, options);
+                            PluralValue::new(item.quantity.clone(), text)
+                        })
+                        .collect(),
+                }
+            }
+            value => value.clone(),
+        };
+        match &value {
+            StringValue::Single(text) if options.resource_type == ResourceType::Strings => {
+                let mut attributes = String::new();
+                if !self.translatable && !options.skip_translatable_tag {
+                    attributes.push_str(" translatable=\"false\"");
+                }
+                if options.formatted_attr && !has_placeholders(text) {
+                    attributes.push_str(" formatted=\"false\"");
+                }
+                result.push(format!(
+                    "<string name=\"{}\"{}>{}</string>",
+                    self.name, attributes, text
+                ))
+            }
+            StringValue::Single(text) => result.push(generate_value(
+                options.resource_type.tag(),
+                &self.name,
+                text,
+            )),
+            StringValue::Plural { quantities } => {
+                result.extend(generate_plural_value(&self.name, quantities))
+            }
+            StringValue::Array(items) => result.extend(generate_array_value(&self.name, items)),
+        }
+        result
+    }
+}
+
+/// Kind of value resources generated from keys
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum ResourceType {
+    #[default]
+    Strings,
+    /// `<color>` resources with hex values, e.g. `#FF0000`
+    Colors,
+    /// `<dimen>` resources with values like `16dp`
+    Dimens,
+}
+
+impl ResourceType {
+    fn tag(&self) -> &'static str {
+        match self {
+            ResourceType::Strings => "string",
+            ResourceType::Colors => "color",
+            ResourceType::Dimens => "dimen",
+        }
+    }
+
+    /// Whether value is valid for the resource type, plurals and arrays are only supported by strings
+    fn supports(&self, value: &StringValue) -> bool {
+        lazy_static! {
+            static ref COLOR_RE: Regex =
+                Regex::new(r"^#([0-9A-Fa-f]{3}|[0-9A-Fa-f]{4}|[0-9A-Fa-f]{6}|[0-9A-Fa-f]{8})$")
+                    .unwrap();
+            static ref DIMEN_RE: Regex =
+                Regex::new(r"^-?\d+(\.\d+)?(dp|dip|sp|pt|px|mm|in)$").unwrap();
+        }
+        match (self, value) {
+            (ResourceType::Strings, _) => true,
+            (_, StringValue::Plural { .. } | StringValue::Array(_)) => false,
+            (ResourceType::Colors, StringValue::Single(text)) => COLOR_RE.is_match(text),
+            (ResourceType::Dimens, StringValue::Single(text)) => DIMEN_RE.is_match(text),
         }
     }
 }
 
+/// Tweaks of generated strings.xml content
+#[derive(Default)]
+pub struct Options {
+    /// Application package used for `R` references in comments above every resource
+    pub package_name: Option<String>,
+    /// Language which values are added as comments above resources of all other languages
+    pub source_comments_lang: Option<String>,
+    pub resource_type: ResourceType,
+    /// Wrap printf placeholders of strings into `<xliff:g>` so that translation tools keep them
+    pub xliff_placeholders: bool,
+    /// `example` attributes of `<xliff:g>` keyed by key name and placeholder id, e.g. `arg1`.
+    /// Placeholders without an example get the type of the value, e.g. `string` or `number`.
+    pub placeholder_examples: HashMap<String, HashMap<String, String>>,
+    /// Don't add `translatable="false"` to strings of non-translatable keys
+    pub skip_translatable_tag: bool,
+    /// `minSdkVersion` of the app, language directories are named in BCP 47 format since 21,
+    /// e.g. `values-b+zh+Hant`
+    pub min_sdk: u32,
+    /// Add `formatted="false"` to strings without printf placeholders, so that lint doesn't
+    /// report `StringFormatInvalid` when they are passed to `String.format()`
+    pub formatted_attr: bool,
+}
+
 pub struct GenResult {
     value: HashMap<Locale, StrLines>,
 }
@@ -42,47 +174,49 @@ impl GenResult {
         dir: impl AsRef<Path>,
         file_name: &str,
         default_lang: &Option<String>,
-    ) -> Result<()> {
-        lazy_static! {
-            static ref LANG_WITH_REGION_RE: Regex = Regex::new(r"-(\p{Lu})").unwrap();
-        }
+        options: &Options,
+        sink: &mut dyn WriteSink,
+    ) -> Result<(), GenError> {
         for (locale, lines) in &self.value {
-            let lang = LANG_WITH_REGION_RE.replace_all(&locale.value, |caps: &Captures| {
-                format!("-r{}", caps.get(1).unwrap().as_str())
-            });
-            if !locale_code_supported_in_android(&lang) {
+            let Some(lang) = android_lang(&locale.value, options.min_sdk) else {
                 continue;
-            }
+            };
 
-            let subpath = dir.as_ref().join(format!("values-{}", lang));
-            if !subpath.is_dir() {
-                fs::create_dir(&subpath)?;
-            }
-            let filepath = subpath.join(format!("{}.xml", file_name));
-            let mut file = fs::OpenOptions::new()
-                .write(true)
-                .truncate(true)
-                .create(true)
-                .open(&filepath)?;
-            file.write("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n".as_bytes())?;
-            file.write("\n".as_bytes())?;
-            file.write("<resources>\n".as_bytes())?;
-            for line in &lines.value {
-                let formatted = line.format();
-                for item in formatted {
-                    file.write(format!("  {}\n", item).as_bytes())?;
-                }
-            }
-            file.write("</resources>\n".as_bytes())?;
+            let filepath = dir
+                .as_ref()
+                .join(format!("values-{}", lang))
+                .join(format!("{}.xml", file_name));
+            let mut file: Vec<u8> = Vec::new();
+            let source_lines = options
+                .source_comments_lang
+                .as_ref()
+                .filter(|source_lang| *source_lang != &locale.value)
+                .and_then(|source_lang| {
+                    self.value.get(&Locale {
+                        value: source_lang.clone(),
+                    })
+                });
+            // Non-translatable lines only go to `values` of the default language if it's known
+            let translatable_lines = StrLines {
+                value: lines
+                    .value
+                    .iter()
+                    .filter(|line| line.translatable || default_lang.is_none())
+                    .cloned()
+                    .collect(),
+            };
+            write_resources(&translatable_lines, source_lines, options, &mut file)?;
+            sink.write(&filepath, &file)?;
             match default_lang {
                 Some(lang) => {
                     if lang == &locale.value {
-                        let subpath = dir.as_ref().join("values");
-                        if !subpath.is_dir() {
-                            fs::create_dir(&subpath)?;
-                        }
-                        let copy = subpath.join(format!("{}.xml", file_name));
-                        fs::copy(filepath, copy)?;
+                        let filepath = dir
+                            .as_ref()
+                            .join("values")
+                            .join(format!("{}.xml", file_name));
+                        let mut default_file: Vec<u8> = Vec::new();
+                        write_resources(lines, source_lines, options, &mut default_file)?;
+                        sink.write(&filepath, &default_file)?;
                     }
                 }
                 None => (),
@@ -92,57 +226,381 @@ impl GenResult {
     }
 }
 
-fn locale_code_supported_in_android(code: &str) -> bool {
-    // https://stackoverflow.com/questions/17275697/is-there-any-need-to-prepare-values-zh-and-values-zh-rhk/17276279
-    return code != "zh-rHans" && code != "zh-rHant" && code != "zh-rPinyin";
+/// Generates strings.xml content for a single language and returns it as a String
+pub fn generate_android_xml_to_string(file: &File, lang: &str) -> Result<String, GenError> {
+    let mut buffer: Vec<u8> = Vec::new();
+    generate_android_xml_to_writer(file, lang, &mut buffer)?;
+    String::from_utf8(buffer)
+        .map_err(|err| GenError::IoError(io::Error::new(io::ErrorKind::InvalidData, err)))
 }
 
-pub fn generate(source: &File) -> Result<GenResult> {
-    if source.sections.len() > 1 {
-        panic!("Expected only one section currently")
+/// Generates strings.xml content for a single language and streams it into the writer
+pub fn generate_android_xml_to_writer<W: Write>(
+    file: &File,
+    lang: &str,
+    writer: &mut W,
+) -> Result<(), GenError> {
+    let generated = generate(file)?;
+    let locale = Locale {
+        value: lang.to_string(),
+    };
+    let Some(lines) = generated.value.get(&locale) else {
+        return Err(GenError::UnknownLanguage(lang.to_string()))
     };
+    write_resources(lines, None, &Options::default(), writer)
+}
 
-    let Some(keys) = source.sections.first().map(|section| &section.keys) else {
-        return Err(anyhow!("Expected at least one section"))
+impl File {
+    /// Generates strings.xml content of every language in memory, keyed by language code
+    pub fn to_android_xml_map(&self) -> Result<HashMap<String, String>, GenError> {
+        let generated = generate(self)?;
+        let mut result = HashMap::with_capacity(generated.value.len());
+        for (locale, lines) in &generated.value {
+            let mut buffer: Vec<u8> = Vec::new();
+            write_resources(lines, None, &Options::default(), &mut buffer)?;
+            let xml = String::from_utf8(buffer)
+                .map_err(|err| GenError::IoError(io::Error::new(io::ErrorKind::InvalidData, err)))?;
+            result.insert(locale.value.clone(), xml);
+        }
+        Ok(result)
+    }
+}
+
+fn write_resources<W: Write>(
+    lines: &StrLines,
+    source_lines: Option<&StrLines>,
+    options: &Options,
+    writer: &mut W,
+) -> Result<(), GenError> {
+    let source_by_name: HashMap<&str, &Line> = source_lines
+        .map(|source| {
+            source
+                .value
+                .iter()
+                .map(|line| (line.name.as_str(), line))
+                .collect()
+        })
+        .unwrap_or_default();
+    write_resources_start(options, writer)?;
+    for line in &lines.value {
+        let source = source_by_name.get(line.name.as_str()).copied();
+        write_line(line, source, options, writer)?;
+    }
+    write_resources_end(writer)
+}
+
+fn write_resources_start<W: Write>(options: &Options, writer: &mut W) -> Result<(), GenError> {
+    writer.write_all("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n".as_bytes())?;
+    writer.write_all("\n".as_bytes())?;
+    let start = if options.xliff_placeholders {
+        format!("<resources xmlns:xliff=\"{}\">\n", XLIFF_NAMESPACE)
+    } else {
+        "<resources>\n".to_string()
     };
+    writer.write_all(start.as_bytes())?;
+    Ok(())
+}
 
-    let mut result: HashMap<Locale, StrLines> = HashMap::new();
-    let keys_len = keys.len();
+fn write_line<W: Write>(
+    line: &Line,
+    source: Option<&Line>,
+    options: &Options,
+    writer: &mut W,
+) -> Result<(), GenError> {
+    if !options.resource_type.supports(&line.value) {
+        return Err(GenError::InvalidResourceValue(line.name.clone()));
+    }
+    for item in line.format(options, source) {
+        writer.write_all(format!("  {}\n", item).as_bytes())?;
+    }
+    Ok(())
+}
+
+fn write_resources_end<W: Write>(writer: &mut W) -> Result<(), GenError> {
+    writer.write_all("</resources>\n".as_bytes())?;
+    Ok(())
+}
+
+/// Writes strings.xml of every language into `values-<lang>` subdirectories of `dir` while
+/// keys are produced, e.g. by `TwineParser`, so that only the current key of the input is
+/// kept in memory. strings.xml of `default_lang` is also written into `values` as by
+/// `GenResult::write`. Files are written to the file system directly since their content
+/// isn't known until the last key is read.
+pub fn write_streaming<I>(
+    keys: I,
+    dir: impl AsRef<Path>,
+    file_name: &str,
+    default_lang: &Option<String>,
+    options: &Options,
+) -> Result<(), GenError>
+where
+    I: IntoIterator<Item = Result<Key, ParseError>>,
+{
+    let dir = dir.as_ref();
+    let file_name = format!("{}.xml", file_name);
+    stream_resources(keys, default_lang, options, |dir_name| {
+        let dir = dir.join(dir_name);
+        fs::create_dir_all(&dir)?;
+        Ok(BufWriter::new(fs::File::create(dir.join(&file_name))?))
+    })?;
+    Ok(())
+}
+
+/// Streams lines of every key into writers opened by `open` with the name of the resource
+/// directory, e.g. `values-pt-rBR` or `values` for `default_lang`, when the directory is met
+/// for the first time. Returns flushed writers keyed by the directory name.
+pub(crate) fn stream_resources<I, W, F>(
+    keys: I,
+    default_lang: &Option<String>,
+    options: &Options,
+    mut open: F,
+) -> Result<HashMap<String, W>, GenError>
+where
+    I: IntoIterator<Item = Result<Key, ParseError>>,
+    W: Write,
+    F: FnMut(&str) -> io::Result<W>,
+{
+    let mut writers: HashMap<String, W> = HashMap::new();
     for key in keys {
-        let str_name = &key.name;
-        for str in &key.localizations {
-            let code = Locale {
-                value: str.language_code.clone(),
+        let lines = key_lines(&key?)?;
+        let source_line = |locale: &Locale| {
+            let source_lang = options.source_comments_lang.as_ref()?;
+            if source_lang == &locale.value {
+                return None;
+            }
+            lines
+                .iter()
+                .find(|(code, _)| &code.value == source_lang)
+                .map(|(_, line)| line)
+        };
+        for (locale, line) in &lines {
+            let Some(lang) = android_lang(&locale.value, options.min_sdk) else {
+                continue;
             };
+            let source = source_line(locale);
+            let dir_name = format!("values-{}", lang);
+            let writer = resources_writer(&mut writers, dir_name, options, &mut open)?;
+            // Non-translatable lines only go to `values` of the default language if it's known
+            if line.translatable || default_lang.is_none() {
+                write_line(line, source, options, writer)?;
+            }
+            if default_lang.as_ref() == Some(&locale.value) {
+                let dir_name = "values".to_string();
+                let writer = resources_writer(&mut writers, dir_name, options, &mut open)?;
+                write_line(line, source, options, writer)?;
+            }
+        }
+    }
+    for writer in writers.values_mut() {
+        write_resources_end(writer)?;
+        writer.flush()?;
+    }
+    Ok(writers)
+}
+
+/// Writer of the resource directory, opened and started if it's met for the first time
+fn resources_writer<'a, W, F>(
+    writers: &'a mut HashMap<String, W>,
+    dir_name: String,
+    options: &Options,
+    open: &mut F,
+) -> Result<&'a mut W, GenError>
+where
+    W: Write,
+    F: FnMut(&str) -> io::Result<W>,
+{
+    match writers.entry(dir_name) {
+        Entry::Occupied(entry) => Ok(entry.into_mut()),
+        Entry::Vacant(entry) => {
+            let mut writer = open(entry.key())?;
+            write_resources_start(options, &mut writer)?;
+            Ok(entry.insert(writer))
+        }
+    }
+}
+
+/// Language code used in `values-<lang>` directory names, e.g. `pt-rBR` for `pt-BR`
+/// or `b+pt+BR` since API 21, see `locale_to_dir_name`. `None` if Android doesn't support the code.
+fn android_lang(code: &str, min_sdk: u32) -> Option<String> {
+    let lang = locale_qualifier(code, min_sdk);
+    if !locale_code_supported_in_android(&lang) {
+        return None;
+    }
+    Some(lang)
+}
 
-            let current = &mut result
+fn locale_code_supported_in_android(code: &str) -> bool {
+    // https://stackoverflow.com/questions/17275697/is-there-any-need-to-prepare-values-zh-and-values-zh-rhk/17276279
+    return code != "zh-rHans" && code != "zh-rHant" && code != "zh-rPinyin";
+}
+
+pub fn generate(source: &File) -> Result<GenResult, GenError> {
+    if source.sections.is_empty() {
+        return Err(GenError::EmptyFile);
+    }
+
+    let keys_len = source.sections.iter().map(|section| section.keys.len()).sum();
+    let mut result: HashMap<Locale, StrLines> = HashMap::new();
+    for key in source.sections.iter().flat_map(|section| &section.keys) {
+        for (code, line) in key_lines(key)? {
+            result
                 .entry(code)
                 .or_insert(StrLines {
                     value: Vec::with_capacity(keys_len),
                 })
-                .value;
-
-            current.push(Line {
-                name: str_name.clone(),
-                value: str.value.clone(),
-            })
+                .value
+                .push(line);
         }
     }
 
     Ok(GenResult { value: result })
 }
 
-fn generate_str_value(str_name: &str, str_value: &str) -> String {
-    String::from(format!(
-        "<string name=\"{}\">{}</string>",
-        str_name, str_value
-    ))
+/// Lines of every localization of the key
+fn key_lines(key: &Key) -> Result<Vec<(Locale, Line)>, GenError> {
+    let str_name = &key.name;
+    if !key_name_supported_in_android(str_name) {
+        return Err(GenError::InvalidKeyName(str_name.clone()));
+    }
+    // Resource type must be the same for all languages, so single values
+    // of a plural key are emitted as plurals with the only "other" quantity
+    let is_plural_key = key
+        .localizations
+        .iter()
+        .any(|str| matches!(str.value, StringValue::Plural { .. }));
+    let mut result = Vec::with_capacity(key.localizations.len());
+    for str in &key.localizations {
+        if let StringValue::Plural { quantities } = &str.value {
+            if let Some(item) = quantities
+                .iter()
+                .find(|item| item.quantity_as_cldr_category().is_none())
+            {
+                return Err(GenError::UnsupportedPluralCategory(item.quantity.clone()));
+            }
+        }
+        let code = Locale {
+            value: str.language_code.clone(),
+        };
+        let value = match &str.value {
+            StringValue::Single(text) if is_plural_key => StringValue::Plural {
+                quantities: vec![PluralValue::new("other", text.clone())],
+            },
+            value => value.clone(),
+        };
+        result.push((
+            code,
+            Line {
+                name: str_name.clone(),
+                value,
+                translatable: key.translatable,
+            },
+        ));
+    }
+    Ok(result)
+}
+
+pub(crate) fn key_name_supported_in_android(name: &str) -> bool {
+    lazy_static! {
+        static ref RESOURCE_NAME_RE: Regex = Regex::new(r"^[A-Za-z_][A-Za-z0-9_.]*$").unwrap();
+    }
+    RESOURCE_NAME_RE.is_match(name)
+}
+
+/// Wraps every printf placeholder into `<xliff:g id="argN" example="...">`, N is the
+/// positional number if present or the order of the placeholder otherwise
+fn wrap_placeholders(str_name: &str, text: &str, options: &Options) -> String {
+    let examples = options.placeholder_examples.get(str_name);
+    let mut index = 0;
+    PLACEHOLDER_OR_PERCENT_RE
+        .replace_all(text, |caps: &Captures| {
+            let placeholder = &caps[0];
+            if placeholder == "%%" {
+                return placeholder.to_string();
+            }
+            index += 1;
+            let number = caps
+                .get(1)
+                .and_then(|position| position.as_str().trim_end_matches('$').parse().ok())
+                .unwrap_or(index);
+            let id = format!("arg{}", number);
+            let example = match examples.and_then(|examples| examples.get(&id)) {
+                Some(example) => escape(example.as_str()).to_string(),
+                None => match placeholder.chars().last() {
+                    Some('s' | '@') => "string".to_string(),
+                    Some('c') => "character".to_string(),
+                    _ => "number".to_string(),
+                },
+            };
+            format!(
+                "<xliff:g id=\"{}\" example=\"{}\">{}</xliff:g>",
+                id, example, placeholder
+            )
+        })
+        .into_owned()
+}
+
+fn has_placeholders(text: &str) -> bool {
+    PLACEHOLDER_OR_PERCENT_RE
+        .find_iter(text)
+        .any(|found| found.as_str() != "%%")
+}
+
+fn generate_value(tag: &str, str_name: &str, str_value: &str) -> String {
+    format!("<{tag} name=\"{}\">{}</{tag}>", str_name, str_value)
+}
+
+fn generate_r_reference_comment(
+    package_name: &str,
+    resource_type: ResourceType,
+    line: &Line,
+) -> String {
+    let resource_type = match line.value {
+        StringValue::Single(_) => resource_type.tag(),
+        StringValue::Plural { .. } => "plurals",
+        StringValue::Array(_) => "array",
+    };
+    // Dots in resource names are replaced with underscores in R class
+    format!(
+        "<!-- {}.R.{}.{} -->",
+        package_name,
+        resource_type,
+        line.name.replace('.', "_")
+    )
+}
+
+fn generate_source_comments(source_lang: &str, source: &StringValue) -> Vec<String> {
+    // "--" is not allowed inside of XML comments
+    let escape = |text: &str| text.replace("--", "- -");
+    match source {
+        StringValue::Single(text) => vec![format!("<!-- {}: {} -->", source_lang, escape(text))],
+        StringValue::Plural { quantities } => quantities
+            .iter()
+            .map(|item| {
+                format!(
+                    "<!-- {}:{}: {} -->",
+                    source_lang,
+                    item.quantity,
+                    escape(&item.text)
+                )
+            })
+            .collect(),
+        StringValue::Array(items) => items
+            .iter()
+            .map(|item| format!("<!-- {}: {} -->", source_lang, escape(item)))
+            .collect(),
+    }
 }
 
 fn generate_plural_value(str_name: &String, items: &Vec<PluralValue>) -> Vec<String> {
     let mut result: Vec<String> = Vec::with_capacity(items.len() + 2);
     result.push(format!("<plurals name=\"{}\">", str_name));
 
+    // Items are emitted in CLDR order, unknown quantities go last keeping their source order
+    let mut items: Vec<&PluralValue> = items.iter().collect();
+    items.sort_by_key(|item| {
+        let category = item.quantity_as_cldr_category();
+        (category.is_none(), category)
+    });
     for item in items {
         result.push(format!(
             "  <item quantity=\"{}\">{}</item>",
@@ -153,57 +611,44 @@ fn generate_plural_value(str_name: &String, items: &Vec<PluralValue>) -> Vec<Str
     result
 }
 
-// -----------------------------  test tools ------------------------------
-fn plain_str(lang: &str, txt: &str) -> LocalizedString {
-    LocalizedString {
-        language_code: lang.to_string(),
-        value: StringValue::Single(txt.to_string()),
-    }
-}
-
-fn plurals(lang: &str, quantities: Vec<PluralValue>) -> LocalizedString {
-    LocalizedString {
-        language_code: lang.to_string(),
-        value: StringValue::Plural { quantities },
-    }
-}
-
-fn plural_val(quantity: &str, text: &str) -> PluralValue {
-    PluralValue {
-        quantity: quantity.to_string(),
-        text: text.to_string(),
-    }
-}
-
-fn key(name: &str, localizations: Vec<LocalizedString>) -> Key {
-    Key {
-        name: name.to_string(),
-        localizations: localizations,
+fn generate_array_value(str_name: &str, items: &[String]) -> Vec<String> {
+    let mut result: Vec<String> = Vec::with_capacity(items.len() + 2);
+    result.push(format!("<string-array name=\"{}\">", str_name));
+    for item in items {
+        result.push(format!("  <item>{}</item>", item));
     }
+    result.push("</string-array>".to_string());
+    result
 }
 
+// -----------------------------  test tools ------------------------------
+#[cfg(test)]
 fn sorted_strings(input: GenResult) -> Vec<(Locale, StrLines)> {
     let mut result = Vec::with_capacity(input.value.len());
     let mut keys: Vec<&Locale> = input.value.keys().collect();
     keys.sort();
     for key in keys {
-        result.push((key.clone(), input.value.get(&key).unwrap().clone()))
+        result.push((key.clone(), input.value.get(key).unwrap().clone()))
     }
     result
 }
 
+#[cfg(test)]
 fn single(name: &str, text: &str) -> Line {
-    return Line {
+    Line {
         name: name.to_string(),
         value: StringValue::Single(text.to_string()),
-    };
+        translatable: true,
+    }
 }
 
+#[cfg(test)]
 fn plural(name: &str, items: Vec<PluralValue>) -> Line {
-    return Line {
+    Line {
         name: name.to_string(),
         value: StringValue::Plural { quantities: items },
-    };
+        translatable: true,
+    }
 }
 
 // ------------------------------- tests -----------------------------------
@@ -270,10 +715,16 @@ fn generate_3_lang_2_str() -> Result<()> {
         Key {
             name: "find".to_string(),
             localizations: localizations_find,
+            max_length: None,
+            comment: None,
+            translatable: true,
         },
         Key {
             name: "search".to_string(),
             localizations: localizations_search,
+            max_length: None,
+            comment: None,
+            translatable: true,
         },
     ];
     let source = File {
@@ -351,12 +802,42 @@ fn generate_error_if_empty_sections() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn generate_keys_of_all_sections() -> Result<()> {
+    let source = File {
+        sections: vec![
+            Section {
+                keys: vec![key("login", vec![plain_str("en", "Login")])],
+            },
+            Section {
+                keys: vec![key("logout", vec![plain_str("en", "Logout")])],
+            },
+        ],
+    };
+
+    let actual = sorted_strings(generate(&source)?);
+    let expected = vec![(
+        Locale {
+            value: "en".to_string(),
+        },
+        StrLines {
+            value: vec![single("login", "Login"), single("logout", "Logout")],
+        },
+    )];
+    assert_eq!(actual, expected);
+
+    Ok(())
+}
+
 #[test]
 fn generate_1_lang_1_simple_plural() -> Result<()> {
-    let localizations_songs = vec![plurals("mn", vec![plural_val("other", "%d дуу")])];
+    let localizations_songs = vec![plurals("mn", vec![PluralValue::new("other", "%d дуу")])];
     let keys = vec![Key {
         name: "songs".to_string(),
         localizations: localizations_songs,
+        max_length: None,
+        comment: None,
+        translatable: true,
     }];
     let source = File {
         sections: vec![Section { keys }],
@@ -387,19 +868,25 @@ fn generate_1_lang_1_str_1_plurals() -> Result<()> {
     let localizations_cows = vec![plurals(
         "en",
         vec![
-            plural_val("one", "%d cow"),
-            plural_val("two", "%d cows"),
-            plural_val("other", "33 copy-on-writes"),
+            PluralValue::new("one", "%d cow"),
+            PluralValue::new("two", "%d cows"),
+            PluralValue::new("other", "33 copy-on-writes"),
         ],
     )];
     let keys = vec![
         Key {
             name: "chicken".to_string(),
             localizations: localizations_chicken,
+            max_length: None,
+            comment: None,
+            translatable: true,
         },
         Key {
             name: "cows".to_string(),
             localizations: localizations_cows,
+            max_length: None,
+            comment: None,
+            translatable: true,
         },
     ];
     let source = File {
@@ -439,3 +926,389 @@ fn generate_1_lang_1_str_1_plurals() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn generate_xml_to_string_for_1_lang() -> Result<()> {
+    let localizations_chicken = vec![plain_str("en", "Chicken"), plain_str("ru", "Курица")];
+    let localizations_cows = vec![plurals(
+        "en",
+        vec![PluralValue::new("other", "%d cows"), PluralValue::new("one", "%d cow")],
+    )];
+    let keys = vec![
+        key("chicken", localizations_chicken),
+        key("cows", localizations_cows),
+    ];
+    let source = File {
+        sections: vec![Section { keys }],
+    };
+
+    let actual = generate_android_xml_to_string(&source, "en")?;
+    let expected = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+        \n\
+        <resources>\n\
+        \x20 <string name=\"chicken\">Chicken</string>\n\
+        \x20 <plurals name=\"cows\">\n\
+        \x20   <item quantity=\"one\">%d cow</item>\n\
+        \x20   <item quantity=\"other\">%d cows</item>\n\
+        \x20 </plurals>\n\
+        </resources>\n";
+    assert_eq!(expected, actual);
+
+    Ok(())
+}
+
+#[test]
+fn generate_xml_to_string_error_if_no_such_lang() -> Result<()> {
+    let keys = vec![key("chicken", vec![plain_str("en", "Chicken")])];
+    let source = File {
+        sections: vec![Section { keys }],
+    };
+
+    let actual = generate_android_xml_to_string(&source, "mn");
+    assert!(actual.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn generate_error_if_invalid_key_name() -> Result<()> {
+    let keys = vec![key("chicken-wings", vec![plain_str("en", "Chicken wings")])];
+    let source = File {
+        sections: vec![Section { keys }],
+    };
+
+    let actual = generate(&source);
+    assert!(matches!(actual, Err(GenError::InvalidKeyName(name)) if name == "chicken-wings"));
+
+    Ok(())
+}
+
+#[test]
+fn generate_error_if_unsupported_plural_category() -> Result<()> {
+    let localizations_cows = vec![plurals(
+        "en",
+        vec![PluralValue::new("one", "%d cow"), PluralValue::new("herd", "%d cows")],
+    )];
+    let keys = vec![key("cows", localizations_cows)];
+    let source = File {
+        sections: vec![Section { keys }],
+    };
+
+    let actual = generate(&source);
+    assert!(matches!(actual, Err(GenError::UnsupportedPluralCategory(quantity)) if quantity == "herd"));
+
+    Ok(())
+}
+
+#[test]
+fn write_r_reference_comments_if_package_name_is_set() -> Result<()> {
+    let lines = StrLines {
+        value: vec![
+            single("login_button", "Login"),
+            plural("days", vec![PluralValue::new("other", "%d days")]),
+        ],
+    };
+    let options = Options {
+        package_name: Some("com.example.app".to_string()),
+        ..Options::default()
+    };
+    let mut buffer: Vec<u8> = Vec::new();
+    write_resources(&lines, None, &options, &mut buffer)?;
+
+    let expected = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+        \n\
+        <resources>\n\
+        \x20 <!-- com.example.app.R.string.login_button -->\n\
+        \x20 <string name=\"login_button\">Login</string>\n\
+        \x20 <!-- com.example.app.R.plurals.days -->\n\
+        \x20 <plurals name=\"days\">\n\
+        \x20   <item quantity=\"other\">%d days</item>\n\
+        \x20 </plurals>\n\
+        </resources>\n";
+    assert_eq!(expected, String::from_utf8(buffer)?);
+
+    Ok(())
+}
+
+#[test]
+fn write_colors_and_error_if_invalid_color() -> Result<()> {
+    let lines = StrLines {
+        value: vec![single("primary", "#FF0000"), single("overlay", "#80000000")],
+    };
+    let options = Options {
+        package_name: Some("com.example.app".to_string()),
+        resource_type: ResourceType::Colors,
+        ..Options::default()
+    };
+    let mut buffer: Vec<u8> = Vec::new();
+    write_resources(&lines, None, &options, &mut buffer)?;
+
+    let expected = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+        \n\
+        <resources>\n\
+        \x20 <!-- com.example.app.R.color.primary -->\n\
+        \x20 <color name=\"primary\">#FF0000</color>\n\
+        \x20 <!-- com.example.app.R.color.overlay -->\n\
+        \x20 <color name=\"overlay\">#80000000</color>\n\
+        </resources>\n";
+    assert_eq!(expected, String::from_utf8(buffer)?);
+
+    for invalid in [
+        single("primary", "red"),
+        plural("days", vec![PluralValue::new("other", "#FF0000")]),
+    ] {
+        let lines = StrLines {
+            value: vec![invalid],
+        };
+        let mut buffer: Vec<u8> = Vec::new();
+        assert!(matches!(
+            write_resources(&lines, None, &options, &mut buffer),
+            Err(GenError::InvalidResourceValue(_))
+        ));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn write_dimens() -> Result<()> {
+    let lines = StrLines {
+        value: vec![single("margin", "16dp"), single("title_size", "20.5sp")],
+    };
+    let options = Options {
+        resource_type: ResourceType::Dimens,
+        ..Options::default()
+    };
+    let mut buffer: Vec<u8> = Vec::new();
+    write_resources(&lines, None, &options, &mut buffer)?;
+
+    let expected = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+        \n\
+        <resources>\n\
+        \x20 <dimen name=\"margin\">16dp</dimen>\n\
+        \x20 <dimen name=\"title_size\">20.5sp</dimen>\n\
+        </resources>\n";
+    assert_eq!(expected, String::from_utf8(buffer)?);
+
+    let lines = StrLines {
+        value: vec![single("margin", "16")],
+    };
+    assert!(write_resources(&lines, None, &options, &mut Vec::new()).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn write_source_comments_if_source_lang_is_set() -> Result<()> {
+    let source = StrLines {
+        value: vec![
+            single("login_button", "Login -- now"),
+            plural(
+                "days",
+                vec![PluralValue::new("one", "%d day"), PluralValue::new("other", "%d days")],
+            ),
+        ],
+    };
+    let lines = StrLines {
+        value: vec![
+            single("login_button", "Войти"),
+            plural("days", vec![PluralValue::new("other", "%d дней")]),
+            single("untranslated", "Нет в исходном"),
+        ],
+    };
+    let options = Options {
+        source_comments_lang: Some("en".to_string()),
+        ..Options::default()
+    };
+    let mut buffer: Vec<u8> = Vec::new();
+    write_resources(&lines, Some(&source), &options, &mut buffer)?;
+
+    let expected = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+        \n\
+        <resources>\n\
+        \x20 <!-- en: Login - - now -->\n\
+        \x20 <string name=\"login_button\">Войти</string>\n\
+        \x20 <!-- en:one: %d day -->\n\
+        \x20 <!-- en:other: %d days -->\n\
+        \x20 <plurals name=\"days\">\n\
+        \x20   <item quantity=\"other\">%d дней</item>\n\
+        \x20 </plurals>\n\
+        \x20 <string name=\"untranslated\">Нет в исходном</string>\n\
+        </resources>\n";
+    assert_eq!(expected, String::from_utf8(buffer)?);
+
+    Ok(())
+}
+
+#[test]
+fn generate_xml_map_for_all_langs() -> Result<()> {
+    let source = File {
+        sections: vec![Section {
+            keys: vec![key(
+                "kek",
+                vec![plain_str("ru", "Кек"), plain_str("en", "Kek")],
+            )],
+        }],
+    };
+
+    let actual = source.to_android_xml_map()?;
+    assert_eq!(actual.len(), 2);
+    assert_eq!(actual["en"], generate_android_xml_to_string(&source, "en")?);
+    assert!(actual["ru"].contains("<string name=\"kek\">Кек</string>"));
+
+    Ok(())
+}
+
+#[test]
+fn stream_the_same_resources_as_batch_generation() -> Result<()> {
+    let keys = || {
+        vec![
+            key("kek", vec![plain_str("ru", "Кек"), plain_str("en", "Kek")]),
+            key(
+                "days",
+                vec![
+                    plurals(
+                        "en",
+                        vec![PluralValue::new("other", "%d days"), PluralValue::new("one", "%d day")],
+                    ),
+                    plain_str("pt-BR", "%d dias"),
+                ],
+            ),
+        ]
+    };
+    let source = File {
+        sections: vec![Section { keys: keys() }],
+    };
+
+    let actual = stream_resources(
+        keys().into_iter().map(Ok),
+        &None,
+        &Options::default(),
+        |_| Ok(Vec::new()),
+    )?;
+
+    let expected = source.to_android_xml_map()?;
+    assert_eq!(actual.len(), 3);
+    assert_eq!(String::from_utf8(actual["values-en"].clone())?, expected["en"]);
+    assert_eq!(String::from_utf8(actual["values-ru"].clone())?, expected["ru"]);
+    assert_eq!(
+        String::from_utf8(actual["values-pt-rBR"].clone())?,
+        expected["pt-BR"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn write_streaming_the_same_files_as_batch_generation() -> Result<()> {
+    let keys = || {
+        let mut internal = key(
+            "internal",
+            vec![plain_str("en", "Debug"), plain_str("ru", "Отладка")],
+        );
+        internal.translatable = false;
+        vec![
+            key(
+                "greeting",
+                vec![plain_str("en", "Hi %1$s"), plain_str("ru", "Привет %1$s")],
+            ),
+            key("title", vec![plain_str("en", "Title"), plain_str("zh-Hant", "標題")]),
+            internal,
+        ]
+    };
+    let source = File {
+        sections: vec![Section { keys: keys() }],
+    };
+    let options = Options {
+        source_comments_lang: Some("en".to_string()),
+        xliff_placeholders: true,
+        min_sdk: 21,
+        formatted_attr: true,
+        ..Options::default()
+    };
+    let default_lang = Some("en".to_string());
+    let batch_dir = tempfile::tempdir()?;
+    let streaming_dir = tempfile::tempdir()?;
+
+    generate(&source)?.write(
+        batch_dir.path(),
+        "strings",
+        &default_lang,
+        &options,
+        &mut crate::sink::FsSink::default(),
+    )?;
+    write_streaming(
+        keys().into_iter().map(Ok),
+        streaming_dir.path(),
+        "strings",
+        &default_lang,
+        &options,
+    )?;
+
+    for dir_name in ["values", "values-en", "values-ru", "values-b+zh+Hant"] {
+        let path = |dir: &Path| dir.join(dir_name).join("strings.xml");
+        assert_eq!(
+            std::fs::read_to_string(path(streaming_dir.path()))?,
+            std::fs::read_to_string(path(batch_dir.path()))?,
+            "{}",
+            dir_name
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn write_xliff_placeholders_if_enabled() -> Result<()> {
+    let lines = StrLines {
+        value: vec![
+            single("greeting", "Hi %1$s, you have %2$d coins, 100%%"),
+            plural("days", vec![PluralValue::new("other", "%d days")]),
+        ],
+    };
+    let options = Options {
+        xliff_placeholders: true,
+        placeholder_examples: HashMap::from([(
+            "greeting".to_string(),
+            HashMap::from([("arg1".to_string(), "Bob & Alice".to_string())]),
+        )]),
+        ..Options::default()
+    };
+    let mut buffer: Vec<u8> = Vec::new();
+    write_resources(&lines, None, &options, &mut buffer)?;
+
+    let expected = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+        \n\
+        <resources xmlns:xliff=\"urn:oasis:names:tc:xliff:document:1.2\">\n\
+        \x20 <string name=\"greeting\">Hi <xliff:g id=\"arg1\" example=\"Bob &amp; Alice\">%1$s</xliff:g>, \
+        you have <xliff:g id=\"arg2\" example=\"number\">%2$d</xliff:g> coins, 100%%</string>\n\
+        \x20 <plurals name=\"days\">\n\
+        \x20   <item quantity=\"other\"><xliff:g id=\"arg1\" example=\"number\">%d</xliff:g> days</item>\n\
+        \x20 </plurals>\n\
+        </resources>\n";
+    assert_eq!(expected, String::from_utf8(buffer)?);
+
+    Ok(())
+}
+
+#[test]
+fn write_formatted_false_for_strings_without_placeholders() -> Result<()> {
+    let lines = StrLines {
+        value: vec![
+            single("title", "Done for 100%%"),
+            single("progress", "Done for %d%%"),
+        ],
+    };
+    let options = Options {
+        formatted_attr: true,
+        ..Options::default()
+    };
+    let mut buffer: Vec<u8> = Vec::new();
+    write_resources(&lines, None, &options, &mut buffer)?;
+
+    let actual = String::from_utf8(buffer)?;
+    assert!(actual.contains("<string name=\"title\" formatted=\"false\">Done for 100%%</string>"));
+    assert!(actual.contains("<string name=\"progress\">Done for %d%%</string>"));
+
+    Ok(())
+}
@@ -0,0 +1,306 @@
+use anyhow::{anyhow, Result};
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use regex::{Captures, Regex};
+use std::{borrow::Cow, fs, path::Path};
+
+use crate::fs_util::read_to_string_with_encoding;
+use crate::import::{to_twine_content, ImportedKey, ImportedValue};
+use crate::locale::dir_name_to_locale;
+
+/// Resource of strings.xml of a single language
+#[derive(PartialEq, Eq, Debug)]
+struct Resource {
+    name: String,
+    translatable: bool,
+    value: ImportedValue,
+}
+
+/// Reads `strings.xml` files of `values` and `values-XX` directories of Android `res_dir`
+/// and generates twine file content of their `<string>` and `<plurals>` resources.
+/// Values of `values` directory get `default_lang` code, keys which some languages don't
+/// have get empty entries of those languages. Reverts the escaping and placeholder
+/// conversions done by the Android generator.
+pub fn import_android(res_dir: &Path, default_lang: &str) -> Result<String> {
+    let mut dirs: Vec<(String, String)> = Vec::new();
+    for entry in fs::read_dir(res_dir)? {
+        let entry = entry?;
+        let dir_name = entry.file_name().to_string_lossy().to_string();
+        let lang = match dir_name.as_str() {
+            "values" => Some(default_lang.to_string()),
+            dir_name => dir_name_to_locale(dir_name),
+        };
+        if let Some(lang) = lang {
+            if entry.path().join("strings.xml").is_file() {
+                dirs.push((dir_name, lang));
+            }
+        }
+    }
+    // Keys of the default language go first, the generator writes its values into
+    // both `values` and `values-<default_lang>`
+    dirs.sort_by(|(left, _), (right, _)| (left != "values", left).cmp(&(right != "values", right)));
+    let mut languages: Vec<String> = Vec::new();
+    dirs.retain(|(_, lang)| {
+        if languages.contains(lang) {
+            return false;
+        }
+        languages.push(lang.clone());
+        true
+    });
+    if dirs.is_empty() {
+        return Err(anyhow!(
+            "No values directories with strings.xml found in {}",
+            res_dir.display()
+        ));
+    }
+
+    let mut keys: IndexMap<String, ImportedKey> = IndexMap::new();
+    for (dir_name, lang) in &dirs {
+        let path = res_dir.join(dir_name).join("strings.xml");
+        let (content, _) = read_to_string_with_encoding(&path, None)?;
+        let resources = parse_strings_xml(&content)
+            .map_err(|err| anyhow!("Cannot import {}: {}", path.display(), err))?;
+        for resource in resources {
+            let key = keys.entry(resource.name).or_default();
+            key.translatable &= resource.translatable;
+            key.values.insert(lang.clone(), resource.value);
+        }
+    }
+
+    Ok(to_twine_content(&keys, &languages))
+}
+
+/// `<string>` and `<plurals>` resources of strings.xml content in file order
+fn parse_strings_xml(xml: &str) -> Result<Vec<Resource>> {
+    let mut result: Vec<Resource> = Vec::new();
+    let mut reader = Reader::from_str(xml);
+    // Resource which value is being read with the quantity of the current plural item
+    let mut current: Option<(Resource, Option<String>)> = None;
+    let mut text: Option<String> = None;
+    loop {
+        let event = reader.read_event().map_err(|err| {
+            anyhow!(
+                "invalid XML at position {}: {}",
+                reader.error_position(),
+                err
+            )
+        })?;
+        match event {
+            Event::Start(element) if text.is_some() => {
+                push_tag(text.as_mut().unwrap(), &element, "")
+            }
+            Event::Empty(element) if text.is_some() => {
+                push_tag(text.as_mut().unwrap(), &element, "/")
+            }
+            Event::End(element) if text.is_some() && !is_value_end(element.name().as_ref()) => {
+                let name = String::from_utf8_lossy(element.name().as_ref()).to_string();
+                if name != "xliff:g" {
+                    text.as_mut().unwrap().push_str(&format!("</{}>", name));
+                }
+            }
+            Event::Start(element) => match element.name().as_ref() {
+                tag @ (b"string" | b"plurals") => {
+                    let value = match tag {
+                        b"string" => ImportedValue::Single(String::new()),
+                        _ => ImportedValue::Plural(Vec::new()),
+                    };
+                    let resource = Resource {
+                        name: attribute(&element, b"name")
+                            .ok_or(anyhow!("resource without name"))?,
+                        translatable: attribute(&element, b"translatable").as_deref()
+                            != Some("false"),
+                        value,
+                    };
+                    if tag == b"string" {
+                        text = Some(String::new());
+                    }
+                    current = Some((resource, None));
+                }
+                b"item" => {
+                    if let Some((_, quantity)) = &mut current {
+                        *quantity = attribute(&element, b"quantity");
+                        text = Some(String::new());
+                    }
+                }
+                _ => {}
+            },
+            Event::Text(value) => {
+                if let Some(text) = &mut text {
+                    text.push_str(&escape_tag_like_text(&value.unescape()?));
+                }
+            }
+            Event::CData(value) => {
+                if let Some(text) = &mut text {
+                    text.push_str(&String::from_utf8_lossy(&value));
+                }
+            }
+            Event::End(element) => match element.name().as_ref() {
+                b"string" => {
+                    if let Some((mut resource, _)) = current.take() {
+                        resource.value =
+                            ImportedValue::Single(to_twine_text(&text.take().unwrap_or_default()));
+                        result.push(resource);
+                    }
+                }
+                b"item" => {
+                    if let Some((resource, quantity)) = &mut current {
+                        let value = to_twine_text(&text.take().unwrap_or_default());
+                        if let (ImportedValue::Plural(items), Some(quantity)) =
+                            (&mut resource.value, quantity.take())
+                        {
+                            items.push((quantity, value));
+                        }
+                    }
+                }
+                b"plurals" => {
+                    if let Some((resource, _)) = current.take() {
+                        result.push(resource);
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => return Ok(result),
+            _ => {}
+        }
+    }
+}
+
+fn is_value_end(name: &[u8]) -> bool {
+    matches!(name, b"string" | b"item")
+}
+
+/// Pushes HTML tag of a value as is, `<xliff:g>` tags are dropped keeping their content
+fn push_tag(text: &mut String, element: &BytesStart, end: &str) {
+    if element.name().as_ref() != b"xliff:g" {
+        text.push_str(&format!("<{}{}>", String::from_utf8_lossy(element), end));
+    }
+}
+
+/// Keeps `<` of the text escaped where it would be read as an HTML tag in twine file
+fn escape_tag_like_text(text: &str) -> Cow<'_, str> {
+    lazy_static! {
+        static ref TAG_START_RE: Regex = Regex::new(r"<([A-Za-z/])").unwrap();
+    }
+    TAG_START_RE.replace_all(text, "&lt;$1")
+}
+
+/// Reverts Android escaping and quoting of the value, collapses line breaks of the XML
+/// formatting and replaces `%s` placeholders with twine `%@`
+fn to_twine_text(value: &str) -> String {
+    lazy_static! {
+        static ref LINE_BREAK_RE: Regex = Regex::new(r"\s*\n\s*").unwrap();
+        static ref STRING_PLACEHOLDER_RE: Regex =
+            Regex::new(r"%%|%(\d+\$)?([-+0#,]?\d*(\.\d+)?)s").unwrap();
+    }
+    let value = LINE_BREAK_RE.replace_all(value.trim(), " ");
+    let value = match value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    {
+        Some(quoted) if !quoted.ends_with('\\') => quoted,
+        _ => &value,
+    };
+    let value = value
+        .replace("\\'", "'")
+        .replace("\\\"", "\"")
+        .replace("\\@", "@")
+        .replace("\\?", "?");
+    STRING_PLACEHOLDER_RE
+        .replace_all(&value, |caps: &Captures| match caps.get(2) {
+            Some(_) => format!(
+                "%{}{}@",
+                caps.get(1).map_or("", |position| position.as_str()),
+                &caps[2]
+            ),
+            None => caps[0].to_string(),
+        })
+        .into_owned()
+}
+
+fn attribute(element: &BytesStart, name: &[u8]) -> Option<String> {
+    element
+        .try_get_attribute(name)
+        .ok()
+        .flatten()
+        .and_then(|attribute| attribute.unescape_value().ok())
+        .map(|value| value.to_string())
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn imports_android_resources() -> Result<()> {
+    let res = tempfile::tempdir()?;
+    let write = |dir: &str, resources: &str| -> Result<()> {
+        fs::create_dir_all(res.path().join(dir))?;
+        let xml = format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<resources>\n{}</resources>\n",
+            resources
+        );
+        Ok(fs::write(res.path().join(dir).join("strings.xml"), xml)?)
+    };
+    write(
+        "values",
+        r#"  <string name="greeting" formatted="false">Hi <xliff:g id="arg1" example="string">%1$s</xliff:g>, it\'s &lt;b&gt; &amp; <b>bold</b></string>
+  <string name="app_id" translatable="false">com.example</string>
+  <plurals name="days">
+    <item quantity="one">%d day</item>
+    <item quantity="other">%d days</item>
+  </plurals>
+"#,
+    )?;
+    write(
+        "values-pt-rBR",
+        r#"  <plurals name="days">
+    <item quantity="other">%d dias</item>
+  </plurals>
+"#,
+    )?;
+    write(
+        "values-en",
+        r#"  <string name="greeting">Hi</string>
+"#,
+    )?;
+    write("values-night", "")?;
+
+    let imported = import_android(res.path(), "en")?;
+    assert_eq!(
+        imported,
+        "[[Strings]]
+  [greeting]
+    en = Hi %1$@, it's &lt;b> & <b>bold</b>
+    pt-BR =
+  [app_id]
+    en = com.example
+    __translatable = false
+  [days]
+    en:one = %d day
+    en:other = %d days
+    pt-BR:other = %d dias
+"
+    );
+    assert!(crate::parse::parse_str(&imported).is_ok());
+
+    let utf16: Vec<u8> = "\u{feff}<resources><string name=\"greeting\">Hallo</string></resources>"
+        .encode_utf16()
+        .flat_map(|unit| unit.to_le_bytes())
+        .collect();
+    fs::create_dir_all(res.path().join("values-de"))?;
+    fs::write(res.path().join("values-de/strings.xml"), utf16)?;
+    assert!(import_android(res.path(), "en")?.contains("    de = Hallo\n"));
+    Ok(())
+}
+
+#[test]
+fn converts_android_text_to_twine() {
+    assert_eq!(
+        to_twine_text("\"  quoted \\\"text\\\" \""),
+        "  quoted \"text\" "
+    );
+    assert_eq!(
+        to_twine_text("100%% of %s and %2$.1s"),
+        "100%% of %@ and %2$.1@"
+    );
+    assert_eq!(to_twine_text("\\@home\n    line"), "@home line");
+}
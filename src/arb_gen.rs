@@ -0,0 +1,199 @@
+use indexmap::IndexMap;
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+use crate::gen_error::GenError;
+use crate::parse::{File, Key, StringValue};
+#[cfg(test)]
+use crate::parse::Section;
+use crate::platform::{self, Platform};
+use crate::sink::WriteSink;
+#[cfg(test)]
+use crate::test_tools::key;
+
+/// Writes `intl_<lang>.arb` of every language into `output_dir`. Every key gets `@<key>`
+/// metadata with the comment of the key as description and types of its placeholders.
+pub fn write_arb(file: &File, output_dir: &Path, sink: &mut dyn WriteSink) -> Result<(), GenError> {
+    for (lang, bundle) in generate(file)? {
+        let path = output_dir.join(format!("intl_{}.arb", lang));
+        // Serializing of serde_json::Value can't fail
+        let content = serde_json::to_string_pretty(&bundle).unwrap() + "\n";
+        sink.write(&path, content.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Generates ARB bundles keyed by language code in order of their first appearance
+fn generate(file: &File) -> Result<IndexMap<String, Value>, GenError> {
+    if file.sections.is_empty() {
+        return Err(GenError::EmptyFile);
+    }
+
+    let mut result: IndexMap<String, Map<String, Value>> = IndexMap::new();
+    for key in file.sections.iter().flat_map(|section| &section.keys) {
+        for str in &key.localizations {
+            if let StringValue::Plural { quantities } = &str.value {
+                if let Some(item) = quantities
+                    .iter()
+                    .find(|item| item.quantity_as_cldr_category().is_none())
+                {
+                    return Err(GenError::UnsupportedPluralCategory(item.quantity.clone()));
+                }
+            }
+            let bundle = result.entry(str.language_code.clone()).or_insert_with(|| {
+                Map::from_iter([("@@locale".to_string(), json!(str.language_code))])
+            });
+            bundle.insert(
+                key.name.clone(),
+                json!(str.value.to_platform_format(Platform::Flutter)),
+            );
+            bundle.insert(format!("@{}", key.name), generate_metadata(key, &str.value));
+        }
+    }
+
+    Ok(result
+        .into_iter()
+        .map(|(lang, bundle)| (lang, Value::Object(bundle)))
+        .collect())
+}
+
+fn generate_metadata(key: &Key, value: &StringValue) -> Value {
+    let mut placeholders = Map::new();
+    let texts: Vec<String> = match value {
+        StringValue::Single(text) => vec![text.clone()],
+        StringValue::Array(items) => items.clone(),
+        StringValue::Plural { quantities } => {
+            placeholders.insert("count".to_string(), json!({ "type": "num" }));
+            // The count is `{count}` of the message, not an `{argN}` placeholder
+            quantities
+                .iter()
+                .map(|item| platform::plural_form_text(&item.text, Platform::Flutter))
+                .collect()
+        }
+    };
+    for text in &texts {
+        // Numbered the same way as `{argN}` placeholders of the message
+        for (index, caps) in platform::placeholders(text).enumerate() {
+            let number = caps
+                .get(1)
+                .and_then(|position| position.as_str().trim_end_matches('$').parse().ok())
+                .unwrap_or(index + 1);
+            placeholders
+                .entry(format!("arg{}", number))
+                .or_insert_with(|| json!({ "type": placeholder_type(&caps[7]) }));
+        }
+    }
+    json!({
+        "description": key.comment.clone().unwrap_or_default(),
+        "placeholders": placeholders,
+    })
+}
+
+/// Dart type of the printf conversion
+fn placeholder_type(conversion: &str) -> &'static str {
+    match conversion {
+        "d" | "i" | "u" | "x" | "X" | "o" => "int",
+        "f" | "F" | "e" | "E" | "g" | "G" | "a" | "A" => "double",
+        _ => "String",
+    }
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn generate_arb_with_metadata() -> Result<(), GenError> {
+    let single = |text: &str| StringValue::Single(text.to_string());
+    let mut greeting = key(
+        "greeting",
+        vec![
+            ("en", single("Hello, %1$s! You have %2$d new &amp; unread\\n\\\\")),
+            ("de", single("Hallo, %1$s!")),
+        ],
+    );
+    greeting.comment = Some("Home screen title".to_string());
+    let keys = vec![
+        greeting,
+        key(
+            "days",
+            vec![(
+                "en",
+                StringValue::plural()
+                    .quantity("other", "%d days")
+                    .quantity("one", "%d day")
+                    .build(),
+            )],
+        ),
+        key(
+            "files",
+            vec![(
+                "en",
+                StringValue::plural()
+                    .quantity("one", "%1$d file of %2$s")
+                    .quantity("other", "%1$d files of %2$s")
+                    .build(),
+            )],
+        ),
+    ];
+    let source = File {
+        sections: vec![Section { keys }],
+    };
+
+    let actual = generate(&source)?;
+
+    assert_eq!(actual.keys().collect::<Vec<_>>(), vec!["en", "de"]);
+    let expected = json!({
+        "@@locale": "en",
+        "greeting": "Hello, {arg1}! You have {arg2} new & unread\n\\",
+        "@greeting": {
+            "description": "Home screen title",
+            "placeholders": {
+                "arg1": { "type": "String" },
+                "arg2": { "type": "int" },
+            },
+        },
+        "days": "{count, plural, one {{count} day} other {{count} days}}",
+        "@days": {
+            "description": "",
+            "placeholders": {
+                "count": { "type": "num" },
+            },
+        },
+        "files": "{count, plural, one {{count} file of {arg2}} other {{count} files of {arg2}}}",
+        "@files": {
+            "description": "",
+            "placeholders": {
+                "count": { "type": "num" },
+                "arg2": { "type": "String" },
+            },
+        },
+    });
+    assert_eq!(actual["en"], expected);
+    assert_eq!(actual["de"]["greeting"], "Hallo, {arg1}!");
+
+    Ok(())
+}
+
+#[test]
+fn write_arb_file_per_lang() -> Result<(), GenError> {
+    let keys = vec![key(
+        "chicken",
+        vec![
+            ("en", StringValue::Single("Chicken".to_string())),
+            ("ru", StringValue::Single("Курица".to_string())),
+        ],
+    )];
+    let source = File {
+        sections: vec![Section { keys }],
+    };
+    let dir = tempfile::tempdir()?;
+
+    write_arb(&source, dir.path(), &mut crate::sink::FsSink::default())?;
+
+    let actual = std::fs::read_to_string(dir.path().join("intl_ru.arb"))?;
+    assert_eq!(
+        actual,
+        "{\n  \"@@locale\": \"ru\",\n  \"chicken\": \"Курица\",\n  \"@chicken\": {\n    \"description\": \"\",\n    \"placeholders\": {}\n  }\n}\n"
+    );
+    assert!(dir.path().join("intl_en.arb").is_file());
+
+    Ok(())
+}
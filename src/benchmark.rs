@@ -0,0 +1,136 @@
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::{fmt, fs, io, path::Path, time::Instant};
+
+use crate::{android_gen, parse};
+
+/// Timings of repeated parse and generate cycles of a single file
+pub struct BenchmarkReport {
+    pub iterations: usize,
+    pub keys: usize,
+    pub mean_ms: f64,
+    pub median_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    /// Peak resident memory of the process, only available on Linux
+    pub peak_memory_kb: Option<u64>,
+}
+
+impl BenchmarkReport {
+    pub fn keys_per_second(&self) -> f64 {
+        if self.mean_ms == 0.0 {
+            return 0.0;
+        }
+        self.keys as f64 * 1000.0 / self.mean_ms
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "iterations": self.iterations,
+            "keys": self.keys,
+            "mean_ms": self.mean_ms,
+            "median_ms": self.median_ms,
+            "p95_ms": self.p95_ms,
+            "p99_ms": self.p99_ms,
+            "keys_per_second": self.keys_per_second(),
+            "peak_memory_kb": self.peak_memory_kb,
+        })
+    }
+}
+
+impl fmt::Display for BenchmarkReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<16} {:>12}", "iterations", self.iterations)?;
+        writeln!(f, "{:<16} {:>12}", "keys", self.keys)?;
+        writeln!(f, "{:<16} {:>12.3}", "mean, ms", self.mean_ms)?;
+        writeln!(f, "{:<16} {:>12.3}", "median, ms", self.median_ms)?;
+        writeln!(f, "{:<16} {:>12.3}", "p95, ms", self.p95_ms)?;
+        writeln!(f, "{:<16} {:>12.3}", "p99, ms", self.p99_ms)?;
+        writeln!(f, "{:<16} {:>12.0}", "keys/second", self.keys_per_second())?;
+        match self.peak_memory_kb {
+            Some(peak_memory_kb) => write!(f, "{:<16} {:>12}", "peak memory, kB", peak_memory_kb),
+            None => write!(f, "{:<16} {:>12}", "peak memory, kB", "n/a"),
+        }
+    }
+}
+
+/// Parses the file and generates Android resources in memory `iterations` times
+pub fn run(path: impl AsRef<Path>, iterations: usize) -> Result<BenchmarkReport> {
+    measure(iterations, || {
+        let parsed = parse::parse(&path).map_err(|err| anyhow!(err))?;
+        parsed.to_android_xml_map()?;
+        Ok(parsed
+            .sections
+            .iter()
+            .map(|section| section.keys.len())
+            .sum())
+    })
+}
+
+/// Same as `run`, but keys are parsed by `TwineParser` and streamed into discarded Android
+/// resources one by one, so that peak memory of both modes can be compared. Peak memory is
+/// tracked for the whole process, so modes have to be measured in separate runs.
+pub fn run_streaming(path: impl AsRef<Path>, iterations: usize) -> Result<BenchmarkReport> {
+    measure(iterations, || {
+        let mut keys = 0;
+        let parser = parse::TwineParser::from_path(&path).map_err(|err| anyhow!(err))?;
+        android_gen::stream_resources(
+            parser.inspect(|_| keys += 1),
+            &None,
+            &android_gen::Options::default(),
+            |_| Ok(io::sink()),
+        )?;
+        Ok(keys)
+    })
+}
+
+/// Times `iterations` calls of `cycle` which returns the number of processed keys
+fn measure(iterations: usize, mut cycle: impl FnMut() -> Result<usize>) -> Result<BenchmarkReport> {
+    if iterations == 0 {
+        return Err(anyhow!("Expected at least one iteration"));
+    }
+    let mut timings_ms: Vec<f64> = Vec::with_capacity(iterations);
+    let mut keys = 0;
+    for _ in 0..iterations {
+        let start = Instant::now();
+        keys = cycle()?;
+        timings_ms.push(start.elapsed().as_secs_f64() * 1000.0);
+    }
+    timings_ms.sort_by(|left, right| left.total_cmp(right));
+
+    Ok(BenchmarkReport {
+        iterations,
+        keys,
+        mean_ms: timings_ms.iter().sum::<f64>() / iterations as f64,
+        median_ms: percentile(&timings_ms, 50.0),
+        p95_ms: percentile(&timings_ms, 95.0),
+        p99_ms: percentile(&timings_ms, 99.0),
+        peak_memory_kb: peak_memory_kb(),
+    })
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[f64], percent: f64) -> f64 {
+    let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Reads VmHWM (peak resident set size) of the current process
+fn peak_memory_kb() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn calculates_nearest_rank_percentile() {
+    let sorted: Vec<f64> = (1..=20).map(f64::from).collect();
+
+    assert_eq!(percentile(&sorted, 50.0), 10.0);
+    assert_eq!(percentile(&sorted, 95.0), 19.0);
+    assert_eq!(percentile(&sorted, 99.0), 20.0);
+    assert_eq!(percentile(&[3.0], 99.0), 3.0);
+}
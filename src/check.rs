@@ -0,0 +1,170 @@
+use serde_json::{json, Value};
+use std::fmt;
+
+use crate::parse::{parse_str_with_warnings, ParseOptions};
+use crate::parse_error::{ParseError, ParseWarning};
+use crate::validate::{validate_placeholder_consistency, validate_plural_quantities};
+
+/// Problem of a twine file found by `check`
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct CheckIssue {
+    /// Validation pass which found the problem, e.g. `missing_translation`
+    pub kind: &'static str,
+    pub key: String,
+    pub lang: Option<String>,
+    pub message: String,
+}
+
+/// Problems of a twine file in order of validation passes
+#[derive(PartialEq, Eq, Debug, Default)]
+pub struct CheckReport {
+    pub issues: Vec<CheckIssue>,
+}
+
+/// Runs all validation passes over twine `content`: duplicate keys, language codes,
+/// missing translations, placeholder consistency and CLDR plural categories.
+/// Fails only if the content can't be parsed at all.
+pub fn check(content: &str) -> Result<CheckReport, ParseError> {
+    let options = ParseOptions {
+        allow_duplicate_keys: true,
+        strict_lang: false,
+    };
+    let (file, warnings) = parse_str_with_warnings(content, &options)?;
+    let mut issues: Vec<CheckIssue> = Vec::new();
+    for warning in warnings {
+        let (kind, key, lang) = match &warning {
+            ParseWarning::DuplicateKey { name, .. } => ("duplicate_key", name.clone(), None),
+            ParseWarning::InvalidLanguageCode { key, code, .. } => {
+                ("invalid_language_code", key.clone(), Some(code.clone()))
+            }
+        };
+        issues.push(CheckIssue {
+            kind,
+            key,
+            lang,
+            message: warning.to_string(),
+        });
+    }
+    for missing in file.missing_translations() {
+        issues.push(CheckIssue {
+            kind: "missing_translation",
+            message: format!(
+                "key \"{}\" is missing \"{}\"",
+                missing.key_name, missing.missing_lang
+            ),
+            key: missing.key_name,
+            lang: Some(missing.missing_lang),
+        });
+    }
+    let keys: Vec<_> = file
+        .sections
+        .iter()
+        .flat_map(|section| &section.keys)
+        .collect();
+    for key in &keys {
+        for mismatch in validate_placeholder_consistency(key) {
+            issues.push(CheckIssue {
+                kind: "placeholder_mismatch",
+                key: mismatch.key_name.clone(),
+                lang: Some(mismatch.lang.clone()),
+                message: mismatch.to_string(),
+            });
+        }
+    }
+    for key in &keys {
+        for localization in &key.localizations {
+            for warning in validate_plural_quantities(key, &localization.language_code) {
+                issues.push(CheckIssue {
+                    kind: "cldr_plural",
+                    message: warning.to_string(),
+                    key: warning.key,
+                    lang: Some(warning.lang),
+                });
+            }
+        }
+    }
+    Ok(CheckReport { issues })
+}
+
+impl CheckReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    pub fn to_json(&self) -> Value {
+        let issues: Vec<Value> = self
+            .issues
+            .iter()
+            .map(|issue| {
+                json!({
+                    "kind": issue.kind,
+                    "key": issue.key,
+                    "lang": issue.lang,
+                    "message": issue.message,
+                })
+            })
+            .collect();
+        json!({
+            "ok": self.is_ok(),
+            "issues": issues,
+        })
+    }
+}
+
+impl fmt::Display for CheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in &self.issues {
+            writeln!(f, "{}: {}", issue.kind, issue.message)?;
+        }
+        match self.issues.len() {
+            0 => write!(f, "No problems found"),
+            count => write!(f, "{} problems found", count),
+        }
+    }
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn reports_problems_of_all_passes() {
+    let content = "[[Login]]
+  [title]
+    en = Login
+    de = Anmelden
+  [title]
+    en = Log in
+    de = Anmelden
+  [greeting]
+    en = Hello %s
+    de = Hallo
+  [apples]
+    en:one = %d apple
+    en:few = %d apples
+    de:one = %d Apfel
+    de:other = %d Äpfel
+  [logout]
+    eng = Logout
+";
+    let report = check(content).unwrap();
+    let kinds: Vec<&str> = report.issues.iter().map(|issue| issue.kind).collect();
+
+    assert_eq!(
+        kinds,
+        vec![
+            "duplicate_key",
+            "invalid_language_code",
+            "missing_translation",
+            "missing_translation",
+            "missing_translation",
+            "missing_translation",
+            "missing_translation",
+            "placeholder_mismatch",
+            "cldr_plural",
+            "cldr_plural",
+        ]
+    );
+    assert_eq!(report.to_json()["ok"], false);
+    assert!(report.to_string().ends_with("10 problems found"));
+    assert!(check("[[Login]]\n  [title]\n    en = Login\n")
+        .unwrap()
+        .is_ok());
+}
@@ -0,0 +1,103 @@
+use indexmap::IndexMap;
+
+use crate::parse::File;
+#[cfg(test)]
+use crate::parse::{Key, LocalizedString, Section, StringValue};
+
+/// Translation progress of a single language
+#[derive(PartialEq, Eq, Debug)]
+pub struct LocaleCompleteness {
+    pub language_code: String,
+    pub translated: usize,
+    pub total: usize,
+}
+
+impl LocaleCompleteness {
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            return 100.0;
+        }
+        self.translated as f64 * 100.0 / self.total as f64
+    }
+}
+
+/// Counts keys localized to each language of the file, languages are listed
+/// in order of their first appearance
+pub fn completeness(file: &File, exclude_locales: &[String]) -> Vec<LocaleCompleteness> {
+    let keys: Vec<_> = file
+        .sections
+        .iter()
+        .flat_map(|section| &section.keys)
+        .collect();
+
+    let mut translated: IndexMap<&str, usize> = IndexMap::new();
+    for key in &keys {
+        for localization in &key.localizations {
+            let code = localization.language_code.as_str();
+            if exclude_locales.iter().any(|excluded| excluded == code) {
+                continue;
+            }
+            *translated.entry(code).or_default() += 1;
+        }
+    }
+
+    translated
+        .into_iter()
+        .map(|(code, translated)| LocaleCompleteness {
+            language_code: code.to_string(),
+            translated,
+            total: keys.len(),
+        })
+        .collect()
+}
+
+// -----------------------------  test tools ------------------------------
+#[cfg(test)]
+fn key(name: &str, langs: &[&str]) -> Key {
+    Key {
+        name: name.to_string(),
+        localizations: langs
+            .iter()
+            .map(|lang| LocalizedString {
+                language_code: lang.to_string(),
+                value: StringValue::Single(name.to_string()),
+            })
+            .collect(),
+        max_length: None,
+        comment: None,
+        translatable: true,
+    }
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn count_translated_keys_per_locale() {
+    let file = File {
+        sections: vec![Section {
+            keys: vec![
+                key("login", &["en", "ru", "debug"]),
+                key("logout", &["en", "debug"]),
+                key("settings", &["en", "ru", "debug"]),
+                key("profile", &["en", "debug"]),
+            ],
+        }],
+    };
+
+    let actual = completeness(&file, &["debug".to_string()]);
+    assert_eq!(
+        actual,
+        vec![
+            LocaleCompleteness {
+                language_code: "en".to_string(),
+                translated: 4,
+                total: 4,
+            },
+            LocaleCompleteness {
+                language_code: "ru".to_string(),
+                translated: 2,
+                total: 4,
+            },
+        ]
+    );
+    assert_eq!(actual[1].percent(), 50.0);
+}
@@ -0,0 +1,175 @@
+use anyhow::Result;
+use std::{io::Write, path::Path};
+
+use crate::gen_error::GenError;
+use crate::parse::{File, StringValue};
+#[cfg(test)]
+use crate::parse::{LocalizedString, PluralValue, Section};
+use crate::sink::WriteSink;
+#[cfg(test)]
+use crate::test_tools::{key, plain_str, plurals};
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Wrapper {
+    Single { name: String },
+    Plural { name: String },
+}
+
+impl Wrapper {
+    fn format(&self) -> String {
+        match self {
+            Wrapper::Single { name } => format!(
+                "@Composable\nfun {}String(): String = stringResource(R.string.{})",
+                function_name(name),
+                resource_name(name)
+            ),
+            Wrapper::Plural { name } => format!(
+                "@Composable\nfun {}String(count: Int): String = pluralStringResource(R.plurals.{}, count, count)",
+                function_name(name),
+                resource_name(name)
+            ),
+        }
+    }
+}
+
+pub struct GenResult {
+    value: Vec<Wrapper>,
+}
+
+impl GenResult {
+    /// Writes Kotlin file with a @Composable wrapper for every string resource
+    pub fn write(
+        &self,
+        dir: impl AsRef<Path>,
+        file_name: &str,
+        package: &Option<String>,
+        sink: &mut dyn WriteSink,
+    ) -> Result<(), GenError> {
+        let filepath = dir.as_ref().join(format!("{}.kt", file_name));
+        let mut file: Vec<u8> = Vec::new();
+        self.write_to(&mut file, package)?;
+        sink.write(&filepath, &file)?;
+        Ok(())
+    }
+
+    fn write_to<W: Write>(&self, writer: &mut W, package: &Option<String>) -> Result<(), GenError> {
+        if let Some(package) = package {
+            writer.write_all(format!("package {}\n\n", package).as_bytes())?;
+        }
+        writer.write_all("import androidx.compose.runtime.Composable\n".as_bytes())?;
+        if self
+            .value
+            .iter()
+            .any(|wrapper| matches!(wrapper, Wrapper::Plural { .. }))
+        {
+            writer.write_all("import androidx.compose.ui.res.pluralStringResource\n".as_bytes())?;
+        }
+        writer.write_all("import androidx.compose.ui.res.stringResource\n".as_bytes())?;
+        for wrapper in &self.value {
+            writer.write_all(format!("\n{}\n", wrapper.format()).as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+pub fn generate(source: &File) -> Result<GenResult, GenError> {
+    if source.sections.is_empty() {
+        return Err(GenError::EmptyFile);
+    }
+
+    let keys_len = source.sections.iter().map(|section| section.keys.len()).sum();
+    let mut result: Vec<Wrapper> = Vec::with_capacity(keys_len);
+    for key in source.sections.iter().flat_map(|section| &section.keys) {
+        if key.localizations.is_empty() {
+            continue;
+        }
+        // Key with at least one plural localization is generated as plurals resource
+        let is_plural_key = key
+            .localizations
+            .iter()
+            .any(|localization| matches!(localization.value, StringValue::Plural { .. }));
+        let wrapper = if is_plural_key {
+            Wrapper::Plural {
+                name: key.name.clone(),
+            }
+        } else {
+            Wrapper::Single {
+                name: key.name.clone(),
+            }
+        };
+        result.push(wrapper);
+    }
+
+    Ok(GenResult { value: result })
+}
+
+/// Android replaces dots in resource names with underscores in generated R class
+fn resource_name(key_name: &str) -> String {
+    key_name.replace('.', "_")
+}
+
+/// Converts snake_case key name into camelCase Kotlin function name
+fn function_name(key_name: &str) -> String {
+    let mut result = String::with_capacity(key_name.len());
+    for (index, part) in key_name
+        .split(['_', '.'])
+        .filter(|part| !part.is_empty())
+        .enumerate()
+    {
+        let mut chars = part.chars();
+        let Some(first) = chars.next() else {
+            continue;
+        };
+        if index == 0 {
+            result.extend(first.to_lowercase());
+        } else {
+            result.extend(first.to_uppercase());
+        }
+        result.push_str(chars.as_str());
+    }
+    result
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn converts_key_names_to_function_names() {
+    assert_eq!(function_name("login_button"), "loginButton");
+    assert_eq!(function_name("Antigua_Barbadoa"), "antiguaBarbadoa");
+    assert_eq!(function_name("settings.title__main"), "settingsTitleMain");
+    assert_eq!(function_name("days"), "days");
+}
+
+#[test]
+fn generate_wrappers_for_single_and_plural() -> Result<()> {
+    let keys = vec![
+        key("login_button", vec![plain_str("en", "Login")]),
+        key(
+            "receipt_example",
+            vec![plurals("en", vec![PluralValue::new("other", "%d receipts")])],
+        ),
+        key::<LocalizedString>("empty", vec![]),
+    ];
+    let source = File {
+        sections: vec![Section { keys }],
+    };
+
+    let generated = generate(&source)?;
+    let mut buffer: Vec<u8> = Vec::new();
+    generated.write_to(&mut buffer, &Some("com.example.app".to_string()))?;
+
+    let expected = "package com.example.app
+
+import androidx.compose.runtime.Composable
+import androidx.compose.ui.res.pluralStringResource
+import androidx.compose.ui.res.stringResource
+
+@Composable
+fun loginButtonString(): String = stringResource(R.string.login_button)
+
+@Composable
+fun receiptExampleString(count: Int): String = pluralStringResource(R.plurals.receipt_example, count, count)
+";
+    assert_eq!(expected, String::from_utf8(buffer)?);
+
+    Ok(())
+}
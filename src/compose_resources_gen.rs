@@ -0,0 +1,23 @@
+use crate::android_gen::{self, GenResult};
+use crate::gen_error::GenError;
+use crate::parse::{File, StringValue};
+use crate::platform::to_named_placeholders;
+use crate::transform;
+
+/// Generates `composeResources/values-*` strings of Compose Multiplatform. Resources are
+/// Android strings.xml without `<plurals>`, plural quantities become separate
+/// `name_quantity` strings, and with `{argN}` placeholders instead of printf ones.
+pub fn generate(mut file: File) -> Result<GenResult, GenError> {
+    transform::flatten_plurals(&mut file);
+    let localizations = file
+        .sections
+        .iter_mut()
+        .flat_map(|section| &mut section.keys)
+        .flat_map(|key| &mut key.localizations);
+    for localization in localizations {
+        if let StringValue::Single(text) = &mut localization.value {
+            *text = to_named_placeholders(text, "{", "}");
+        }
+    }
+    android_gen::generate(&file)
+}
@@ -0,0 +1,364 @@
+use serde_json::{json, Value};
+use std::fmt;
+
+use crate::parse::{CldrCategory, LocalizedString, PluralValue, StringValue, KEY_ATTRIBUTES};
+use crate::twine_text::raw_sections;
+
+/// Everything known about a single key of twine file
+#[derive(PartialEq, Debug)]
+pub struct KeyDescription {
+    pub name: String,
+    /// Empty for keys before the first section header
+    pub section: String,
+    /// 1-based number of the key header line
+    pub line_number: usize,
+    pub localizations: Vec<LocaleDescription>,
+    /// Attributes like `comment` or `tags` as (name, value) pairs
+    pub annotations: Vec<(String, String)>,
+    pub warnings: Vec<String>,
+}
+
+/// Value of the key in a single language, as written in twine file
+#[derive(PartialEq, Debug)]
+pub struct LocaleDescription {
+    pub language_code: String,
+    pub value: StringValue,
+    /// Number of placeholders in the value, the largest one among quantities of plurals
+    pub placeholders: usize,
+}
+
+impl KeyDescription {
+    pub fn has_plurals(&self) -> bool {
+        self.localizations
+            .iter()
+            .any(|localization| matches!(localization.value, StringValue::Plural { .. }))
+    }
+
+    /// Values of `lang` or of all languages prefixed with the language code, plural values
+    /// are printed as a line per quantity. `None` if the key has no value in `lang`.
+    pub fn value_lines(&self, lang: Option<&str>) -> Option<Vec<String>> {
+        let mut result: Vec<String> = Vec::new();
+        for localization in &self.localizations {
+            let prefix = match lang {
+                Some(lang) if lang != localization.language_code => continue,
+                Some(_) => String::new(),
+                None => format!("{}:", localization.language_code),
+            };
+            match &localization.value {
+                StringValue::Single(text) if prefix.is_empty() => result.push(text.clone()),
+                StringValue::Single(text) => result.push(format!("{} {}", prefix, text)),
+                StringValue::Plural { quantities } => {
+                    for item in quantities {
+                        result.push(format!("{}{}: {}", prefix, item.quantity, item.text));
+                    }
+                }
+                StringValue::Array(items) => {
+                    for (index, item) in items.iter().enumerate() {
+                        result.push(format!("{}{}: {}", prefix, index, item));
+                    }
+                }
+            }
+        }
+        let found = match lang {
+            Some(lang) => self.language_codes().contains(&lang),
+            None => true,
+        };
+        found.then_some(result)
+    }
+
+    pub fn language_codes(&self) -> Vec<&str> {
+        self.localizations
+            .iter()
+            .map(|localization| localization.language_code.as_str())
+            .collect()
+    }
+
+    pub fn to_json(&self) -> Value {
+        let localizations: Vec<Value> = self
+            .localizations
+            .iter()
+            .map(|localization| {
+                let value = match &localization.value {
+                    StringValue::Single(text) => json!(text),
+                    StringValue::Array(items) => json!(items),
+                    StringValue::Plural { quantities } => Value::Object(
+                        quantities
+                            .iter()
+                            .map(|item| (item.quantity.clone(), json!(item.text)))
+                            .collect(),
+                    ),
+                };
+                json!({
+                    "language_code": localization.language_code,
+                    "value": value,
+                    "placeholders": localization.placeholders,
+                })
+            })
+            .collect();
+        let annotations: serde_json::Map<String, Value> = self
+            .annotations
+            .iter()
+            .map(|(name, value)| (name.clone(), json!(value)))
+            .collect();
+        json!({
+            "name": self.name,
+            "section": self.section,
+            "line_number": self.line_number,
+            "has_plurals": self.has_plurals(),
+            "localizations": localizations,
+            "annotations": annotations,
+            "warnings": self.warnings,
+        })
+    }
+}
+
+impl fmt::Display for KeyDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "key: {}", self.name)?;
+        writeln!(f, "section: {}", self.section)?;
+        writeln!(f, "line: {}", self.line_number)?;
+        writeln!(
+            f,
+            "plurals: {}",
+            if self.has_plurals() { "yes" } else { "no" }
+        )?;
+        for (name, value) in &self.annotations {
+            writeln!(f, "{}: {}", name, value)?;
+        }
+        writeln!(f, "localizations:")?;
+        for localization in &self.localizations {
+            let code = &localization.language_code;
+            let placeholders = localization.placeholders;
+            match &localization.value {
+                StringValue::Single(text) => {
+                    writeln!(f, "  {} ({} placeholders): {}", code, placeholders, text)?
+                }
+                StringValue::Plural { quantities } => {
+                    writeln!(f, "  {} ({} placeholders):", code, placeholders)?;
+                    for item in quantities {
+                        writeln!(f, "    {}: {}", item.quantity, item.text)?;
+                    }
+                }
+                StringValue::Array(items) => {
+                    writeln!(f, "  {} ({} placeholders):", code, placeholders)?;
+                    for (index, item) in items.iter().enumerate() {
+                        writeln!(f, "    {}: {}", index, item)?;
+                    }
+                }
+            }
+        }
+        if self.warnings.is_empty() {
+            return Ok(());
+        }
+        writeln!(f, "warnings:")?;
+        for warning in &self.warnings {
+            writeln!(f, "  {}", warning)?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects information about the key from twine file content, `None` if there is no such key
+pub fn describe_key(content: &str, key_name: &str) -> Option<KeyDescription> {
+    let (section, raw_key) = raw_sections(content)
+        .into_iter()
+        .flat_map(|section| {
+            let name = section.name;
+            section.keys.into_iter().map(move |key| (name.clone(), key))
+        })
+        .find(|(_, key)| key.name == key_name)?;
+
+    let mut annotations: Vec<(String, String)> = Vec::new();
+    let mut values: Vec<(String, StringValue)> = Vec::new();
+    let mut warnings: Vec<String> = Vec::new();
+    for entry in raw_key.entries {
+        if KEY_ATTRIBUTES.contains(&entry.name.as_str()) {
+            annotations.push((entry.name, entry.value));
+            continue;
+        }
+        if entry.value.is_empty() {
+            warnings.push(format!("\"{}\" has empty value", entry.name));
+        }
+        let (lang, quantity) = match entry.name.split_once(':') {
+            Some((lang, quantity)) => (lang.to_string(), Some(quantity.to_string())),
+            None => (entry.name, None),
+        };
+        let index = match values.iter().position(|(code, _)| *code == lang) {
+            Some(index) => index,
+            None => {
+                let value = match quantity {
+                    Some(_) => StringValue::Plural {
+                        quantities: Vec::new(),
+                    },
+                    None => StringValue::Single(String::new()),
+                };
+                values.push((lang.clone(), value));
+                values.len() - 1
+            }
+        };
+        match (&mut values[index].1, quantity) {
+            (StringValue::Single(text), None) => *text = entry.value,
+            (StringValue::Plural { quantities }, Some(quantity)) => {
+                if CldrCategory::from_quantity(&quantity).is_none() {
+                    warnings.push(format!(
+                        "\"{}\" has unknown plural quantity \"{}\"",
+                        lang, quantity
+                    ));
+                }
+                quantities.push(PluralValue::new(quantity, entry.value));
+            }
+            _ => warnings.push(format!(
+                "\"{}\" is defined both as a plural and a regular string",
+                lang
+            )),
+        }
+    }
+
+    let localizations: Vec<LocaleDescription> = values
+        .into_iter()
+        .map(|(language_code, value)| {
+            let localization = LocalizedString {
+                language_code,
+                value,
+            };
+            LocaleDescription {
+                placeholders: localization.placeholder_count(),
+                language_code: localization.language_code,
+                value: localization.value,
+            }
+        })
+        .collect();
+
+    for localization in &localizations {
+        if let StringValue::Plural { quantities } = &localization.value {
+            if !quantities.iter().any(|item| item.quantity == "other") {
+                warnings.push(format!(
+                    "\"{}\" has no \"other\" plural quantity",
+                    localization.language_code
+                ));
+            }
+        }
+    }
+    if let Some(first) = localizations.first() {
+        for localization in &localizations[1..] {
+            if localization.placeholders != first.placeholders {
+                warnings.push(format!(
+                    "\"{}\" has {} placeholders while \"{}\" has {}",
+                    localization.language_code,
+                    localization.placeholders,
+                    first.language_code,
+                    first.placeholders
+                ));
+            }
+        }
+    }
+
+    Some(KeyDescription {
+        name: raw_key.name,
+        section,
+        line_number: raw_key.line_number,
+        localizations,
+        annotations,
+        warnings,
+    })
+}
+
+/// Names of all keys of twine file content in order of appearance
+pub fn key_names(content: &str) -> Vec<String> {
+    raw_sections(content)
+        .into_iter()
+        .flat_map(|section| section.keys)
+        .map(|key| key.name)
+        .collect()
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn describes_key_with_warnings() {
+    let content = "[[Buttons]]
+  [login]
+    en = Login as %@
+    ru = Войти
+    comment = Login button title
+
+  [days]
+    en:one = %d day
+    en:other = %d days
+    ru:one = %d день
+    ru:few = %d дня
+";
+
+    let actual = describe_key(content, "days").unwrap();
+    assert_eq!(actual.section, "Buttons");
+    assert_eq!(actual.line_number, 7);
+    assert!(actual.has_plurals());
+    assert_eq!(
+        actual.warnings,
+        vec!["\"ru\" has no \"other\" plural quantity".to_string()]
+    );
+
+    let actual = describe_key(content, "login").unwrap();
+    assert!(!actual.has_plurals());
+    assert_eq!(
+        actual.annotations,
+        vec![("comment".to_string(), "Login button title".to_string())]
+    );
+    assert_eq!(
+        actual.localizations[0],
+        LocaleDescription {
+            language_code: "en".to_string(),
+            value: StringValue::Single("Login as %@".to_string()),
+            placeholders: 1,
+        }
+    );
+    assert_eq!(
+        actual.warnings,
+        vec!["\"ru\" has 0 placeholders while \"en\" has 1".to_string()]
+    );
+
+    assert!(describe_key(content, "logout").is_none());
+}
+
+#[test]
+fn prints_values_of_key() {
+    let content = "[[Buttons]]
+  [login]
+    en = Login
+    ru = Войти
+
+  [days]
+    en:one = %d day
+    en:other = %d days
+    ru:few = %d дня
+";
+
+    assert_eq!(key_names(content), vec!["login", "days"]);
+
+    let login = describe_key(content, "login").unwrap();
+    assert_eq!(
+        login.value_lines(Some("ru")),
+        Some(vec!["Войти".to_string()])
+    );
+    assert_eq!(
+        login.value_lines(None),
+        Some(vec!["en: Login".to_string(), "ru: Войти".to_string()])
+    );
+    assert_eq!(login.value_lines(Some("de")), None);
+
+    let days = describe_key(content, "days").unwrap();
+    assert_eq!(
+        days.value_lines(Some("en")),
+        Some(vec![
+            "one: %d day".to_string(),
+            "other: %d days".to_string()
+        ])
+    );
+    assert_eq!(
+        days.value_lines(None),
+        Some(vec![
+            "en:one: %d day".to_string(),
+            "en:other: %d days".to_string(),
+            "ru:few: %d дня".to_string(),
+        ])
+    );
+}
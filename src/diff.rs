@@ -0,0 +1,219 @@
+use indexmap::IndexMap;
+use std::fmt;
+
+use crate::parse::{File, Key, StringValue};
+#[cfg(test)]
+use crate::parse::{PluralValue, Section};
+#[cfg(test)]
+use crate::test_tools::{key, plain_str};
+
+/// Difference of a single key between two versions of a file
+#[derive(PartialEq, Eq, Debug)]
+pub enum KeyDiff {
+    Added {
+        name: String,
+    },
+    Removed {
+        name: String,
+    },
+    /// Localization was added, removed (`None` on the corresponding side) or modified
+    Changed {
+        name: String,
+        lang: String,
+        old: Option<StringValue>,
+        new: Option<StringValue>,
+    },
+}
+
+impl fmt::Display for KeyDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyDiff::Added { name } => write!(f, "+ {}", name),
+            KeyDiff::Removed { name } => write!(f, "- {}", name),
+            KeyDiff::Changed {
+                name,
+                lang,
+                old,
+                new,
+            } => write!(
+                f,
+                "~ {} [{}]: {} -> {}",
+                name,
+                lang,
+                format_value(old),
+                format_value(new)
+            ),
+        }
+    }
+}
+
+fn format_value(value: &Option<StringValue>) -> String {
+    match value {
+        None => "|NONE|".to_string(),
+        Some(StringValue::Single(text)) => format!("\"{}\"", text),
+        Some(StringValue::Plural { quantities }) => quantities
+            .iter()
+            .map(|item| format!("{}: \"{}\"", item.quantity, item.text))
+            .collect::<Vec<_>>()
+            .join(", "),
+        Some(StringValue::Array(items)) => items
+            .iter()
+            .map(|item| format!("\"{}\"", item))
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
+
+/// Compares keys of two files, keys are matched by name
+pub fn diff_files(old: &File, new: &File) -> Vec<KeyDiff> {
+    let old_keys = keys_by_name(old);
+    let new_keys = keys_by_name(new);
+
+    let mut result: Vec<KeyDiff> = Vec::new();
+    for (name, old_key) in &old_keys {
+        let Some(new_key) = new_keys.get(name) else {
+            result.push(KeyDiff::Removed {
+                name: name.to_string(),
+            });
+            continue;
+        };
+        result.extend(diff_localizations(old_key, new_key));
+    }
+    for name in new_keys.keys() {
+        if !old_keys.contains_key(name) {
+            result.push(KeyDiff::Added {
+                name: name.to_string(),
+            });
+        }
+    }
+    result
+}
+
+fn keys_by_name(file: &File) -> IndexMap<&str, &Key> {
+    file.sections
+        .iter()
+        .flat_map(|section| &section.keys)
+        .map(|key| (key.name.as_str(), key))
+        .collect()
+}
+
+fn diff_localizations(old: &Key, new: &Key) -> Vec<KeyDiff> {
+    let old_values: IndexMap<&str, &StringValue> = old
+        .localizations
+        .iter()
+        .map(|str| (str.language_code.as_str(), &str.value))
+        .collect();
+    let new_values: IndexMap<&str, &StringValue> = new
+        .localizations
+        .iter()
+        .map(|str| (str.language_code.as_str(), &str.value))
+        .collect();
+
+    let mut langs: Vec<&str> = old_values.keys().copied().collect();
+    langs.extend(
+        new_values
+            .keys()
+            .filter(|lang| !old_values.contains_key(*lang)),
+    );
+
+    langs
+        .into_iter()
+        .filter_map(|lang| {
+            let old_value = old_values.get(lang).copied();
+            let new_value = new_values.get(lang).copied();
+            if old_value == new_value {
+                return None;
+            }
+            Some(KeyDiff::Changed {
+                name: old.name.clone(),
+                lang: lang.to_string(),
+                old: old_value.cloned(),
+                new: new_value.cloned(),
+            })
+        })
+        .collect()
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn diff_added_removed_and_changed_keys() {
+    let old = File {
+        sections: vec![Section {
+            keys: vec![
+                key(
+                    "login",
+                    vec![plain_str("en", "Login"), plain_str("ru", "Вход")],
+                ),
+                key("logout", vec![plain_str("en", "Logout")]),
+                key("same", vec![plain_str("en", "Same")]),
+            ],
+        }],
+    };
+    let new = File {
+        sections: vec![Section {
+            keys: vec![
+                key(
+                    "login",
+                    vec![plain_str("en", "Sign in"), plain_str("de", "Anmelden")],
+                ),
+                key("same", vec![plain_str("en", "Same")]),
+                key("title", vec![plain_str("en", "Title")]),
+            ],
+        }],
+    };
+
+    let actual = diff_files(&old, &new);
+    let expected = vec![
+        KeyDiff::Changed {
+            name: "login".to_string(),
+            lang: "en".to_string(),
+            old: Some(StringValue::Single("Login".to_string())),
+            new: Some(StringValue::Single("Sign in".to_string())),
+        },
+        KeyDiff::Changed {
+            name: "login".to_string(),
+            lang: "ru".to_string(),
+            old: Some(StringValue::Single("Вход".to_string())),
+            new: None,
+        },
+        KeyDiff::Changed {
+            name: "login".to_string(),
+            lang: "de".to_string(),
+            old: None,
+            new: Some(StringValue::Single("Anmelden".to_string())),
+        },
+        KeyDiff::Removed {
+            name: "logout".to_string(),
+        },
+        KeyDiff::Added {
+            name: "title".to_string(),
+        },
+    ];
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn formats_diffs() {
+    let changed = KeyDiff::Changed {
+        name: "days".to_string(),
+        lang: "en".to_string(),
+        old: None,
+        new: Some(StringValue::Plural {
+            quantities: vec![
+                PluralValue::new("one", "%d day"),
+                PluralValue::new("other", "%d days"),
+            ],
+        }),
+    };
+    assert_eq!(
+        changed.to_string(),
+        "~ days [en]: |NONE| -> one: \"%d day\", other: \"%d days\""
+    );
+    assert_eq!(
+        KeyDiff::Added {
+            name: "title".to_string()
+        }
+        .to_string(),
+        "+ title"
+    );
+}
@@ -0,0 +1,116 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
+use std::fs;
+use std::io::{self, ErrorKind, Write};
+use std::path::Path;
+use tempfile::NamedTempFile;
+
+/// Encoding which file content was decoded with
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct DetectedEncoding {
+    /// WHATWG name of the encoding, e.g. `UTF-8` or `windows-1251`
+    pub name: &'static str,
+    /// Whether the encoding was guessed from content rather than taken from the hint or BOM
+    pub guessed: bool,
+}
+
+/// Reads file content decoding it with `hint` encoding label, e.g. `windows-1251`.
+/// Without the hint, encoding is taken from BOM, UTF-8 is used for valid UTF-8 content
+/// and any other encoding is guessed with `chardetng`.
+pub fn read_to_string_with_encoding(
+    path: &Path,
+    hint: Option<&str>,
+) -> Result<(String, DetectedEncoding), io::Error> {
+    let bytes = fs::read(path)?;
+    let (encoding, bom_length, guessed) = match hint {
+        Some(label) => {
+            let encoding = Encoding::for_label(label.trim().as_bytes()).ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Unknown encoding \"{}\"", label),
+                )
+            })?;
+            (encoding, 0, false)
+        }
+        None => match Encoding::for_bom(&bytes) {
+            Some((encoding, bom_length)) => (encoding, bom_length, false),
+            None if std::str::from_utf8(&bytes).is_ok() => (UTF_8, 0, false),
+            None => {
+                let mut detector = EncodingDetector::new();
+                detector.feed(&bytes, true);
+                (detector.guess(None, true), 0, true)
+            }
+        },
+    };
+
+    let (content, had_errors) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+    if had_errors {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("{} is not valid {}", path.display(), encoding.name()),
+        ));
+    }
+    let detected = DetectedEncoding {
+        name: encoding.name(),
+        guessed,
+    };
+    Ok((content.into_owned(), detected))
+}
+
+/// Writes `contents` to a temporary file next to `path` and renames it to `path`, so readers
+/// never see a partially written file. Existing file is overwritten.
+pub fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), io::Error> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut temp_file = NamedTempFile::new_in(dir)?;
+    temp_file.write_all(contents)?;
+    temp_file.persist(path).map_err(|error| error.error)?;
+    Ok(())
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn detects_encoding_of_file_content() -> io::Result<()> {
+    let dir = tempfile::tempdir()?;
+
+    let path = dir.path().join("utf8.twine");
+    fs::write(&path, "\u{feff}[login]\n  ru = Вход\n")?;
+    let (content, encoding) = read_to_string_with_encoding(&path, None)?;
+    assert_eq!(content, "[login]\n  ru = Вход\n");
+    assert_eq!(
+        encoding,
+        DetectedEncoding {
+            name: "UTF-8",
+            guessed: false
+        }
+    );
+
+    // "Войти в систему" in windows-1251
+    let path = dir.path().join("cp1251.twine");
+    let (bytes, _, _) = encoding_rs::WINDOWS_1251.encode("[login]\n  ru = Войти в систему\n");
+    fs::write(&path, &bytes)?;
+    let (content, encoding) = read_to_string_with_encoding(&path, Some("cp1251"))?;
+    assert_eq!(content, "[login]\n  ru = Войти в систему\n");
+    assert_eq!(encoding.name, "windows-1251");
+
+    let (_, encoding) = read_to_string_with_encoding(&path, None)?;
+    assert!(encoding.guessed);
+
+    assert!(read_to_string_with_encoding(&path, Some("klingon")).is_err());
+    Ok(())
+}
+
+#[test]
+fn writes_atomically_overwriting_existing_file() -> io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("skipped.json");
+    fs::write(&path, "old content")?;
+
+    write_atomically(&path, b"[]")?;
+
+    assert_eq!(fs::read_to_string(&path)?, "[]");
+    assert_eq!(fs::read_dir(dir.path())?.count(), 1);
+    Ok(())
+}
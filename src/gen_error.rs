@@ -0,0 +1,63 @@
+use std::{fmt, io};
+
+use crate::parse_error::ParseError;
+
+/// Errors which can happen while generating platform resources
+#[derive(Debug)]
+pub enum GenError {
+    IoError(io::Error),
+    /// Key name can't be used as a resource name on target platform
+    InvalidKeyName(String),
+    /// Plural quantity is not one of the CLDR plural categories
+    UnsupportedPluralCategory(String),
+    /// Source file has no sections to generate resources from
+    EmptyFile,
+    /// Requested language has no localizations in source file
+    UnknownLanguage(String),
+    /// Value of the key can't be used for the resource type, e.g. plural color
+    InvalidResourceValue(String),
+    /// Keys of source file are parsed while generating, e.g. by `TwineParser`
+    Parse(ParseError),
+}
+
+impl fmt::Display for GenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenError::IoError(err) => write!(f, "I/O error: {}", err),
+            GenError::InvalidKeyName(name) => write!(f, "Invalid key name \"{}\"", name),
+            GenError::UnsupportedPluralCategory(quantity) => {
+                write!(f, "Unsupported plural category \"{}\"", quantity)
+            }
+            GenError::EmptyFile => write!(f, "Expected at least one section"),
+            GenError::UnknownLanguage(lang) => {
+                write!(f, "No localizations found for language \"{}\"", lang)
+            }
+            GenError::InvalidResourceValue(name) => {
+                write!(f, "Invalid value of resource \"{}\"", name)
+            }
+            GenError::Parse(err) => write!(f, "Parse error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for GenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GenError::IoError(err) => Some(err),
+            GenError::Parse(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for GenError {
+    fn from(err: io::Error) -> Self {
+        GenError::IoError(err)
+    }
+}
+
+impl From<ParseError> for GenError {
+    fn from(err: ParseError) -> Self {
+        GenError::Parse(err)
+    }
+}
@@ -0,0 +1,396 @@
+use anyhow::Result;
+use std::path::Path;
+
+use crate::parse::File;
+use crate::sink::WriteSink;
+use crate::{
+    android_gen, arb_gen, compose_gen, compose_resources_gen, ios_gen, java_properties_gen,
+    json_gen, kotlin_constants_gen, laravel_gen, moko_gen, po, rails_gen, transform, xcstrings_gen,
+    xliff2_gen, xliff_gen,
+};
+
+pub use crate::gen_error::GenError;
+
+/// Parsed source files of generation, e.g. the CLI parses files of the input directory
+/// with its parse options and validations
+pub trait Sources {
+    /// Every source file with its name without extension, e.g. `strings` for `strings.twine`
+    fn files(&self) -> Result<Vec<(String, File)>>;
+
+    /// Keys of all source files combined into one file, for platforms which have a single
+    /// file per language
+    fn merged(&self) -> Result<File>;
+}
+
+/// Version of XLIFF documents written by xliff generator
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum XliffFormat {
+    #[default]
+    Xliff1,
+    Xliff2,
+}
+
+/// Options of registered generators, each generator reads only its own ones
+pub struct GenOptions {
+    pub default_lang: Option<String>,
+    pub android: android_gen::Options,
+    /// Name of Android resources file with keys of all source files, every source file
+    /// gets its own one otherwise
+    pub android_merge_into: Option<String>,
+    /// See `transform::flatten_plurals`
+    pub android_flatten_plurals: bool,
+    /// See `transform::convert_singles_to_plurals`
+    pub android_generate_plurals_only: bool,
+    /// See `transform::escape_newlines`
+    pub android_escape_newlines: bool,
+    /// Package declaration of compose-wrappers Kotlin files
+    pub compose_package: Option<String>,
+    pub xliff_format: XliffFormat,
+    pub json_flat_plurals: bool,
+    pub rails_keep_printf: bool,
+    /// Name of PHP files, `lang/<lang>/<name>.php`
+    pub laravel_file: String,
+    pub laravel_plural_style: laravel_gen::PluralStyle,
+    pub kotlin_package: Option<String>,
+    pub kotlin_object_name: String,
+}
+
+impl Default for GenOptions {
+    fn default() -> Self {
+        GenOptions {
+            default_lang: None,
+            android: android_gen::Options::default(),
+            android_merge_into: None,
+            android_flatten_plurals: false,
+            android_generate_plurals_only: false,
+            android_escape_newlines: true,
+            compose_package: None,
+            xliff_format: XliffFormat::default(),
+            json_flat_plurals: false,
+            rails_keep_printf: false,
+            laravel_file: "strings".to_string(),
+            laravel_plural_style: laravel_gen::PluralStyle::default(),
+            kotlin_package: None,
+            kotlin_object_name: kotlin_constants_gen::DEFAULT_OBJECT_NAME.to_string(),
+        }
+    }
+}
+
+/// Generates resources of a target from the sources into the output directory
+pub type Generator = fn(&dyn Sources, &GenOptions, &Path, &mut dyn WriteSink) -> Result<()>;
+
+/// Maps target names to generators, so that every target can be generated in one pass
+pub struct GeneratorRegistry {
+    generators: &'static [(&'static str, Generator)],
+}
+
+impl GeneratorRegistry {
+    /// Generator of the target, `None` if it's not registered
+    pub fn get(&self, target: &str) -> Option<Generator> {
+        self.iter()
+            .find(|(name, _)| *name == target)
+            .map(|(_, generator)| generator)
+    }
+
+    /// Target names in order of registration
+    pub fn names(&self) -> Vec<&'static str> {
+        self.iter().map(|(name, _)| name).collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, Generator)> {
+        self.generators.iter().copied()
+    }
+}
+
+/// Generators of all platforms, `all` target of the CLI runs every one of them writing into
+/// `<output_dir>/<target>` subdirectories
+pub const GENERATOR_REGISTRY: GeneratorRegistry = GeneratorRegistry {
+    generators: &[
+        ("android", generate_android),
+        ("ios", generate_ios),
+        ("xcstrings", generate_xcstrings),
+        ("compose-wrappers", generate_compose_wrappers),
+        ("compose-multiplatform", generate_compose_resources),
+        ("kmp-moko", generate_moko),
+        ("arb", generate_arb),
+        ("po", generate_po),
+        ("xliff", generate_xliff),
+        ("java-properties", generate_java_properties),
+        ("json", generate_json),
+        ("rails-yaml", generate_rails_yaml),
+        ("laravel-php", generate_laravel_php),
+        ("kotlin-constants", generate_kotlin_constants),
+    ],
+};
+
+fn generate_android(
+    sources: &dyn Sources,
+    options: &GenOptions,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<()> {
+    let files = match &options.android_merge_into {
+        Some(name) => vec![(name.clone(), sources.merged()?)],
+        None => sources.files()?,
+    };
+    for (name, mut parsed) in files {
+        if options.android_flatten_plurals {
+            transform::flatten_plurals(&mut parsed);
+        }
+        if options.android_generate_plurals_only {
+            transform::convert_singles_to_plurals(&mut parsed);
+        }
+        if options.android_escape_newlines {
+            transform::escape_newlines(&mut parsed);
+        }
+        let generated = android_gen::generate(&parsed)?;
+        generated.write(
+            output_dir,
+            &name,
+            &options.default_lang,
+            &options.android,
+            sink,
+        )?;
+    }
+    Ok(())
+}
+
+fn generate_ios(
+    sources: &dyn Sources,
+    options: &GenOptions,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<()> {
+    let generated = ios_gen::generate(vec![sources.merged()?])?;
+    generated.write(output_dir, &options.default_lang, sink)?;
+    Ok(())
+}
+
+fn generate_xcstrings(
+    sources: &dyn Sources,
+    options: &GenOptions,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<()> {
+    let generated = xcstrings_gen::generate(vec![sources.merged()?])?;
+    generated.write(output_dir, &options.default_lang, sink)?;
+    Ok(())
+}
+
+fn generate_compose_wrappers(
+    sources: &dyn Sources,
+    options: &GenOptions,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<()> {
+    for (name, parsed) in sources.files()? {
+        let generated = compose_gen::generate(&parsed)?;
+        generated.write(output_dir, &name, &options.compose_package, sink)?;
+    }
+    Ok(())
+}
+
+fn generate_compose_resources(
+    sources: &dyn Sources,
+    options: &GenOptions,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<()> {
+    for (name, parsed) in sources.files()? {
+        let generated = compose_resources_gen::generate(parsed)?;
+        generated.write(
+            output_dir,
+            &name,
+            &options.default_lang,
+            &android_gen::Options::default(),
+            sink,
+        )?;
+    }
+    Ok(())
+}
+
+/// moko-resources has a single strings.xml per language, so keys of all files are combined
+fn generate_moko(
+    sources: &dyn Sources,
+    options: &GenOptions,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<()> {
+    let generated = moko_gen::generate(&sources.merged()?)?;
+    generated.write(output_dir, &options.default_lang, sink)?;
+    Ok(())
+}
+
+/// Flutter has a single .arb file per language, so keys of all files are combined
+fn generate_arb(
+    sources: &dyn Sources,
+    _options: &GenOptions,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<()> {
+    arb_gen::write_arb(&sources.merged()?, output_dir, sink)?;
+    Ok(())
+}
+
+fn generate_po(
+    sources: &dyn Sources,
+    _options: &GenOptions,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<()> {
+    po::write_po_all(&sources.merged()?, output_dir, sink)?;
+    Ok(())
+}
+
+/// Writes XLIFF of every language translated from the default language, English if it's not set
+fn generate_xliff(
+    sources: &dyn Sources,
+    options: &GenOptions,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<()> {
+    let combined = sources.merged()?;
+    let source_lang = options.default_lang.as_deref().unwrap_or("en");
+    match options.xliff_format {
+        XliffFormat::Xliff1 => {
+            xliff_gen::write_xliff_all(&combined, source_lang, output_dir, sink)?
+        }
+        XliffFormat::Xliff2 => {
+            xliff2_gen::write_xliff_2_0_all(&combined, source_lang, output_dir, sink)?
+        }
+    }
+    Ok(())
+}
+
+fn generate_java_properties(
+    sources: &dyn Sources,
+    _options: &GenOptions,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<()> {
+    java_properties_gen::write_java_properties(&sources.merged()?, output_dir, sink)?;
+    Ok(())
+}
+
+fn generate_json(
+    sources: &dyn Sources,
+    options: &GenOptions,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<()> {
+    let combined = sources.merged()?;
+    if options.json_flat_plurals {
+        json_gen::write_json_flat_plurals(&combined, output_dir, sink)?;
+    } else {
+        json_gen::write_json(&combined, output_dir, sink)?;
+    }
+    Ok(())
+}
+
+fn generate_rails_yaml(
+    sources: &dyn Sources,
+    options: &GenOptions,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<()> {
+    let combined = sources.merged()?;
+    if options.rails_keep_printf {
+        rails_gen::write_rails_yaml_keep_printf(&combined, output_dir, sink)?;
+    } else {
+        rails_gen::write_rails_yaml(&combined, output_dir, sink)?;
+    }
+    Ok(())
+}
+
+fn generate_laravel_php(
+    sources: &dyn Sources,
+    options: &GenOptions,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<()> {
+    laravel_gen::write_laravel_php(
+        &sources.merged()?,
+        output_dir,
+        &options.laravel_file,
+        options.laravel_plural_style,
+        sink,
+    )?;
+    Ok(())
+}
+
+fn generate_kotlin_constants(
+    sources: &dyn Sources,
+    options: &GenOptions,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<()> {
+    let output_path = output_dir.join(format!("{}.kt", options.kotlin_object_name));
+    kotlin_constants_gen::write_kotlin_constants_object(
+        &sources.merged()?,
+        options.kotlin_package.as_deref().unwrap_or_default(),
+        &options.kotlin_object_name,
+        &output_path,
+        sink,
+    )?;
+    Ok(())
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn generate_every_registered_target_from_sources() -> Result<()> {
+    let sources = TwineSources(vec![
+        (
+            "main",
+            "[[General]]\n\t[app_name]\n\t\ten = App\n\t\tru = Приложение\n",
+        ),
+        (
+            "extra",
+            "[[General]]\n\t[hello]\n\t\ten = Hello\n\t\tru = Привет\n",
+        ),
+    ]);
+    for (target, generator) in GENERATOR_REGISTRY.iter() {
+        let mut sink = crate::sink::MemorySink::default();
+        generator(
+            &sources,
+            &GenOptions::default(),
+            Path::new(target),
+            &mut sink,
+        )?;
+        assert!(!sink.files.is_empty(), "{} wrote no files", target);
+        assert!(sink.files.iter().all(|(path, _)| path.starts_with(target)));
+    }
+    Ok(())
+}
+
+#[test]
+fn find_generators_by_target_name() {
+    assert!(GENERATOR_REGISTRY.get("android").is_some());
+    assert!(GENERATOR_REGISTRY.get("unknown").is_none());
+    assert_eq!(
+        GENERATOR_REGISTRY.names().len(),
+        GENERATOR_REGISTRY.iter().count()
+    );
+}
+
+// -----------------------------  test tools -----------------------------------
+#[cfg(test)]
+struct TwineSources(Vec<(&'static str, &'static str)>);
+
+#[cfg(test)]
+impl Sources for TwineSources {
+    fn files(&self) -> Result<Vec<(String, File)>> {
+        let mut files = Vec::new();
+        for (name, content) in &self.0 {
+            files.push((name.to_string(), crate::parse::parse_str(content)?));
+        }
+        Ok(files)
+    }
+
+    fn merged(&self) -> Result<File> {
+        let mut merged = File { sections: vec![] };
+        for (_, file) in self.files()? {
+            merged = merged.merge(file)?;
+        }
+        Ok(merged)
+    }
+}
@@ -0,0 +1,67 @@
+use indexmap::IndexMap;
+
+use crate::parse::{COMMENT_ATTRIBUTE, TRANSLATABLE_ATTRIBUTE};
+use crate::twine_text::format_key;
+
+/// Name of the only section of imported twine files
+const SECTION_NAME: &str = "Strings";
+
+/// Value of a key in a single language read from platform resources
+#[derive(PartialEq, Eq, Debug)]
+pub(crate) enum ImportedValue {
+    Single(String),
+    /// (quantity, text) pairs in file order
+    Plural(Vec<(String, String)>),
+}
+
+/// Key of all imported languages
+#[derive(Debug)]
+pub(crate) struct ImportedKey {
+    pub(crate) translatable: bool,
+    pub(crate) comment: Option<String>,
+    /// Values keyed by language code
+    pub(crate) values: IndexMap<String, ImportedValue>,
+}
+
+impl Default for ImportedKey {
+    fn default() -> Self {
+        ImportedKey {
+            translatable: true,
+            comment: None,
+            values: IndexMap::new(),
+        }
+    }
+}
+
+/// Twine file content with a single section of the keys in their order. Entries of
+/// `languages` go in the given order, languages a translatable key has no value of get
+/// empty entries.
+pub(crate) fn to_twine_content(
+    keys: &IndexMap<String, ImportedKey>,
+    languages: &[String],
+) -> String {
+    let mut lines = vec![format!("[[{}]]", SECTION_NAME)];
+    for (name, key) in keys {
+        let mut entries: Vec<(String, String)> = Vec::new();
+        for lang in languages {
+            match key.values.get(lang) {
+                Some(ImportedValue::Single(text)) => entries.push((lang.clone(), text.clone())),
+                Some(ImportedValue::Plural(items)) => entries.extend(
+                    items
+                        .iter()
+                        .map(|(quantity, text)| (format!("{}:{}", lang, quantity), text.clone())),
+                ),
+                None if key.translatable => entries.push((lang.clone(), String::new())),
+                None => {}
+            }
+        }
+        if let Some(comment) = &key.comment {
+            entries.push((COMMENT_ATTRIBUTE.to_string(), comment.clone()));
+        }
+        if !key.translatable {
+            entries.push((TRANSLATABLE_ATTRIBUTE.to_string(), "false".to_string()));
+        }
+        lines.extend(format_key(name, &entries));
+    }
+    lines.join("\n") + "\n"
+}
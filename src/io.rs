@@ -0,0 +1,79 @@
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Reads files from Git object store without checking them out
+pub struct GitReader {
+    repo_dir: PathBuf,
+}
+
+impl GitReader {
+    pub fn new(repo_dir: impl AsRef<Path>) -> Self {
+        GitReader {
+            repo_dir: repo_dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Reads file content by `<revision>:<path>` spec, e.g. `HEAD~1:strings.twine`
+    pub fn read(&self, spec: &str) -> io::Result<Vec<u8>> {
+        if !spec.contains(':') {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Expected <revision>:<path>, got \"{}\"", spec),
+            ));
+        }
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.repo_dir)
+            .args(["cat-file", "blob", spec])
+            .output()?;
+        if !output.status.success() {
+            return Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!(
+                    "git cat-file failed for \"{}\": {}",
+                    spec,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+            ));
+        }
+        Ok(output.stdout)
+    }
+}
+
+impl Default for GitReader {
+    fn default() -> Self {
+        GitReader::new(".")
+    }
+}
+
+#[cfg(test)]
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["-c", "user.name=utas", "-c", "user.email=utas@example.com"])
+        .args(args)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn reads_file_from_previous_commit() -> io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    git(dir.path(), &["init", "-q"]);
+    std::fs::write(dir.path().join("strings.twine"), "first")?;
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "first"]);
+    std::fs::write(dir.path().join("strings.twine"), "second")?;
+    git(dir.path(), &["commit", "-q", "-a", "-m", "second"]);
+
+    let reader = GitReader::new(dir.path());
+    assert_eq!(reader.read("HEAD~1:strings.twine")?, b"first");
+    assert_eq!(reader.read("HEAD:strings.twine")?, b"second");
+    assert!(reader.read("HEAD:missing.twine").is_err());
+    assert!(reader.read("strings.twine").is_err());
+
+    Ok(())
+}
@@ -1,16 +1,26 @@
-use anyhow::{anyhow, Ok, Result};
-use std::{collections::HashMap, io::Write, path::Path};
-
-use std::fs;
-
-use crate::parse::{File, Key, LocalizedString, PluralValue, Section, StringValue};
+use anyhow::Result;
+use quick_xml::escape::partial_escape;
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::gen_error::GenError;
+use crate::parse::{CldrCategory, File, PluralValue, StringValue};
+#[cfg(test)]
+use crate::parse::{Key, LocalizedString, Section};
+use crate::platform::to_ios_text;
+use crate::sink::WriteSink;
+#[cfg(test)]
+use crate::test_tools::{key, plain_str, plurals};
 
 #[derive(PartialEq, Eq, Hash, Debug, PartialOrd, Ord, Clone)]
 pub struct Locale {
     value: String,
 }
 
-#[derive(PartialEq, Eq, Debug, PartialOrd, Ord, Clone)]
+#[derive(PartialEq, Eq, Debug, PartialOrd, Ord, Clone, Default)]
 pub struct StrLines {
     value: Vec<Line>,
 }
@@ -25,84 +35,155 @@ pub struct GenResult {
     value: HashMap<Locale, StrLines>,
 }
 
-impl Default for StrLines {
-    fn default() -> Self {
-        Self { value: Default::default() }
-    }
-}
-
 impl GenResult {
     pub fn write(
         &self,
         dir: impl AsRef<Path>,
         default_lang: &Option<String>,
-    ) -> Result<()> {
+        sink: &mut dyn WriteSink,
+    ) -> Result<(), GenError> {
         for (locale, lines) in &self.value {
             if !locale_code_supported_in_ios(&locale.value) {
                 continue;
             }
 
             let subpath = dir.as_ref().join(format!("{}.lproj", locale.value));
-            if !subpath.is_dir() {
-                fs::create_dir(&subpath)?;
-            }
-            let nonPluralsFilePath = subpath.join("Localizable.strings");
-            let mut nonPluralsFile = fs::OpenOptions::new()
-                .write(true)
-                .truncate(true)
-                .create(true)
-                .open(&nonPluralsFilePath)?;
-
-            let pluralsFilePath = subpath.join(format!("Localizable.stringsdict"));
-            let mut pluralsFile = fs::OpenOptions::new()
-                .write(true)
-                .truncate(true)
-                .create(true)
-                .open(&pluralsFilePath)?;
-
-            pluralsFile.write("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n".as_bytes())?;
-            pluralsFile.write("<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n".as_bytes())?;
-            pluralsFile.write("<plist version=\"1.0\">\n".as_bytes())?;
-            pluralsFile.write("  <dict>\n".as_bytes())?;
-
-            for line in &lines.value {
-                match &line.value {
-                    StringValue::Single(text) => {
-                        nonPluralsFile.write(
-                            format!(
-                                "{}\n", 
-                                vec![generate_str_value(&line.name, text)].join("\n")
-                            ).as_bytes()
-                        )?
-                    },
-                    StringValue::Plural { quantities } => {
-                        pluralsFile.write(
-                            format!(
-                                "{}\n",
-                                generate_plural_value(&line.name, quantities).join("\n")
-                            ).as_bytes()
-                        )?
-                    },
-                };
-            }
-            pluralsFile.write("  </dict>\n".as_bytes())?;
-            pluralsFile.write("</plist>\n".as_bytes())?;
+            let mut strings: Vec<u8> = Vec::new();
+            let mut stringsdict: Vec<u8> = Vec::new();
+
+            write_strings(lines, &mut strings)?;
+            write_stringsdict(&locale.value, lines, &mut stringsdict)?;
+            sink.write(&subpath.join("Localizable.strings"), &strings)?;
+            sink.write(&subpath.join("Localizable.stringsdict"), &stringsdict)?;
         }
         Ok(())
     }
 }
 
+impl File {
+    /// Generates Localizable.strings content of every language in memory, keyed by language code.
+    /// Plurals are not included, they go to Localizable.stringsdict
+    pub fn to_ios_strings_map(&self) -> Result<HashMap<String, String>, GenError> {
+        let mut result = HashMap::new();
+        for (locale, lines) in generate_for_file(self)? {
+            let mut buffer: Vec<u8> = Vec::new();
+            write_strings(&lines, &mut buffer)?;
+            let strings = String::from_utf8(buffer)
+                .map_err(|err| GenError::IoError(io::Error::new(io::ErrorKind::InvalidData, err)))?;
+            result.insert(locale.value, strings);
+        }
+        Ok(result)
+    }
+}
+
+/// Writes `<lang>.lproj/Localizable.strings` of every language into `output_dir`.
+/// Only single values are written, plurals go to Localizable.stringsdict
+pub fn write_ios_strings(
+    file: &File,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<(), GenError> {
+    for (locale, lines) in generate_for_file(file)? {
+        if !locale_code_supported_in_ios(&locale.value) {
+            continue;
+        }
+        let path = output_dir
+            .join(format!("{}.lproj", locale.value))
+            .join("Localizable.strings");
+        let mut strings_file: Vec<u8> = Vec::new();
+        write_strings(&lines, &mut strings_file)?;
+        sink.write(&path, &strings_file)?;
+    }
+    Ok(())
+}
+
+/// Writes `<lang>.lproj/Localizable.stringsdict` with plural keys of every language into
+/// `output_dir`. Languages without plurals are left out.
+pub fn write_ios_stringsdict(
+    file: &File,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<(), GenError> {
+    for (locale, lines) in generate_for_file(file)? {
+        let has_plurals = lines
+            .value
+            .iter()
+            .any(|line| matches!(line.value, StringValue::Plural { .. }));
+        if !has_plurals || !locale_code_supported_in_ios(&locale.value) {
+            continue;
+        }
+        let path = output_dir
+            .join(format!("{}.lproj", locale.value))
+            .join("Localizable.stringsdict");
+        let mut stringsdict_file: Vec<u8> = Vec::new();
+        write_stringsdict(&locale.value, &lines, &mut stringsdict_file)?;
+        sink.write(&path, &stringsdict_file)?;
+    }
+    Ok(())
+}
+
+/// Writes plural values of the lines as plist with `NSStringPluralRuleType` dictionaries.
+/// Quantities which CLDR doesn't define for the language are kept, iOS ignores them,
+/// but a warning is printed since they are most likely a mistake.
+fn write_stringsdict<W: Write>(
+    lang: &str,
+    lines: &StrLines,
+    writer: &mut W,
+) -> Result<(), GenError> {
+    writer.write_all("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n".as_bytes())?;
+    writer.write_all("<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n".as_bytes())?;
+    writer.write_all("<plist version=\"1.0\">\n".as_bytes())?;
+    writer.write_all("  <dict>\n".as_bytes())?;
+    for line in &lines.value {
+        if let StringValue::Plural { quantities } = &line.value {
+            if let Some(categories) = CldrCategory::for_language(lang) {
+                for item in quantities {
+                    let defined = item
+                        .quantity_as_cldr_category()
+                        .is_some_and(|category| categories.contains(&category));
+                    if !defined {
+                        eprintln!(
+                            "warning: \"{}\" quantity of \"{}\" is not used by \"{}\" plural rules",
+                            item.quantity, line.name, lang
+                        );
+                    }
+                }
+            }
+            writer.write_all(
+                format!(
+                    "{}\n",
+                    generate_plural_value(&line.name, quantities).join("\n")
+                )
+                .as_bytes(),
+            )?;
+        }
+    }
+    writer.write_all("  </dict>\n".as_bytes())?;
+    writer.write_all("</plist>\n".as_bytes())?;
+    Ok(())
+}
+
+/// Writes single values of the lines in .strings format
+fn write_strings<W: Write>(lines: &StrLines, writer: &mut W) -> Result<(), GenError> {
+    for line in &lines.value {
+        if let StringValue::Single(text) = &line.value {
+            writer.write_all(format!("{}\n", generate_str_value(&line.name, text)).as_bytes())?;
+        }
+    }
+    Ok(())
+}
+
 fn locale_code_supported_in_ios(code: &str) -> bool {
     return true;
 }
 
-pub fn generate(sources: Vec<File>) -> Result<GenResult> {
+pub fn generate(sources: Vec<File>) -> Result<GenResult, GenError> {
     let generated_files: Vec<_> = sources.iter().map( |src| {
         generate_for_file(src)
     }).collect();
 
     if generated_files.is_empty() {
-        return Err(anyhow!("Expected at least one successfuly generated file"));
+        return Err(GenError::EmptyFile);
     }
 
     let mut result: HashMap<Locale, StrLines> = HashMap::new();
@@ -117,20 +198,24 @@ pub fn generate(sources: Vec<File>) -> Result<GenResult> {
     Ok(GenResult { value: result })
 }
 
-fn generate_for_file(source: &File) -> Result<HashMap<Locale, StrLines>> {
-    if source.sections.len() > 1 {
-        panic!("Expected only one section currently")
-    };
-
-    let Some(keys) = source.sections.first().map(|section| &section.keys) else {
-        return Err(anyhow!("Expected at least one section"))
-    };
+fn generate_for_file(source: &File) -> Result<HashMap<Locale, StrLines>, GenError> {
+    if source.sections.is_empty() {
+        return Err(GenError::EmptyFile);
+    }
 
     let mut result: HashMap<Locale, StrLines> = HashMap::new();
-    let keys_len = keys.len();
-    for key in keys {
+    let keys_len = source.sections.iter().map(|section| section.keys.len()).sum();
+    for key in source.sections.iter().flat_map(|section| &section.keys) {
         let str_name = &key.name;
         for str in &key.localizations {
+            if let StringValue::Plural { quantities } = &str.value {
+                if let Some(item) = quantities
+                    .iter()
+                    .find(|item| item.quantity_as_cldr_category().is_none())
+                {
+                    return Err(GenError::UnsupportedPluralCategory(item.quantity.clone()));
+                }
+            }
             let code = Locale {
                 value: str.language_code.clone(),
             };
@@ -153,10 +238,22 @@ fn generate_for_file(source: &File) -> Result<HashMap<Locale, StrLines>> {
 }
 
 fn generate_str_value(str_name: &str, str_value: &str) -> String {
-    String::from(format!(
+    format!(
         "\"{}\" = \"{}\";\n",
-        str_name, str_value
-    ))
+        str_name,
+        to_strings_text(str_value)
+    )
+}
+
+/// Reverts Android specific escaping done while parsing, e.g. `&amp;` becomes `&`,
+/// brings back `%@` placeholders and escapes quotes which would end the .strings value
+fn to_strings_text(text: &str) -> String {
+    to_ios_text(text).replace('"', "\\\"")
+}
+
+/// The same as `to_strings_text` but escaped for `<string>` of .stringsdict plist
+fn to_stringsdict_text(text: &str) -> String {
+    partial_escape(&to_ios_text(text)).into_owned()
 }
 
 fn generate_plural_value(str_name: &String, items: &Vec<PluralValue>) -> Vec<String> {
@@ -178,7 +275,10 @@ fn generate_plural_value(str_name: &String, items: &Vec<PluralValue>) -> Vec<Str
 
     for item in items {
         result.push(format!("        <key>{}</key>", item.quantity));
-        result.push(format!("        <string>{}</string>", item.text));
+        result.push(format!(
+            "        <string>{}</string>",
+            to_stringsdict_text(&item.text)
+        ));
     }
     result.push("      </dict>".to_string());
     result.push("    </dict>".to_string());
@@ -186,56 +286,31 @@ fn generate_plural_value(str_name: &String, items: &Vec<PluralValue>) -> Vec<Str
 }
 
 // -----------------------------  test tools ------------------------------
-fn plain_str(lang: &str, txt: &str) -> LocalizedString {
-    LocalizedString {
-        language_code: lang.to_string(),
-        value: StringValue::Single(txt.to_string()),
-    }
-}
-
-fn plurals(lang: &str, quantities: Vec<PluralValue>) -> LocalizedString {
-    LocalizedString {
-        language_code: lang.to_string(),
-        value: StringValue::Plural { quantities },
-    }
-}
-
-fn plural_val(quantity: &str, text: &str) -> PluralValue {
-    PluralValue {
-        quantity: quantity.to_string(),
-        text: text.to_string(),
-    }
-}
-
-fn key(name: &str, localizations: Vec<LocalizedString>) -> Key {
-    Key {
-        name: name.to_string(),
-        localizations: localizations,
-    }
-}
-
+#[cfg(test)]
 fn sorted_strings(input: GenResult) -> Vec<(Locale, StrLines)> {
     let mut result = Vec::with_capacity(input.value.len());
     let mut keys: Vec<&Locale> = input.value.keys().collect();
     keys.sort();
     for key in keys {
-        result.push((key.clone(), input.value.get(&key).unwrap().clone()))
+        result.push((key.clone(), input.value.get(key).unwrap().clone()))
     }
     result
 }
 
+#[cfg(test)]
 fn single(name: &str, text: &str) -> Line {
-    return Line {
+    Line {
         name: name.to_string(),
         value: StringValue::Single(text.to_string()),
-    };
+    }
 }
 
+#[cfg(test)]
 fn plural(name: &str, items: Vec<PluralValue>) -> Line {
-    return Line {
+    Line {
         name: name.to_string(),
         value: StringValue::Plural { quantities: items },
-    };
+    }
 }
 
 // ------------------------------- tests -----------------------------------
@@ -302,10 +377,16 @@ fn generate_3_lang_2_str() -> Result<()> {
         Key {
             name: "find".to_string(),
             localizations: localizations_find,
+            max_length: None,
+            comment: None,
+            translatable: true,
         },
         Key {
             name: "search".to_string(),
             localizations: localizations_search,
+            max_length: None,
+            comment: None,
+            translatable: true,
         },
     ];
     let source = File {
@@ -383,12 +464,42 @@ fn generate_error_if_empty_sections() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn generate_keys_of_all_sections() -> Result<()> {
+    let source = File {
+        sections: vec![
+            Section {
+                keys: vec![key("login", vec![plain_str("en", "Login")])],
+            },
+            Section {
+                keys: vec![key("logout", vec![plain_str("en", "Logout")])],
+            },
+        ],
+    };
+
+    let actual = sorted_strings(generate(vec![source])?);
+    let expected = vec![(
+        Locale {
+            value: "en".to_string(),
+        },
+        StrLines {
+            value: vec![single("login", "Login"), single("logout", "Logout")],
+        },
+    )];
+    assert_eq!(actual, expected);
+
+    Ok(())
+}
+
 #[test]
 fn generate_1_lang_1_simple_plural() -> Result<()> {
-    let localizations_songs = vec![plurals("mn", vec![plural_val("other", "%d дуу")])];
+    let localizations_songs = vec![plurals("mn", vec![PluralValue::new("other", "%d дуу")])];
     let keys = vec![Key {
         name: "songs".to_string(),
         localizations: localizations_songs,
+        max_length: None,
+        comment: None,
+        translatable: true,
     }];
     let source = File {
         sections: vec![Section { keys }],
@@ -419,19 +530,25 @@ fn generate_1_lang_1_str_1_plurals() -> Result<()> {
     let localizations_cows = vec![plurals(
         "en",
         vec![
-            plural_val("one", "%d cow"),
-            plural_val("two", "%d cows"),
-            plural_val("other", "33 copy-on-writes"),
+            PluralValue::new("one", "%d cow"),
+            PluralValue::new("two", "%d cows"),
+            PluralValue::new("other", "33 copy-on-writes"),
         ],
     )];
     let keys = vec![
         Key {
             name: "chicken".to_string(),
             localizations: localizations_chicken,
+            max_length: None,
+            comment: None,
+            translatable: true,
         },
         Key {
             name: "cows".to_string(),
             localizations: localizations_cows,
+            max_length: None,
+            comment: None,
+            translatable: true,
         },
     ];
     let source = File {
@@ -471,3 +588,116 @@ fn generate_1_lang_1_str_1_plurals() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn generate_strings_map_for_all_langs() -> Result<()> {
+    let source = File {
+        sections: vec![Section {
+            keys: vec![
+                key("kek", vec![plain_str("ru", "Кек"), plain_str("en", "Kek")]),
+                key(
+                    "days",
+                    vec![plurals("en", vec![PluralValue::new("other", "%d days")])],
+                ),
+            ],
+        }],
+    };
+
+    let actual = source.to_ios_strings_map()?;
+    assert_eq!(actual.len(), 2);
+    assert_eq!(actual["en"], "\"kek\" = \"Kek\";\n\n");
+    assert_eq!(actual["ru"], "\"kek\" = \"Кек\";\n\n");
+
+    Ok(())
+}
+
+#[test]
+fn write_strings_of_every_lang_without_android_escaping() -> Result<()> {
+    let source = File {
+        sections: vec![Section {
+            keys: vec![
+                key(
+                    "terms",
+                    vec![
+                        plain_str("en", "Terms &amp; \\\"conditions\\\""),
+                        plain_str("fr", "Conditions d\\'utilisation"),
+                    ],
+                ),
+                key(
+                    "days",
+                    vec![plurals("en", vec![PluralValue::new("other", "%d days")])],
+                ),
+            ],
+        }],
+    };
+    let dir = tempfile::tempdir()?;
+
+    write_ios_strings(&source, dir.path(), &mut crate::sink::FsSink::default())?;
+
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("en.lproj/Localizable.strings"))?,
+        "\"terms\" = \"Terms & \\\"conditions\\\"\";\n\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("fr.lproj/Localizable.strings"))?,
+        "\"terms\" = \"Conditions d'utilisation\";\n\n"
+    );
+    assert!(!dir.path().join("en.lproj/Localizable.stringsdict").exists());
+
+    Ok(())
+}
+
+#[test]
+fn write_stringsdict_of_langs_with_plurals() -> Result<()> {
+    let source = File {
+        sections: vec![Section {
+            keys: vec![
+                key("kek", vec![plain_str("ru", "Кек"), plain_str("en", "Kek")]),
+                key(
+                    "days",
+                    vec![plurals(
+                        "en",
+                        vec![PluralValue::new("one", "%d day"), PluralValue::new("few", "%d days")],
+                    )],
+                ),
+            ],
+        }],
+    };
+    let dir = tempfile::tempdir()?;
+
+    write_ios_stringsdict(&source, dir.path(), &mut crate::sink::FsSink::default())?;
+
+    let actual = std::fs::read_to_string(dir.path().join("en.lproj/Localizable.stringsdict"))?;
+    assert!(actual.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"));
+    assert!(actual.contains("<key>NSStringFormatSpecTypeKey</key>"));
+    assert!(actual.contains("<string>NSStringPluralRuleType</string>"));
+    assert!(actual.contains("<key>NSStringFormatValueTypeKey</key>"));
+    // "few" isn't used in English but is kept
+    assert!(actual.contains("        <key>one</key>\n        <string>%d day</string>"));
+    assert!(actual.contains("        <key>few</key>\n        <string>%d days</string>"));
+    assert!(actual.ends_with("  </dict>\n</plist>\n"));
+    assert!(!dir.path().join("ru.lproj").exists());
+    assert!(!dir.path().join("en.lproj/Localizable.strings").exists());
+
+    Ok(())
+}
+
+#[test]
+fn write_stringsdict_values_without_android_escaping() -> Result<()> {
+    let source = crate::parse::parse_str(
+        "[[Section]]
+  [files]
+    en:one = %s's file \"x\" & co
+    en:other = %s's files \"x\" & co
+",
+    )?;
+    let dir = tempfile::tempdir()?;
+
+    write_ios_stringsdict(&source, dir.path(), &mut crate::sink::FsSink::default())?;
+
+    let actual = std::fs::read_to_string(dir.path().join("en.lproj/Localizable.stringsdict"))?;
+    assert!(actual.contains("        <key>one</key>\n        <string>%@'s file \"x\" &amp; co</string>"));
+    assert!(actual.contains("        <key>other</key>\n        <string>%@'s files \"x\" &amp; co</string>"));
+
+    Ok(())
+}
@@ -0,0 +1,414 @@
+use anyhow::{anyhow, Result};
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use quick_xml::events::Event;
+use quick_xml::name::QName;
+use quick_xml::Reader;
+use regex::{Captures, Regex};
+use std::{fs, path::Path};
+
+use crate::fs_util::read_to_string_with_encoding;
+use crate::import::{to_twine_content, ImportedKey, ImportedValue};
+use crate::lang::validate_lang_code;
+use crate::parse::CldrCategory;
+
+/// `"key" = "value";` entry of .strings file
+#[derive(PartialEq, Eq, Debug)]
+struct StringsEntry {
+    key: String,
+    value: String,
+    /// Text of comments right above the entry joined with spaces, twine values are single line
+    comment: Option<String>,
+}
+
+/// Value of .stringsdict property list
+#[derive(PartialEq, Eq, Debug)]
+enum PlistValue {
+    String(String),
+    Dict(Vec<(String, PlistValue)>),
+    /// Values of other types which plurals don't use, e.g. `<integer>`
+    Other,
+}
+
+/// Reads `Localizable.strings` and `Localizable.stringsdict` files of `<lang>.lproj`
+/// directories of `lproj_parent_dir` and generates twine file content of them. Plurals of
+/// .stringsdict become `lang:quantity` entries of the key with the same name, keys which
+/// some languages don't have get empty entries of those languages. Comments above
+/// .strings entries become `comment` attributes if `keep_comments` is set.
+pub fn import_ios(lproj_parent_dir: &Path, keep_comments: bool) -> Result<String> {
+    let mut dirs: Vec<(String, String)> = Vec::new();
+    for entry in fs::read_dir(lproj_parent_dir)? {
+        let entry = entry?;
+        let dir_name = entry.file_name().to_string_lossy().to_string();
+        // Base.lproj holds storyboards rather than strings of a language
+        if let Some(lang) = dir_name.strip_suffix(".lproj") {
+            if entry.file_type()?.is_dir() && validate_lang_code(lang).is_ok() {
+                dirs.push((dir_name.clone(), lang.to_string()));
+            }
+        }
+    }
+    dirs.sort();
+    if dirs.is_empty() {
+        return Err(anyhow!(
+            "No .lproj directories found in {}",
+            lproj_parent_dir.display()
+        ));
+    }
+
+    let mut keys: IndexMap<String, ImportedKey> = IndexMap::new();
+    for (dir_name, lang) in &dirs {
+        let dir = lproj_parent_dir.join(dir_name);
+        let strings_path = dir.join("Localizable.strings");
+        if strings_path.is_file() {
+            let (content, _) = read_to_string_with_encoding(&strings_path, None)?;
+            let entries = parse_strings(&content)
+                .map_err(|err| anyhow!("Cannot import {}: {}", strings_path.display(), err))?;
+            for entry in entries {
+                let key = keys.entry(entry.key).or_default();
+                if keep_comments && key.comment.is_none() {
+                    key.comment = entry.comment;
+                }
+                let value = ImportedValue::Single(to_twine_text(&entry.value));
+                key.values.insert(lang.clone(), value);
+            }
+        }
+        let stringsdict_path = dir.join("Localizable.stringsdict");
+        if stringsdict_path.is_file() {
+            let (content, _) = read_to_string_with_encoding(&stringsdict_path, None)?;
+            let plurals = parse_stringsdict(&content)
+                .map_err(|err| anyhow!("Cannot import {}: {}", stringsdict_path.display(), err))?;
+            for (name, items) in plurals {
+                let items = items
+                    .into_iter()
+                    .map(|(quantity, text)| (quantity, to_twine_text(&text)))
+                    .collect();
+                let key = keys.entry(name).or_default();
+                key.values
+                    .insert(lang.clone(), ImportedValue::Plural(items));
+            }
+        }
+    }
+
+    let languages: Vec<String> = dirs.into_iter().map(|(_, lang)| lang).collect();
+    Ok(to_twine_content(&keys, &languages))
+}
+
+/// Entries of .strings file content in file order. Keys and values are quoted strings
+/// with `\"`, `\\`, `\n` and other escapes kept as is, keys may also be unquoted words.
+fn parse_strings(content: &str) -> Result<Vec<StringsEntry>> {
+    let mut result: Vec<StringsEntry> = Vec::new();
+    let mut comments: Vec<String> = Vec::new();
+    let mut chars = content.char_indices().peekable();
+    let line_of = |index: usize| content[..index].lines().count().max(1);
+    while let Some((index, char)) = chars.next() {
+        match char {
+            char if char.is_whitespace() => {}
+            '/' if matches!(chars.peek(), Some((_, '*'))) => {
+                chars.next();
+                let start = index + 2;
+                let end = content[start..]
+                    .find("*/")
+                    .map(|end| start + end)
+                    .ok_or_else(|| anyhow!("unterminated comment at line {}", line_of(index)))?;
+                let comment: Vec<&str> = content[start..end].split_whitespace().collect();
+                comments.push(comment.join(" "));
+                while chars.next_if(|(next, _)| *next < end + 2).is_some() {}
+            }
+            '/' if matches!(chars.peek(), Some((_, '/'))) => {
+                let end = content[index..]
+                    .find('\n')
+                    .map_or(content.len(), |end| index + end);
+                comments.push(content[index + 2..end].trim().to_string());
+                while chars.next_if(|(next, _)| *next < end).is_some() {}
+            }
+            _ => {
+                let key = match char {
+                    '"' => read_quoted(&mut chars),
+                    _ => {
+                        let mut word = char.to_string();
+                        while let Some((_, char)) = chars.next_if(|(_, char)| is_word_char(*char)) {
+                            word.push(char);
+                        }
+                        Some(word)
+                    }
+                };
+                let expected = |what: &str| anyhow!("expected {} at line {}", what, line_of(index));
+                let key = key.ok_or_else(|| expected("closing quote of key"))?;
+                skip_whitespace(&mut chars);
+                if chars.next().map(|(_, char)| char) != Some('=') {
+                    return Err(expected("\"=\" after key"));
+                }
+                skip_whitespace(&mut chars);
+                if chars.next().map(|(_, char)| char) != Some('"') {
+                    return Err(expected("quoted value"));
+                }
+                let value = read_quoted(&mut chars).ok_or_else(|| expected("closing quote"))?;
+                skip_whitespace(&mut chars);
+                if chars.next().map(|(_, char)| char) != Some(';') {
+                    return Err(expected("\";\" after value"));
+                }
+                let comment = (!comments.is_empty()).then(|| comments.join(" "));
+                comments.clear();
+                result.push(StringsEntry {
+                    key,
+                    value,
+                    comment,
+                });
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Reads a string up to the closing quote, the opening quote is already read
+fn read_quoted(chars: &mut impl Iterator<Item = (usize, char)>) -> Option<String> {
+    let mut result = String::new();
+    while let Some((_, char)) = chars.next() {
+        match char {
+            '"' => return Some(result),
+            '\\' => {
+                result.push(char);
+                result.push(chars.next()?.1);
+            }
+            char => result.push(char),
+        }
+    }
+    None
+}
+
+fn skip_whitespace<I: Iterator<Item = (usize, char)>>(chars: &mut std::iter::Peekable<I>) {
+    while chars.next_if(|(_, char)| char.is_whitespace()).is_some() {}
+}
+
+fn is_word_char(char: char) -> bool {
+    char.is_alphanumeric() || matches!(char, '_' | '.' | '-' | '$' | ':' | '/')
+}
+
+/// Name of .stringsdict key with (quantity, text) pairs of its plural rule
+type PluralKey = (String, Vec<(String, String)>);
+
+/// Plural keys of .stringsdict content in file order with quantities of the first
+/// `NSStringPluralRuleType` dictionary of each key
+fn parse_stringsdict(xml: &str) -> Result<Vec<PluralKey>> {
+    let mut reader = Reader::from_str(xml);
+    let root = loop {
+        match reader.read_event()? {
+            Event::Start(element) if element.name().as_ref() == b"dict" => {
+                break read_dict(&mut reader)?
+            }
+            Event::Eof => return Ok(Vec::new()),
+            _ => {}
+        }
+    };
+    let PlistValue::Dict(entries) = root else {
+        return Ok(Vec::new());
+    };
+    let result = entries
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let PlistValue::Dict(entries) = value else {
+                return None;
+            };
+            let quantities = entries.into_iter().find_map(|(_, value)| match value {
+                PlistValue::Dict(rule) if is_plural_rule(&rule) => Some(
+                    rule.into_iter()
+                        .filter_map(|(quantity, value)| match value {
+                            PlistValue::String(text)
+                                if CldrCategory::from_quantity(&quantity).is_some() =>
+                            {
+                                Some((quantity, text))
+                            }
+                            _ => None,
+                        })
+                        .collect(),
+                ),
+                _ => None,
+            })?;
+            Some((name, quantities))
+        })
+        .collect();
+    Ok(result)
+}
+
+fn is_plural_rule(entries: &[(String, PlistValue)]) -> bool {
+    entries.iter().any(|(key, value)| {
+        key == "NSStringFormatSpecTypeKey"
+            && *value == PlistValue::String("NSStringPluralRuleType".to_string())
+    })
+}
+
+/// Reads `<key>`/value pairs up to `</dict>`, the `<dict>` start is already read
+fn read_dict(reader: &mut Reader<&[u8]>) -> Result<PlistValue> {
+    let mut entries: Vec<(String, PlistValue)> = Vec::new();
+    let mut key: Option<String> = None;
+    loop {
+        let value = match reader.read_event()? {
+            Event::Start(element) => match element.name().as_ref() {
+                b"key" => {
+                    key = Some(read_text(reader, b"key")?);
+                    continue;
+                }
+                b"string" => PlistValue::String(read_text(reader, b"string")?),
+                b"dict" => read_dict(reader)?,
+                name => {
+                    reader.read_to_end(QName(name))?;
+                    PlistValue::Other
+                }
+            },
+            Event::Empty(element) => match element.name().as_ref() {
+                b"string" => PlistValue::String(String::new()),
+                b"dict" => PlistValue::Dict(Vec::new()),
+                _ => PlistValue::Other,
+            },
+            Event::End(element) if element.name().as_ref() == b"dict" => {
+                return Ok(PlistValue::Dict(entries))
+            }
+            Event::Eof => return Err(anyhow!("unexpected end of file inside <dict>")),
+            _ => continue,
+        };
+        let key = key.take().ok_or_else(|| anyhow!("value without <key>"))?;
+        entries.push((key, value));
+    }
+}
+
+/// Text of the element up to its end tag, the start tag is already read
+fn read_text(reader: &mut Reader<&[u8]>, tag: &[u8]) -> Result<String> {
+    let mut text = String::new();
+    loop {
+        match reader.read_event()? {
+            Event::Text(value) => text.push_str(&value.unescape()?),
+            Event::CData(value) => text.push_str(&String::from_utf8_lossy(&value)),
+            Event::End(element) if element.name().as_ref() == tag => return Ok(text),
+            Event::Eof => {
+                return Err(anyhow!(
+                    "unexpected end of file inside <{}>",
+                    String::from_utf8_lossy(tag)
+                ))
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Reverts escaping of quotes and replaces `%s` placeholders with twine `%@`
+fn to_twine_text(value: &str) -> String {
+    lazy_static! {
+        static ref STRING_PLACEHOLDER_RE: Regex =
+            Regex::new(r"%%|%(\d+\$)?([-+0#,]?\d*(\.\d+)?)s").unwrap();
+    }
+    let value = value.replace("\\\"", "\"");
+    STRING_PLACEHOLDER_RE
+        .replace_all(&value, |caps: &Captures| match caps.get(2) {
+            Some(_) => format!(
+                "%{}{}@",
+                caps.get(1).map_or("", |position| position.as_str()),
+                &caps[2]
+            ),
+            None => caps[0].to_string(),
+        })
+        .into_owned()
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn parses_strings_files() -> Result<()> {
+    let content = r#"/* Title of the
+   login screen */
+"login_title" = "Log in";
+// Greeting
+// with a name
+"greeting" = "Hi %@, \"friend\"";
+plain_key="a = b; c";
+"#;
+    assert_eq!(
+        parse_strings(content)?,
+        vec![
+            StringsEntry {
+                key: "login_title".to_string(),
+                value: "Log in".to_string(),
+                comment: Some("Title of the login screen".to_string()),
+            },
+            StringsEntry {
+                key: "greeting".to_string(),
+                value: r#"Hi %@, \"friend\""#.to_string(),
+                comment: Some("Greeting with a name".to_string()),
+            },
+            StringsEntry {
+                key: "plain_key".to_string(),
+                value: "a = b; c".to_string(),
+                comment: None,
+            },
+        ]
+    );
+    assert!(parse_strings("\"key\" = \"value\"").is_err());
+    assert!(parse_strings("\"key\" = \"value;\n").is_err());
+    Ok(())
+}
+
+#[test]
+fn imports_ios_resources() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    fs::create_dir_all(dir.path().join("en.lproj"))?;
+    fs::create_dir_all(dir.path().join("de.lproj"))?;
+    fs::create_dir_all(dir.path().join("Base.lproj"))?;
+    fs::write(
+        dir.path().join("en.lproj/Localizable.strings"),
+        "/* Login button */\n\"login\" = \"Log in as %1$s\";\n\"logout\" = \"Log out\";\n",
+    )?;
+    fs::write(
+        dir.path().join("de.lproj/Localizable.strings"),
+        "\"login\" = \"Als %1$s anmelden\";\n",
+    )?;
+    fs::write(
+        dir.path().join("en.lproj/Localizable.stringsdict"),
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+  <dict>
+    <key>days</key>
+    <dict>
+      <key>NSStringLocalizedFormatKey</key>
+      <string>%#@value@</string>
+      <key>value</key>
+      <dict>
+        <key>NSStringFormatSpecTypeKey</key>
+        <string>NSStringPluralRuleType</string>
+        <key>NSStringFormatValueTypeKey</key>
+        <string>d</string>
+        <key>one</key>
+        <string>%d day</string>
+        <key>other</key>
+        <string>%d days</string>
+      </dict>
+    </dict>
+  </dict>
+</plist>
+"#,
+    )?;
+
+    assert_eq!(
+        import_ios(dir.path(), true)?,
+        "[[Strings]]
+  [login]
+    de = Als %1$@ anmelden
+    en = Log in as %1$@
+    comment = Login button
+  [logout]
+    de =
+    en = Log out
+  [days]
+    de =
+    en:one = %d day
+    en:other = %d days
+"
+    );
+    assert!(!import_ios(dir.path(), false)?.contains("comment"));
+
+    // Legacy .strings files are often UTF-16 with BOM
+    let utf16: Vec<u8> = "\u{feff}\"login\" = \"Anmelden\";\n"
+        .encode_utf16()
+        .flat_map(|unit| unit.to_le_bytes())
+        .collect();
+    fs::write(dir.path().join("de.lproj/Localizable.strings"), utf16)?;
+    assert!(import_ios(dir.path(), false)?.contains("    de = Anmelden\n"));
+    Ok(())
+}
@@ -0,0 +1,149 @@
+use std::fmt::Write as _;
+use std::path::Path;
+
+use indexmap::IndexMap;
+
+use crate::gen_error::GenError;
+use crate::parse::{File, PluralValue, StringValue};
+#[cfg(test)]
+use crate::parse::Section;
+use crate::platform::{decode_android_escapes, unescape_android};
+use crate::sink::WriteSink;
+#[cfg(test)]
+use crate::test_tools::key;
+
+/// Writes `messages_<lang>.properties` of every language into `output_dir`, `-` of language
+/// codes is replaced with `_` as `ResourceBundle` expects, e.g. `messages_pt_BR.properties`.
+/// `.properties` have no plurals, so every quantity becomes a separate property named
+/// `key_name[quantity]`, e.g. `days[one]` and `days[other]`.
+pub fn write_java_properties(
+    file: &File,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<(), GenError> {
+    for (lang, content) in generate(file)? {
+        let path = output_dir.join(format!("messages_{}.properties", lang.replace('-', "_")));
+        sink.write(&path, content.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Generates content of .properties files keyed by language code in order of their first appearance
+fn generate(file: &File) -> Result<IndexMap<String, String>, GenError> {
+    if file.sections.is_empty() {
+        return Err(GenError::EmptyFile);
+    }
+
+    let mut result: IndexMap<String, String> = IndexMap::new();
+    for key in file.sections.iter().flat_map(|section| &section.keys) {
+        for str in &key.localizations {
+            let content = result.entry(str.language_code.clone()).or_default();
+            match &str.value {
+                StringValue::Single(text) => {
+                    push_property(content, &key.name, text);
+                }
+                StringValue::Array(items) => {
+                    for (index, item) in items.iter().enumerate() {
+                        push_property(content, &format!("{}[{}]", key.name, index), item);
+                    }
+                }
+                StringValue::Plural { quantities } => {
+                    let mut items: Vec<&PluralValue> = quantities.iter().collect();
+                    items.sort_by_key(|item| item.quantity_as_cldr_category());
+                    for item in items {
+                        let name = format!("{}[{}]", key.name, item.quantity);
+                        push_property(content, &name, &item.text);
+                    }
+                }
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn push_property(content: &mut String, name: &str, text: &str) {
+    let value = decode_android_escapes(&unescape_android(text));
+    content.push_str(&escape_properties(name, true));
+    content.push('=');
+    content.push_str(&escape_properties(&value, false));
+    content.push('\n');
+}
+
+/// Escapes text according to `java.util.Properties`, characters out of printable ASCII are
+/// written as `\uXXXX` UTF-16 code units. Keys have separators and comment starts escaped as well.
+fn escape_properties(text: &str, is_key: bool) -> String {
+    let mut result = String::with_capacity(text.len());
+    for (index, char) in text.chars().enumerate() {
+        match char {
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            '\r' => result.push_str("\\r"),
+            '\u{c}' => result.push_str("\\f"),
+            // Leading whitespace of values is skipped while loading
+            ' ' if is_key || index == 0 => result.push_str("\\ "),
+            '=' | ':' | '#' | '!' if is_key => {
+                result.push('\\');
+                result.push(char);
+            }
+            ' '..='~' => result.push(char),
+            _ => {
+                let mut units = [0u16; 2];
+                for unit in char.encode_utf16(&mut units) {
+                    // Writing to String can't fail
+                    write!(result, "\\u{:04X}", unit).unwrap();
+                }
+            }
+        }
+    }
+    result
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn generate_properties_per_lang() -> Result<(), GenError> {
+    let single = |text: &str| StringValue::Single(text.to_string());
+    let keys = vec![
+        key(
+            "greeting",
+            vec![
+                ("en", single("Hi %1$s,\\n\\tit\\'s a \\\\ &amp; more")),
+                ("ru", single("Привет 😀")),
+            ],
+        ),
+        key(
+            "key with=separators",
+            vec![("en", single(" leading space"))],
+        ),
+        key(
+            "days",
+            vec![(
+                "en",
+                StringValue::plural()
+                    .quantity("other", "%d days")
+                    .quantity("one", "%d day")
+                    .build(),
+            )],
+        ),
+    ];
+    let source = File {
+        sections: vec![Section { keys }],
+    };
+
+    let actual = generate(&source)?;
+
+    assert_eq!(
+        actual["en"],
+        "greeting=Hi %1$s,\\n\\tit's a \\\\ & more
+key\\ with\\=separators=\\ leading space
+days[one]=%d day
+days[other]=%d days
+"
+    );
+    assert_eq!(
+        actual["ru"],
+        "greeting=\\u041F\\u0440\\u0438\\u0432\\u0435\\u0442 \\uD83D\\uDE00\n"
+    );
+
+    Ok(())
+}
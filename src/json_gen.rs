@@ -0,0 +1,180 @@
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+use crate::gen_error::GenError;
+use crate::parse::{File, PluralValue, StringValue};
+#[cfg(test)]
+use crate::parse::Section;
+use crate::platform::{decode_android_escapes, unescape_android};
+use crate::sink::WriteSink;
+#[cfg(test)]
+use crate::test_tools::key;
+
+lazy_static! {
+    static ref HTML_ENTITY_RE: Regex =
+        Regex::new(r"&(lt|gt|amp|quot|apos|#[0-9]+|#[xX][0-9a-fA-F]+);").unwrap();
+}
+
+/// Writes `<lang>.json` of every language into `output_dir`. Single strings become
+/// `"key_name": "value"` and plurals become `"key_name": { "one": "...", "other": "..." }`.
+pub fn write_json(
+    file: &File,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<(), GenError> {
+    write(file, output_dir, false, sink)
+}
+
+/// Same as `write_json`, but every quantity of plurals becomes a separate flat key
+/// `key_name__<quantity>` for libraries which don't support nested objects
+pub fn write_json_flat_plurals(
+    file: &File,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<(), GenError> {
+    write(file, output_dir, true, sink)
+}
+
+fn write(
+    file: &File,
+    output_dir: &Path,
+    flat_plurals: bool,
+    sink: &mut dyn WriteSink,
+) -> Result<(), GenError> {
+    for (lang, values) in generate(file, flat_plurals)? {
+        let path = output_dir.join(format!("{}.json", lang));
+        // Serializing of serde_json::Value can't fail
+        let content = serde_json::to_string_pretty(&values).unwrap() + "\n";
+        sink.write(&path, content.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Generates JSON objects keyed by language code in order of their first appearance
+fn generate(file: &File, flat_plurals: bool) -> Result<IndexMap<String, Value>, GenError> {
+    if file.sections.is_empty() {
+        return Err(GenError::EmptyFile);
+    }
+
+    let mut result: IndexMap<String, Map<String, Value>> = IndexMap::new();
+    for key in file.sections.iter().flat_map(|section| &section.keys) {
+        for str in &key.localizations {
+            let values = result.entry(str.language_code.clone()).or_default();
+            match &str.value {
+                StringValue::Single(text) => {
+                    values.insert(key.name.clone(), Value::String(to_json_text(text)));
+                }
+                StringValue::Array(items) => {
+                    let items = items.iter().map(|item| Value::String(to_json_text(item)));
+                    values.insert(key.name.clone(), Value::Array(items.collect()));
+                }
+                StringValue::Plural { quantities } => {
+                    let mut items: Vec<&PluralValue> = quantities.iter().collect();
+                    items.sort_by_key(|item| item.quantity_as_cldr_category());
+                    let forms = items.into_iter().map(|item| {
+                        (
+                            item.quantity.clone(),
+                            Value::String(to_json_text(&item.text)),
+                        )
+                    });
+                    if flat_plurals {
+                        for (quantity, text) in forms {
+                            values.insert(format!("{}__{}", key.name, quantity), text);
+                        }
+                    } else {
+                        values.insert(key.name.clone(), Value::Object(forms.collect()));
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(result
+        .into_iter()
+        .map(|(lang, values)| (lang, Value::Object(values)))
+        .collect())
+}
+
+/// Reverts Android specific escaping, turns escape sequences like `\n` into characters
+/// and decodes HTML entities back to characters
+fn to_json_text(text: &str) -> String {
+    let text = decode_android_escapes(&unescape_android(text));
+    HTML_ENTITY_RE
+        .replace_all(&text, |caps: &Captures| {
+            let entity = &caps[1];
+            let decoded = match entity {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => entity[1..].parse().ok(),
+                }
+                .and_then(char::from_u32),
+            };
+            decoded.map_or_else(|| caps[0].to_string(), String::from)
+        })
+        .into_owned()
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn generate_json_with_nested_and_flat_plurals() -> Result<(), GenError> {
+    use serde_json::json;
+
+    let keys = vec![
+        key(
+            "greeting",
+            vec![
+                (
+                    "en",
+                    StringValue::Single(
+                        "Hi %1$s, it\\'s &lt;b&gt; &amp; &#169; &#x1F600;\\nnext \\\\ end".to_string(),
+                    ),
+                ),
+                ("de", StringValue::Single("Hallo %1$s".to_string())),
+            ],
+        ),
+        key(
+            "days",
+            vec![(
+                "en",
+                StringValue::plural()
+                    .quantity("other", "%d days")
+                    .quantity("one", "%d day")
+                    .build(),
+            )],
+        ),
+    ];
+    let source = File {
+        sections: vec![Section { keys }],
+    };
+
+    let actual = generate(&source, false)?;
+    assert_eq!(actual.keys().collect::<Vec<_>>(), vec!["en", "de"]);
+    assert_eq!(
+        actual["en"],
+        json!({
+            "greeting": "Hi %1$s, it's <b> & © 😀\nnext \\ end",
+            "days": { "one": "%d day", "other": "%d days" },
+        })
+    );
+    assert_eq!(actual["de"], json!({ "greeting": "Hallo %1$s" }));
+
+    let actual = generate(&source, true)?;
+    assert_eq!(
+        serde_json::to_string_pretty(&actual["en"]).unwrap(),
+        r#"{
+  "greeting": "Hi %1$s, it's <b> & © 😀\nnext \\ end",
+  "days__one": "%d day",
+  "days__other": "%d days"
+}"#
+    );
+
+    Ok(())
+}
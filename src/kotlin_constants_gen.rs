@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::gen_error::GenError;
+use crate::parse::File;
+use crate::sink::WriteSink;
+
+/// Name of the generated object if it's not set
+pub const DEFAULT_OBJECT_NAME: &str = "StringKeys";
+
+/// Hard keywords which can't be used as identifiers unless escaped with backticks
+const KOTLIN_KEYWORDS: &[&str] = &[
+    "as",
+    "break",
+    "class",
+    "continue",
+    "do",
+    "else",
+    "false",
+    "for",
+    "fun",
+    "if",
+    "in",
+    "interface",
+    "is",
+    "null",
+    "object",
+    "package",
+    "return",
+    "super",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typealias",
+    "typeof",
+    "val",
+    "var",
+    "when",
+    "while",
+];
+
+/// Writes Kotlin file with `object StringKeys { const val key_name = "key_name" }` having
+/// a constant of every key name into `output_path`. Empty `package` writes no package
+/// declaration.
+pub fn write_kotlin_constants(
+    file: &File,
+    package: &str,
+    output_path: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<(), GenError> {
+    write_kotlin_constants_object(file, package, DEFAULT_OBJECT_NAME, output_path, sink)
+}
+
+/// Same as `write_kotlin_constants`, but the object is named `object_name`
+pub fn write_kotlin_constants_object(
+    file: &File,
+    package: &str,
+    object_name: &str,
+    output_path: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<(), GenError> {
+    let content = generate(file, package, object_name)?;
+    sink.write(output_path, content.as_bytes())?;
+    Ok(())
+}
+
+fn generate(file: &File, package: &str, object_name: &str) -> Result<String, GenError> {
+    if file.sections.is_empty() {
+        return Err(GenError::EmptyFile);
+    }
+
+    let mut result = String::new();
+    if !package.is_empty() {
+        result.push_str(&format!("package {}\n\n", package));
+    }
+    result.push_str(&format!("object {} {{\n", object_name));
+    // Constant names by key names, a plural and a single key may share the name
+    let mut constants: HashMap<String, &str> = HashMap::new();
+    for key in file.sections.iter().flat_map(|section| &section.keys) {
+        let name = constant_name(&key.name);
+        match constants.get(&name) {
+            Some(key_name) if *key_name == key.name => continue,
+            // Different keys like `a-b` and `a_b` can't get the same constant
+            Some(_) => return Err(GenError::InvalidKeyName(key.name.clone())),
+            None => {}
+        }
+        result.push_str(&format!(
+            "    const val {} = \"{}\"\n",
+            name,
+            escape_string(&key.name)
+        ));
+        constants.insert(name, &key.name);
+    }
+    result.push_str("}\n");
+    Ok(result)
+}
+
+/// Key name if it's a valid Kotlin identifier, otherwise characters other than letters,
+/// digits and `_` are replaced with `_` and names starting with a digit get `k_` prefix.
+/// Keywords are escaped with backticks.
+fn constant_name(key_name: &str) -> String {
+    let mut name: String = key_name
+        .chars()
+        .map(|char| match char.is_ascii_alphanumeric() {
+            true => char,
+            false => '_',
+        })
+        .collect();
+    if name.is_empty() || name.starts_with(|char: char| char.is_ascii_digit()) {
+        name.insert_str(0, "k_");
+    }
+    if KOTLIN_KEYWORDS.contains(&name.as_str()) {
+        return format!("`{}`", name);
+    }
+    name
+}
+
+/// Escapes characters which end Kotlin string literals or start templates
+fn escape_string(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('$', "\\$")
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn generates_kotlin_constants() -> Result<(), GenError> {
+    let file = crate::parse::parse_str(
+        "[[Strings]]
+  [login_title]
+    en = Login
+  [error-message]
+    en = Error
+  [404_title]
+    en = Not found
+  [object]
+    en = Object
+",
+    )
+    .map_err(GenError::Parse)?;
+    let actual = generate(&file, "com.example", "Keys")?;
+    assert!(actual.starts_with("package com.example\n\nobject Keys {\n"));
+    for line in [
+        "    const val login_title = \"login_title\"\n",
+        "    const val error_message = \"error-message\"\n",
+        "    const val k_404_title = \"404_title\"\n",
+        "    const val `object` = \"object\"\n",
+    ] {
+        assert!(actual.contains(line), "{}", line);
+    }
+    assert!(actual.ends_with("}\n"));
+    assert!(!generate(&file, "", DEFAULT_OBJECT_NAME)?.contains("package"));
+
+    let file = crate::parse::parse_str("[[Strings]]\n  [a-b]\n    en = 1\n  [a_b]\n    en = 2\n")
+        .map_err(GenError::Parse)?;
+    assert!(matches!(
+        generate(&file, "", DEFAULT_OBJECT_NAME),
+        Err(GenError::InvalidKeyName(_))
+    ));
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use std::fmt;
+
+/// ISO 639-2 codes which are often written instead of ISO 639-1 codes, with the latter
+const ISO_639_2_CODES: &[(&str, &str)] = &[
+    ("ara", "ar"),
+    ("chi", "zh"),
+    ("deu", "de"),
+    ("dut", "nl"),
+    ("eng", "en"),
+    ("fra", "fr"),
+    ("fre", "fr"),
+    ("ger", "de"),
+    ("heb", "he"),
+    ("ita", "it"),
+    ("jpn", "ja"),
+    ("kor", "ko"),
+    ("nld", "nl"),
+    ("pol", "pl"),
+    ("por", "pt"),
+    ("rus", "ru"),
+    ("spa", "es"),
+    ("swe", "sv"),
+    ("tur", "tr"),
+    ("ukr", "uk"),
+    ("zho", "zh"),
+];
+
+/// Why a language code is not a `language[-Script][-REGION]` BCP 47 tag
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum LangError {
+    Empty,
+    /// Subtags are separated with `_` instead of `-`, e.g. `zh_CN`
+    Underscore,
+    /// Language subtag is not 2 or 3 lowercase letters
+    InvalidLanguage(String),
+    /// ISO 639-2 code which has an ISO 639-1 code, e.g. `eng` for `en`
+    LongLanguage {
+        code: String,
+        short: &'static str,
+    },
+    /// Subtag is neither a script like `Hant` nor a region like `BR` or `419`,
+    /// or goes after the region
+    InvalidSubtag(String),
+}
+
+impl fmt::Display for LangError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LangError::Empty => write!(f, "language code is empty"),
+            LangError::Underscore => write!(f, "subtags must be separated with \"-\""),
+            LangError::InvalidLanguage(subtag) => write!(
+                f,
+                "language \"{}\" is not an ISO 639 code of 2 or 3 lowercase letters",
+                subtag
+            ),
+            LangError::LongLanguage { code, short } => {
+                write!(f, "ISO 639-2 code \"{}\" should be \"{}\"", code, short)
+            }
+            LangError::InvalidSubtag(subtag) => {
+                write!(f, "\"{}\" is neither a script nor a region subtag", subtag)
+            }
+        }
+    }
+}
+
+/// Checks that the code is a BCP 47 tag of a language with optional script and region,
+/// e.g. `en`, `zh-Hant`, `pt-BR` or `es-419`. Only the structure is checked, subtags
+/// are not looked up in the registry except for common ISO 639-2 codes like `eng`.
+pub fn validate_lang_code(code: &str) -> Result<(), LangError> {
+    if code.is_empty() {
+        return Err(LangError::Empty);
+    }
+    if code.contains('_') {
+        return Err(LangError::Underscore);
+    }
+    let mut subtags = code.split('-');
+    let language = subtags.next().unwrap_or_default();
+    let is_language =
+        (2..=3).contains(&language.len()) && language.chars().all(|char| char.is_ascii_lowercase());
+    if !is_language {
+        return Err(LangError::InvalidLanguage(language.to_string()));
+    }
+    if let Some((_, short)) = ISO_639_2_CODES.iter().find(|(long, _)| *long == language) {
+        return Err(LangError::LongLanguage {
+            code: language.to_string(),
+            short,
+        });
+    }
+
+    let mut has_script = false;
+    let mut has_region = false;
+    for subtag in subtags {
+        let is_script = subtag.len() == 4
+            && subtag.starts_with(|char: char| char.is_ascii_uppercase())
+            && subtag[1..].chars().all(|char| char.is_ascii_lowercase());
+        let is_region = (subtag.len() == 2 && subtag.chars().all(|char| char.is_ascii_uppercase()))
+            || (subtag.len() == 3 && subtag.chars().all(|char| char.is_ascii_digit()));
+        match (is_script, is_region) {
+            (true, _) if !has_script && !has_region => has_script = true,
+            (_, true) if !has_region => has_region = true,
+            _ => return Err(LangError::InvalidSubtag(subtag.to_string())),
+        }
+    }
+    Ok(())
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn validates_lang_codes() {
+    for code in ["en", "fil", "pt-BR", "zh-Hant", "zh-Hant-TW", "es-419"] {
+        assert_eq!(validate_lang_code(code), Ok(()), "{}", code);
+    }
+
+    assert_eq!(validate_lang_code(""), Err(LangError::Empty));
+    assert_eq!(validate_lang_code("zh_CN"), Err(LangError::Underscore));
+    assert_eq!(
+        validate_lang_code("eng"),
+        Err(LangError::LongLanguage {
+            code: "eng".to_string(),
+            short: "en"
+        })
+    );
+    assert_eq!(
+        validate_lang_code("EN-us"),
+        Err(LangError::InvalidLanguage("EN".to_string()))
+    );
+    assert_eq!(
+        validate_lang_code("en-us"),
+        Err(LangError::InvalidSubtag("us".to_string()))
+    );
+    assert_eq!(
+        validate_lang_code("sr-RS-Latn"),
+        Err(LangError::InvalidSubtag("Latn".to_string()))
+    );
+}
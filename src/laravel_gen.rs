@@ -0,0 +1,213 @@
+use indexmap::IndexMap;
+use std::path::Path;
+
+use crate::gen_error::GenError;
+use crate::parse::{CldrCategory, File, PluralValue, StringValue};
+#[cfg(test)]
+use crate::parse::Section;
+use crate::platform::{
+    decode_android_escapes, replace_count_placeholder, to_named_placeholders_with_prefix,
+    unescape_android,
+};
+use crate::sink::WriteSink;
+#[cfg(test)]
+use crate::test_tools::key;
+
+/// How forms of plurals are joined into Laravel pluralization string
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum PluralStyle {
+    /// `one string|other string`, Laravel picks the form by plural rules of the locale
+    #[default]
+    Pipe,
+    /// `{0} none|{1} one|[2,*] many`, explicit ranges for zero, one, two and other.
+    /// Plurals with `few` or `many` can't be expressed as ranges and fall back to `Pipe`.
+    Ranges,
+}
+
+/// Writes `lang/<lang>/<file_name>.php` of every language into `output_dir`, e.g.
+/// `lang/en/strings.php` returning `[ 'key' => 'value' ]`. Placeholders become
+/// `:argN` and the count of plurals becomes `:count` which `trans_choice` replaces.
+pub fn write_laravel_php(
+    file: &File,
+    output_dir: &Path,
+    file_name: &str,
+    plural_style: PluralStyle,
+    sink: &mut dyn WriteSink,
+) -> Result<(), GenError> {
+    for (lang, content) in generate(file, plural_style)? {
+        let path = output_dir
+            .join("lang")
+            .join(&lang)
+            .join(format!("{}.php", file_name));
+        sink.write(&path, content.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Generates content of .php files keyed by language code in order of their first appearance
+fn generate(file: &File, plural_style: PluralStyle) -> Result<IndexMap<String, String>, GenError> {
+    if file.sections.is_empty() {
+        return Err(GenError::EmptyFile);
+    }
+
+    let mut entries: IndexMap<String, Vec<(String, String)>> = IndexMap::new();
+    for key in file.sections.iter().flat_map(|section| &section.keys) {
+        for str in &key.localizations {
+            let value = match &str.value {
+                StringValue::Single(text) => to_php_string(&to_laravel_text(text)),
+                StringValue::Plural { quantities } => to_php_string(&to_laravel_plural(
+                    &str.language_code,
+                    quantities,
+                    plural_style,
+                )),
+                StringValue::Array(items) => {
+                    let items: Vec<String> = items
+                        .iter()
+                        .map(|item| to_php_string(&to_laravel_text(item)))
+                        .collect();
+                    format!("[{}]", items.join(", "))
+                }
+            };
+            entries
+                .entry(str.language_code.clone())
+                .or_default()
+                .push((key.name.clone(), value));
+        }
+    }
+
+    Ok(entries
+        .into_iter()
+        .map(|(lang, entries)| {
+            let mut content = "<?php\n\nreturn [\n".to_string();
+            for (name, value) in entries {
+                content.push_str(&format!("    {} => {},\n", to_php_string(&name), value));
+            }
+            content.push_str("];\n");
+            (lang, content)
+        })
+        .collect())
+}
+
+fn to_laravel_plural(lang: &str, quantities: &[PluralValue], style: PluralStyle) -> String {
+    let mut items: Vec<&PluralValue> = quantities.iter().collect();
+    items.sort_by_key(|item| item.quantity_as_cldr_category());
+    let form =
+        |item: &PluralValue| to_laravel_text(&replace_count_placeholder(&item.text, ":count"));
+
+    let has_ranges = items.iter().all(|item| {
+        !matches!(
+            item.quantity_as_cldr_category(),
+            Some(CldrCategory::Few | CldrCategory::Many) | None
+        )
+    });
+    if style == PluralStyle::Ranges && has_ranges {
+        let mut next = 0;
+        let forms: Vec<String> = items
+            .into_iter()
+            .map(|item| {
+                let condition = match item.quantity_as_cldr_category() {
+                    Some(CldrCategory::Zero) => "{0}".to_string(),
+                    Some(CldrCategory::One) => "{1}".to_string(),
+                    Some(CldrCategory::Two) => "{2}".to_string(),
+                    _ => format!("[{},*]", next),
+                };
+                next = match item.quantity_as_cldr_category() {
+                    Some(CldrCategory::Zero) => 1,
+                    Some(CldrCategory::One) => 2,
+                    _ => 3,
+                };
+                format!("{} {}", condition, form(item))
+            })
+            .collect();
+        return forms.join("|");
+    }
+
+    // Laravel expects forms of all plural categories of the locale, e.g. without `zero` for `en`
+    let forms: Vec<String> = match CldrCategory::for_language(lang) {
+        Some(categories) => categories
+            .iter()
+            .filter_map(|category| {
+                items
+                    .iter()
+                    .find(|item| item.quantity_as_cldr_category() == Some(*category))
+                    .or_else(|| items.iter().find(|item| item.quantity == "other"))
+                    .map(|item| form(item))
+            })
+            .collect(),
+        None => items.into_iter().map(form).collect(),
+    };
+    forms.join("|")
+}
+
+/// Reverts Android specific escaping and replaces placeholders with `:argN`
+fn to_laravel_text(text: &str) -> String {
+    let text = decode_android_escapes(&unescape_android(text));
+    to_named_placeholders_with_prefix(&text, ":", "arg", "")
+}
+
+/// Single quoted PHP string, only `\` and `'` are escaped there
+fn to_php_string(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn generate_php_arrays_per_lang() -> Result<(), GenError> {
+    let keys = vec![
+        key(
+            "greeting",
+            vec![(
+                "en",
+                StringValue::Single("Hi %1$s, it\\'s %2$d \\\\ more".to_string()),
+            )],
+        ),
+        key(
+            "apples",
+            vec![
+                (
+                    "en",
+                    StringValue::plural()
+                        .quantity("other", "%d apples")
+                        .quantity("zero", "No apples")
+                        .quantity("one", "%d apple")
+                        .build(),
+                ),
+                (
+                    "ru",
+                    StringValue::plural()
+                        .quantity("one", "%d яблоко")
+                        .quantity("few", "%d яблока")
+                        .quantity("many", "%d яблок")
+                        .build(),
+                ),
+            ],
+        ),
+    ];
+    let source = File {
+        sections: vec![Section { keys }],
+    };
+
+    let actual = generate(&source, PluralStyle::Pipe)?;
+    assert_eq!(
+        actual["en"],
+        r"<?php
+
+return [
+    'greeting' => 'Hi :arg1, it\'s :arg2 \\ more',
+    'apples' => ':count apple|:count apples',
+];
+"
+    );
+    assert_eq!(
+        actual["ru"],
+        "<?php\n\nreturn [\n    'apples' => ':count яблоко|:count яблока|:count яблок',\n];\n"
+    );
+
+    let actual = generate(&source, PluralStyle::Ranges)?;
+    assert!(
+        actual["en"].contains("'apples' => '{0} No apples|{1} :count apple|[2,*] :count apples'")
+    );
+    assert!(actual["ru"].contains("'apples' => ':count яблоко|:count яблока|:count яблок'"));
+
+    Ok(())
+}
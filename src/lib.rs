@@ -0,0 +1,47 @@
+pub mod android_compat;
+pub mod android_gen;
+pub mod android_import;
+pub mod arb_gen;
+pub mod benchmark;
+pub mod check;
+pub mod completeness;
+pub mod compose_gen;
+pub mod compose_resources_gen;
+pub mod describe;
+pub mod diff;
+pub mod fs_util;
+pub mod gen_error;
+pub mod generate;
+pub mod import;
+pub mod io;
+pub mod ios_gen;
+pub mod ios_import;
+pub mod java_properties_gen;
+pub mod json_gen;
+pub mod kotlin_constants_gen;
+pub mod lang;
+pub mod laravel_gen;
+pub mod locale;
+pub mod merge;
+pub mod moko_gen;
+pub mod parse;
+pub mod parse_error;
+pub mod platform;
+pub mod po;
+pub mod rails_gen;
+pub mod react_native_gen;
+pub mod regex_info;
+pub mod sink;
+pub mod split;
+#[cfg(test)]
+mod test_tools;
+pub mod transform;
+pub mod translate;
+pub mod twine_text;
+pub mod unity_gen;
+pub mod validate;
+pub mod watch;
+pub mod xcstrings_gen;
+pub mod xliff2_gen;
+pub mod xliff_gen;
+pub mod xlsx;
@@ -0,0 +1,70 @@
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+use crate::lang::validate_lang_code;
+
+/// Minimal Android API level which resolves `values-b+<tag>` resource directories
+pub const BCP47_DIRS_MIN_SDK: u32 = 21;
+
+/// Name of Android resource directory of the language, e.g. `values-pt-rBR` for `pt-BR`.
+/// Codes with script or region subtags get BCP 47 names if `min_sdk` is at least 21,
+/// e.g. `values-b+zh+Hant` for `zh-Hant` and `values-b+pt+BR` for `pt-BR`.
+pub fn locale_to_dir_name(lang_code: &str, min_sdk: u32) -> String {
+    format!("values-{}", locale_qualifier(lang_code, min_sdk))
+}
+
+/// Language qualifier of `values-<qualifier>` directory names
+pub(crate) fn locale_qualifier(lang_code: &str, min_sdk: u32) -> String {
+    lazy_static! {
+        static ref LANG_WITH_REGION_RE: Regex = Regex::new(r"-(\p{Lu})").unwrap();
+    }
+    if min_sdk >= BCP47_DIRS_MIN_SDK && lang_code.contains('-') {
+        return format!("b+{}", lang_code.replace('-', "+"));
+    }
+    LANG_WITH_REGION_RE
+        .replace_all(lang_code, |caps: &Captures| {
+            format!("-r{}", caps.get(1).unwrap().as_str())
+        })
+        .into_owned()
+}
+
+/// Language code of Android resource directory, e.g. `pt-BR` for `values-pt-rBR` or
+/// `values-b+pt+BR`. None for `values` and directories with other qualifiers like
+/// `values-night`.
+pub fn dir_name_to_locale(dir_name: &str) -> Option<String> {
+    let qualifier = dir_name.strip_prefix("values-")?;
+    let code = match qualifier.strip_prefix("b+") {
+        Some(tag) => tag.replace('+', "-"),
+        None => match qualifier.split_once("-r") {
+            Some((lang, region)) => format!("{}-{}", lang, region),
+            None => qualifier.to_string(),
+        },
+    };
+    validate_lang_code(&code).is_ok().then_some(code)
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn maps_dir_names_to_lang_codes() {
+    assert_eq!(dir_name_to_locale("values-de"), Some("de".to_string()));
+    assert_eq!(
+        dir_name_to_locale("values-pt-rBR"),
+        Some("pt-BR".to_string())
+    );
+    assert_eq!(
+        dir_name_to_locale("values-b+zh+Hant"),
+        Some("zh-Hant".to_string())
+    );
+    assert_eq!(dir_name_to_locale("values"), None);
+    assert_eq!(dir_name_to_locale("values-night"), None);
+    assert_eq!(dir_name_to_locale("values-v21"), None);
+}
+
+#[test]
+fn maps_lang_codes_to_dir_names() {
+    assert_eq!(locale_to_dir_name("en", 21), "values-en");
+    assert_eq!(locale_to_dir_name("pt-BR", 16), "values-pt-rBR");
+    assert_eq!(locale_to_dir_name("pt-BR", 21), "values-b+pt+BR");
+    assert_eq!(locale_to_dir_name("zh-Hant", 24), "values-b+zh+Hant");
+    assert_eq!(locale_to_dir_name("zh-Hant-TW", 21), "values-b+zh+Hant+TW");
+}
@@ -1,80 +1,1341 @@
 use anyhow::{anyhow, Ok, Result};
-use clap::Parser;
-use parse as parser;
-use std::fs;
-
-mod android_gen;
-mod ios_gen;
-mod parse;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use std::{ffi::{OsStr, OsString}, fs, io::Write, path::{Path, PathBuf}};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde_json::json;
+use file::{CompareDirsContentResult, DirDiff};
+use tempfile::NamedTempFile;
+use utas::io::GitReader;
+use utas::sink::{FileStatus, WriteSink};
+use utas::translate::{self, DeepL};
+use utas::{
+    android_compat, android_gen, android_import, benchmark, check, completeness, describe, diff,
+    fs_util, generate, ios_import, kotlin_constants_gen, laravel_gen, merge, parse as parser, po,
+    react_native_gen, regex_info, sink, split, transform, twine_text, unity_gen, validate, watch,
+    xlsx,
+};
 
 #[derive(Parser)]
+#[command(version, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    args: Option<Args>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Fill missing translations of twine file using machine translation
+    Translate(TranslateArgs),
+    /// Show keys and localizations which differ between two twine files
+    Diff(DiffArgs),
+    /// Print translation progress of each language, fails if it's below thresholds
+    Completeness(CompletenessArgs),
+    /// Generate gettext template with source language values of twine file
+    ExportPot(ExportPotArgs),
+    /// Merge translations of gettext .po file into twine file
+    ImportPo(ImportPoArgs),
+    /// Generate Excel workbook with all localizations of twine file
+    ExportXlsx(ExportXlsxArgs),
+    /// Merge localizations of Excel workbook into twine file
+    ImportXlsx(ImportXlsxArgs),
+    /// Convert strings.xml files of Android res directory into twine file
+    ImportAndroid(ImportAndroidArgs),
+    /// Convert Localizable.strings and .stringsdict files of .lproj directories into twine file
+    ImportIos(ImportIosArgs),
+    /// Generate <lang>.json files for react-native-localize or i18n-js
+    ExportReactNative(ExportReactNativeArgs),
+    /// Generate Unity Localization string table from all twine files of the directory
+    ExportUnity(ExportUnityArgs),
+    /// Rewrite twine file with canonical formatting
+    Reformat(ReformatArgs),
+    /// Rewrite twine file in canonical form: sorted keys, normalized language codes and values
+    Canonicalize(CanonicalizeArgs),
+    /// Measure parse and generate performance for the file
+    Benchmark(BenchmarkArgs),
+    /// Print localizations, annotations and warnings of a single key
+    DescribeKey(DescribeKeyArgs),
+    /// Print values of a single key, e.g. `utas print strings.twine login_button --lang ru`
+    Print(PrintArgs),
+    /// Verify that generated Android resources pass aapt2 validation rules
+    CheckAndroidCompat(CheckAndroidCompatArgs),
+    /// Verify that keys have all locales, values fit max_length and placeholders are valid
+    Validate(ValidateArgs),
+    /// Run all validation passes and fail on any problem without generating resources
+    Check(CheckArgs),
+    /// Verify that printf placeholders are valid according to C printf rules
+    CheckFormatStrings(CheckFormatStringsArgs),
+    /// Verify HTML markup of values: allowed tags, nesting and links
+    CheckHtmlTags(CheckHtmlTagsArgs),
+    /// Combine two twine files, keys and locales missing in the primary one are added
+    Merge(MergeArgs),
+    /// Split twine file into smaller files by key name prefix
+    Split(SplitArgs),
+    /// Print version and build information
+    Version(VersionArgs),
+    /// Print pattern, description and example matches of a regex used while parsing
+    ShowRegex(ShowRegexArgs),
+    /// Generate resources into a temporary directory and compare them with OUTPUT_DIR which
+    /// holds the expected files, fails with a diff if they differ
+    Test(Box<Args>),
+    /// Print JSON Schema of the intermediate representation of twine files
+    #[cfg(feature = "serde")]
+    Schema,
+}
+
+#[derive(clap::Args)]
+struct CompletenessArgs {
+    input: String,
+    /// Fail if any language is translated less than this percentage, same as --error-below
+    #[arg(long, conflicts_with = "error_below")]
+    threshold: Option<f64>,
+    /// Fail if any language is translated less than this percentage
+    #[arg(long)]
+    error_below: Option<f64>,
+    /// Print warning if any language is translated less than this percentage
+    #[arg(long)]
+    warn_below: Option<f64>,
+    /// Comma separated languages to ignore, e.g. pseudo-locales
+    #[arg(long, value_delimiter = ',')]
+    exclude_locales: Vec<String>,
+}
+
+#[derive(clap::Args)]
+struct ExportPotArgs {
+    input: String,
+    /// Path of the generated .pot file
+    #[arg(long)]
+    output: String,
+    /// Language which values are used as msgid
+    #[arg(long, default_value = "en")]
+    source_lang: String,
+}
+
+#[derive(clap::Args)]
+struct ImportPoArgs {
+    /// Language code of translations
+    lang: String,
+    /// Path to translated .po file
+    po_file: String,
+    /// Path to twine file which is updated in place
+    input: String,
+    /// Import translations marked as fuzzy as well
+    #[arg(long)]
+    include_fuzzy: bool,
+}
+
+#[derive(clap::Args)]
+struct ExportXlsxArgs {
+    input: String,
+    /// Path of the generated .xlsx file
+    output: String,
+}
+
+#[derive(clap::Args)]
+struct ExportReactNativeArgs {
+    input: String,
+    /// Directory of the generated .json files
+    output_dir: String,
+    /// How quantities of plural keys are appended to the key name
+    #[arg(long, value_enum, default_value_t = RnPluralSuffix::Underscore)]
+    rn_plural_suffix: RnPluralSuffix,
+}
+
+#[derive(Clone, ValueEnum)]
+enum RnPluralSuffix {
+    /// key_one, key_other
+    Underscore,
+    /// key.one, key.other
+    Dot,
+}
+
+#[derive(clap::Args)]
+struct ExportUnityArgs {
+    input_dir: String,
+    /// Directory of the generated Strings.csv or Strings.json
+    output_dir: String,
+    /// Format of the string table
+    #[arg(long, value_enum, default_value_t = UnityTarget::UnityCsv)]
+    target: UnityTarget,
+}
+
+#[derive(Clone, ValueEnum)]
+enum UnityTarget {
+    UnityCsv,
+    UnityJson,
+}
+
+#[derive(clap::Args)]
+struct ImportXlsxArgs {
+    /// Path to workbook in export-xlsx format
+    xlsx_file: String,
+    /// Path to twine file which is updated in place
+    input: String,
+    /// Import only the sheet with this name
+    #[arg(long)]
+    sheet: Option<String>,
+}
+
+#[derive(clap::Args)]
+struct ImportAndroidArgs {
+    /// Android res directory with values and values-XX subdirectories
+    res_dir: PathBuf,
+    /// Path of the generated twine file
+    output: PathBuf,
+    /// Language code of values in values directory
+    #[arg(long, default_value = "en")]
+    default_lang: String,
+}
+
+#[derive(clap::Args)]
+struct ImportIosArgs {
+    /// Directory with <lang>.lproj subdirectories
+    lproj_parent_dir: PathBuf,
+    /// Path of the generated twine file
+    output: PathBuf,
+    /// Keep comments above .strings entries as comment attributes of keys
+    #[arg(long)]
+    keep_comments: bool,
+}
+
+#[derive(clap::Args)]
+struct ReformatArgs {
+    input: String,
+    /// Only check that file is formatted, fail otherwise
+    #[arg(long)]
+    check: bool,
+}
+
+#[derive(clap::Args)]
+struct CanonicalizeArgs {
+    input: String,
+    /// Path to the canonical file, printed to stdout if not set
+    #[arg(long)]
+    output: Option<String>,
+}
+
+#[derive(clap::Args)]
+struct BenchmarkArgs {
+    input: String,
+    /// Number of parse and generate cycles
+    #[arg(long, default_value_t = 100)]
+    iterations: usize,
+    /// Parse and generate key by key instead of the whole file at once
+    #[arg(long)]
+    streaming: bool,
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+#[derive(clap::Args)]
+struct DescribeKeyArgs {
+    input: String,
+    key_name: String,
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+#[derive(clap::Args)]
+struct PrintArgs {
+    input: String,
+    key_name: String,
+    /// Print only the value of this language
+    #[arg(long)]
+    lang: Option<String>,
+}
+
+#[derive(clap::Args)]
+struct CheckAndroidCompatArgs {
+    input: String,
+}
+
+#[derive(clap::Args)]
+struct ValidateArgs {
+    input: String,
+    /// Treat warnings as errors, e.g. fail if any key misses a locale which other keys have
+    #[arg(long)]
+    strict: bool,
+}
+
+#[derive(clap::Args)]
+struct CheckArgs {
+    input: String,
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+#[derive(clap::Args)]
+struct CheckFormatStringsArgs {
+    input: String,
+}
+
+#[derive(clap::Args)]
+struct CheckHtmlTagsArgs {
+    input: String,
+    /// Comma separated tags which values may contain
+    #[arg(long, value_delimiter = ',', default_value = "b,i,u,br,a")]
+    allowed_html_tags: Vec<String>,
+}
+
+#[derive(clap::Args)]
+struct MergeArgs {
+    /// Twine file which formatting is kept
+    primary: String,
+    secondary: String,
+    /// Path to the merged file, printed to stdout if not set
+    #[arg(long)]
+    output: Option<String>,
+    /// Which value to keep if an entry differs in both files
+    #[arg(long, value_enum, default_value_t = ConflictStrategy::Primary)]
+    conflict_strategy: ConflictStrategy,
+}
+
+#[derive(clap::Args)]
+struct SplitArgs {
+    input: String,
+    /// Group keys by name prefix, the only supported way of splitting for now
+    #[arg(long, required = true)]
+    by_prefix: bool,
+    /// Separator between key name prefix and the rest of the name
+    #[arg(long, default_value = "_")]
+    prefix_sep: String,
+    #[arg(long, default_value = ".")]
+    output_dir: String,
+    /// Keys of smaller groups go to misc.twine
+    #[arg(long, default_value_t = 1)]
+    min_keys_per_file: usize,
+}
+
+#[derive(Clone, ValueEnum)]
+enum ConflictStrategy {
+    Primary,
+    Secondary,
+    Error,
+}
+
+#[derive(clap::Args)]
+struct ShowRegexArgs {
+    /// Name of the regex, e.g. placeholder
+    regex_name: String,
+}
+
+#[derive(clap::Args)]
+struct VersionArgs {
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+#[derive(Clone, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(clap::Args)]
+struct DiffArgs {
+    /// Path to the old version of twine file
+    #[arg(long, required_unless_present = "old_input_git", conflicts_with = "old_input_git")]
+    old_input: Option<String>,
+    /// Old version of twine file in Git as <revision>:<path>, e.g. HEAD~1:strings.twine
+    #[arg(long)]
+    old_input_git: Option<String>,
+    /// Path to the new version of twine file
+    #[arg(long, required_unless_present = "new_input_git", conflicts_with = "new_input_git")]
+    new_input: Option<String>,
+    /// New version of twine file in Git as <revision>:<path>
+    #[arg(long)]
+    new_input_git: Option<String>,
+}
+
+#[derive(clap::Args)]
+struct TranslateArgs {
+    #[arg(long, value_enum)]
+    provider: TranslationProvider,
+    #[arg(long)]
+    api_key: String,
+    /// Language code to translate from
+    #[arg(long)]
+    source: String,
+    /// Language code to translate to
+    #[arg(long)]
+    target: String,
+    /// Replace existing translations as well
+    #[arg(long)]
+    overwrite_existing: bool,
+    input: String,
+}
+
+#[derive(Clone, ValueEnum)]
+enum TranslationProvider {
+    Deepl,
+}
+
+#[derive(Clone, ValueEnum)]
+enum AndroidResourceType {
+    Strings,
+    /// <color> resources with hex values like #FF0000
+    Colors,
+    /// <dimen> resources with values like 16dp
+    Dimens,
+}
+
+#[derive(Clone, ValueEnum)]
+enum XliffFormat {
+    /// XLIFF 1.2
+    Xliff1,
+    /// XLIFF 2.0
+    Xliff2,
+}
+
+#[derive(clap::Args, Clone)]
 struct Args {
+    /// Platform to generate, e.g. android or ios, all for every platform. It can also be
+    /// passed with --format or --target, e.g. `utas --format ios in out`
     platform: String,
     input_dir: String,
     output_dir: String,
     default_lang: Option<String>,
+    /// Package declaration for generated compose-wrappers Kotlin files
+    #[arg(long)]
+    compose_package: Option<String>,
+    /// Emit android plurals as separate strings suffixed with _<quantity>
+    #[arg(long)]
+    android_flatten_plurals: bool,
+    /// Emit all android strings as plurals with the only "other" quantity
+    #[arg(long, conflicts_with = "android_flatten_plurals")]
+    android_generate_plurals_only: bool,
+    /// Replace literal line breaks in android strings with \n, pass false to keep them
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+    android_escape_newlines: bool,
+    /// Wrap placeholders of android strings into <xliff:g> tags for translation tools
+    #[arg(long)]
+    xliff_placeholders: bool,
+    /// JSON file with examples of <xliff:g> tags, e.g. {"greeting": {"arg1": "Bob"}}
+    #[arg(long, requires = "xliff_placeholders")]
+    xliff_examples: Option<PathBuf>,
+    /// Don't add translatable="false" to Android strings of keys with `__translatable = false`
+    #[arg(long)]
+    no_translatable_tag: bool,
+    /// Don't add formatted="false" to Android strings without printf placeholders
+    #[arg(long)]
+    no_formatted_attr: bool,
+    /// minSdkVersion of the Android app, since 21 language directories with script or region
+    /// are named in BCP 47 format, e.g. values-b+zh+Hant instead of values-zh-rHant
+    #[arg(long)]
+    min_sdk: Option<u32>,
+    /// Separator of items of keys with `__type = string-array`
+    #[arg(long, default_value = parser::ARRAY_DELIMITER)]
+    array_delimiter: String,
+    /// Kind of android value resources generated from keys
+    #[arg(long, value_enum, default_value_t = AndroidResourceType::Strings)]
+    android_resource_type: AndroidResourceType,
+    /// Convert plurals with exactly one quantity to regular strings
+    #[arg(long)]
+    coerce_plural_to_single: bool,
+    /// Application package used to add R references as comments to android resources
+    #[arg(long)]
+    android_package_name: Option<String>,
+    /// Add values of source language as comments to android resources of other languages
+    #[arg(long)]
+    include_source_comments: bool,
+    /// Language used for source comments
+    #[arg(long, default_value = "en")]
+    source_lang: String,
+    /// Fail if a value is longer than max_length of its key instead of printing warnings
+    #[arg(long)]
+    enforce_max_length: bool,
+    /// Keep the last definition of keys defined more than once with a warning instead of failing
+    #[arg(long)]
+    allow_duplicate_keys: bool,
+    /// Fail on language codes which are not BCP 47 tags, e.g. eng or zh_CN, instead of
+    /// printing warnings
+    #[arg(long)]
+    strict_lang: bool,
+    /// Fail if a key misses a language which other keys have instead of printing warnings
+    #[arg(long)]
+    strict: bool,
+    /// Print warnings for plural quantities which CLDR doesn't define for the language
+    #[arg(long)]
+    validate_cldr: bool,
+    /// Generate resources even if placeholders of a translation differ from the source ones
+    #[arg(long)]
+    ignore_placeholder_mismatch: bool,
+    /// Generate only these comma separated languages, e.g. en,de. Validation still sees
+    /// all languages unless --validate-only-filtered is passed
+    #[arg(long, value_delimiter = ',', alias = "filter-lang")]
+    only_languages: Vec<String>,
+    /// Validate only languages passed with --only-languages
+    #[arg(long, requires = "only_languages")]
+    validate_only_filtered: bool,
+    /// Generate only keys which names match the glob pattern, e.g. login_*,
+    /// keys matching any of repeated patterns are generated
+    #[arg(long)]
+    filter_key: Vec<String>,
+    /// Merge all source files in order of their names into one and generate Android
+    /// resources of it into <NAME>.xml, keys defined in several files are an error
+    #[arg(long, value_name = "NAME")]
+    merge_into: Option<String>,
+    /// Let keys of later source files replace keys of earlier ones when source files are
+    /// merged, i.e. with --merge-into and by platforms writing one file per language
+    #[arg(long)]
+    allow_merge_overwrite: bool,
+    /// Write localizations skipped while parsing, e.g. empty values, to this JSON file
+    #[arg(long)]
+    skip_log: Option<PathBuf>,
+    /// Version of XLIFF documents generated for xliff platform, `--format xliff1` and
+    /// `--format xliff2` select the platform with the version
+    #[arg(long, value_enum, default_value_t = XliffFormat::Xliff1)]
+    xliff_format: XliffFormat,
+    /// Emit plurals of json platform as flat key_name__<quantity> keys instead of nested objects
+    #[arg(long)]
+    json_flat_plurals: bool,
+    /// Keep printf placeholders of rails-yaml platform instead of converting them to %{argN}
+    #[arg(long)]
+    rails_keep_printf: bool,
+    /// Name of PHP files generated for laravel-php platform, lang/<lang>/<name>.php
+    #[arg(long, default_value = "strings")]
+    laravel_file: String,
+    /// Package declaration of the file generated for kotlin-constants platform
+    #[arg(long)]
+    kotlin_package: Option<String>,
+    /// Name of the object with key name constants generated for kotlin-constants platform
+    #[arg(long, default_value = kotlin_constants_gen::DEFAULT_OBJECT_NAME)]
+    kotlin_object_name: String,
+    /// How plurals of laravel-php platform are written
+    #[arg(long, value_enum, default_value_t = LaravelPluralStyle::Pipe)]
+    laravel_plural_style: LaravelPluralStyle,
+    /// Keep running and regenerate resources whenever files of input_dir change
+    #[arg(long)]
+    watch: bool,
+    /// Milliseconds to wait for more changes before regenerating in watch mode
+    #[arg(long, default_value_t = 300, requires = "watch")]
+    watch_debounce: u64,
+    /// Print files which would be created or modified with their content instead of writing them
+    #[arg(long, conflicts_with = "watch")]
+    dry_run: bool,
+}
+
+#[derive(Clone, ValueEnum)]
+enum LaravelPluralStyle {
+    /// one string|other string
+    Pipe,
+    /// {0} none|{1} one|[2,*] many
+    Ranges,
 }
 
 fn main() -> Result<()> {
-    let args = Args::parse();
-    run_gen_pipeline(&args.platform, &args.input_dir, &args.output_dir, &args.default_lang)
-}
-
-fn run_gen_pipeline(
-    platform: &String,
-    input_dir: &String,
-    output_dir: &String,
-    default_lang: &Option<String>,
-) -> Result<()> {
-    // TODO add enum for Platform parameter
-    return match platform.as_str() {
-        "android" => run_android_gen_pipeline(input_dir, output_dir, default_lang),
-        "ios" => run_ios_gen_pipeline(input_dir, output_dir, default_lang),
-        _ => panic!("Invalid platform parameter. Use android or ios")
+    let cli = Cli::parse_from(platform_option_as_positional(std::env::args_os().collect()));
+    match (cli.command, cli.args) {
+        (Some(Command::Translate(args)), _) => run_translate(&args),
+        (Some(Command::Diff(args)), _) => run_diff(&args),
+        (Some(Command::Completeness(args)), _) => run_completeness(&args),
+        (Some(Command::ExportPot(args)), _) => run_export_pot(&args),
+        (Some(Command::ImportPo(args)), _) => run_import_po(&args),
+        (Some(Command::ExportXlsx(args)), _) => run_export_xlsx(&args),
+        (Some(Command::ImportXlsx(args)), _) => run_import_xlsx(&args),
+        (Some(Command::ImportAndroid(args)), _) => run_import_android(&args),
+        (Some(Command::ImportIos(args)), _) => run_import_ios(&args),
+        (Some(Command::ExportReactNative(args)), _) => run_export_react_native(&args),
+        (Some(Command::ExportUnity(args)), _) => run_export_unity(&args),
+        (Some(Command::Reformat(args)), _) => run_reformat(&args),
+        (Some(Command::Canonicalize(args)), _) => run_canonicalize(&args),
+        (Some(Command::Benchmark(args)), _) => run_benchmark(&args),
+        (Some(Command::DescribeKey(args)), _) => run_describe_key(&args),
+        (Some(Command::Print(args)), _) => run_print(&args),
+        (Some(Command::CheckAndroidCompat(args)), _) => run_check_android_compat(&args),
+        (Some(Command::Validate(args)), _) => run_validate(&args),
+        (Some(Command::Check(args)), _) => run_check(&args),
+        (Some(Command::CheckFormatStrings(args)), _) => run_check_format_strings(&args),
+        (Some(Command::CheckHtmlTags(args)), _) => run_check_html_tags(&args),
+        (Some(Command::Merge(args)), _) => run_merge(&args),
+        (Some(Command::Split(args)), _) => run_split(&args),
+        (Some(Command::Version(args)), _) => run_version(&args),
+        (Some(Command::ShowRegex(args)), _) => run_show_regex(&args),
+        (Some(Command::Test(args)), _) => run_test(&args),
+        #[cfg(feature = "serde")]
+        (Some(Command::Schema), _) => {
+            println!("{}", parser::file_json_schema());
+            Ok(())
+        }
+        (None, Some(args)) if args.watch => run_watch(&args),
+        (None, Some(args)) if args.dry_run => run_dry_run(&args),
+        (None, Some(args)) => run_gen_pipeline(&args, &mut sink::FsSink::default()),
+        // clap requires either a subcommand or generation arguments
+        (None, None) => unreachable!(),
+    }
+}
+
+fn run_translate(args: &TranslateArgs) -> Result<()> {
+    let translator = match args.provider {
+        TranslationProvider::Deepl => DeepL::new(args.api_key.clone()),
     };
+    let content = read_input(&args.input)?;
+    let translated = translate::translate_missing(
+        &content,
+        &translator,
+        &args.source,
+        &args.target,
+        args.overwrite_existing,
+    )?;
+    fs::write(&args.input, translated)?;
+    Ok(())
 }
 
-fn run_android_gen_pipeline(
-    input_dir: &String,
-    output_dir: &String,
-    default_lang: &Option<String>,
-) -> Result<()> {
-    for src in fs::read_dir(input_dir)? {
-        let src = src?;
-        if src.file_type()?.is_file() {
-            let parsed = parser::parse(src.path()).map_err(|err| anyhow!(err))?;
-            let generated = android_gen::generate(&parsed)?;
-            generated.write(
-                output_dir,
-                src.path()
-                    .file_stem()
-                    .and_then(|os_str| os_str.to_str())
-                    .ok_or(anyhow!("Cannot extract file name"))?,
-                default_lang,
-            )?;
-        }
-    }
-    Ok(())
-}
-
-fn run_ios_gen_pipeline(
-    input_dir: &String,
-    output_dir: &String,
-    default_lang: &Option<String>,
-) -> Result<()> {
-    let parsed_files: Vec<_> = fs::read_dir(input_dir)?.filter_map( |src| {
-        let src = src.ok()?;
-        // TODO: https://github.com/appKODE/utas/issues/33
-        if src.file_type().ok()?.is_file() && src.file_name() != ".DS_Store" {
-            let parsed = parser::parse(src.path()).map_err(|err| anyhow!(err)).ok()?;
-            Some(parsed)
+fn run_completeness(args: &CompletenessArgs) -> Result<()> {
+    let parsed = parser::parse(&args.input).map_err(|err| anyhow!(err))?;
+    let locales = completeness::completeness(&parsed, &args.exclude_locales);
+
+    println!("{:<10} {:>10} {:>10} {:>8}", "LOCALE", "TRANSLATED", "TOTAL", "PERCENT");
+    for locale in &locales {
+        println!(
+            "{:<10} {:>10} {:>10} {:>7.1}%",
+            locale.language_code,
+            locale.translated,
+            locale.total,
+            locale.percent()
+        );
+    }
+
+    if let Some(warn_below) = args.warn_below {
+        for locale in locales.iter().filter(|locale| locale.percent() < warn_below) {
+            eprintln!(
+                "warning: \"{}\" is translated for {:.1}%, expected at least {}%",
+                locale.language_code,
+                locale.percent(),
+                warn_below
+            );
+        }
+    }
+    if let Some(error_below) = args.error_below.or(args.threshold) {
+        let failed: Vec<_> = locales
+            .iter()
+            .filter(|locale| locale.percent() < error_below)
+            .map(|locale| locale.language_code.as_str())
+            .collect();
+        if !failed.is_empty() {
+            return Err(anyhow!(
+                "Languages translated less than {}%: {}",
+                error_below,
+                failed.join(", ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn run_export_pot(args: &ExportPotArgs) -> Result<()> {
+    let content = read_input(&args.input)?;
+    let source_file_name = Path::new(&args.input)
+        .file_name()
+        .and_then(|os_str| os_str.to_str())
+        .ok_or(anyhow!("Cannot extract file name"))?;
+    fs::write(
+        &args.output,
+        po::export_pot(&content, source_file_name, &args.source_lang),
+    )?;
+    Ok(())
+}
+
+fn run_import_po(args: &ImportPoArgs) -> Result<()> {
+    let po_content = read_input(&args.po_file)?;
+    let content = read_input(&args.input)?;
+    let (updated, missing) =
+        po::import_po(&po_content, &content, &args.lang, args.include_fuzzy);
+    for name in missing {
+        eprintln!("warning: key \"{}\" is not found in {}", name, args.input);
+    }
+    fs::write(&args.input, updated)?;
+    Ok(())
+}
+
+fn run_export_xlsx(args: &ExportXlsxArgs) -> Result<()> {
+    let content = read_input(&args.input)?;
+    xlsx::export_xlsx(&content, &args.output)
+}
+
+fn run_import_xlsx(args: &ImportXlsxArgs) -> Result<()> {
+    let content = read_input(&args.input)?;
+    let imported = xlsx::import_xlsx(&args.xlsx_file, &content, args.sheet.as_deref())?;
+    for name in &imported.unknown_keys {
+        eprintln!("warning: key \"{}\" is not found in {}", name, args.input);
+    }
+    for conflict in &imported.conflicts {
+        eprintln!(
+            "warning: {} [{}] is changed from \"{}\" to \"{}\"",
+            conflict.key, conflict.name, conflict.old, conflict.new
+        );
+    }
+    fs::write(&args.input, imported.content)?;
+    Ok(())
+}
+
+fn run_import_android(args: &ImportAndroidArgs) -> Result<()> {
+    let content = android_import::import_android(&args.res_dir, &args.default_lang)?;
+    fs::write(&args.output, content)?;
+    Ok(())
+}
+
+fn run_import_ios(args: &ImportIosArgs) -> Result<()> {
+    let content = ios_import::import_ios(&args.lproj_parent_dir, args.keep_comments)?;
+    fs::write(&args.output, content)?;
+    Ok(())
+}
+
+fn run_export_react_native(args: &ExportReactNativeArgs) -> Result<()> {
+    let parsed = parser::parse(&args.input).map_err(|err| anyhow!(err))?;
+    let plural_suffix = match args.rn_plural_suffix {
+        RnPluralSuffix::Underscore => react_native_gen::PluralSuffix::Underscore,
+        RnPluralSuffix::Dot => react_native_gen::PluralSuffix::Dot,
+    };
+    react_native_gen::write(
+        &parsed,
+        Path::new(&args.output_dir),
+        plural_suffix,
+        &mut sink::FsSink::default(),
+    )?;
+    Ok(())
+}
+
+fn run_export_unity(args: &ExportUnityArgs) -> Result<()> {
+    let combined = merge_sources(source_paths(&args.input_dir)?, false, |path| {
+        parser::parse(path).map_err(|err| anyhow!(err))
+    })?;
+    let format = match args.target {
+        UnityTarget::UnityCsv => unity_gen::TableFormat::Csv,
+        UnityTarget::UnityJson => unity_gen::TableFormat::Json,
+    };
+    unity_gen::write(
+        &combined,
+        Path::new(&args.output_dir),
+        format,
+        &mut sink::FsSink::default(),
+    )?;
+    Ok(())
+}
+
+fn run_reformat(args: &ReformatArgs) -> Result<()> {
+    let content = read_input(&args.input)?;
+    let formatted = twine_text::reformat(&content);
+    if args.check {
+        if formatted != content {
+            return Err(anyhow!("{} is not formatted", args.input));
+        }
+        return Ok(());
+    }
+    fs::write(&args.input, formatted)?;
+    Ok(())
+}
+
+fn run_canonicalize(args: &CanonicalizeArgs) -> Result<()> {
+    let content = read_input(&args.input)?;
+    let canonical = twine_text::canonicalize(&content);
+    match &args.output {
+        Some(output) => fs::write(output, canonical)?,
+        None => print!("{}", canonical),
+    }
+    Ok(())
+}
+
+fn run_benchmark(args: &BenchmarkArgs) -> Result<()> {
+    let report = if args.streaming {
+        benchmark::run_streaming(&args.input, args.iterations)?
+    } else {
+        benchmark::run(&args.input, args.iterations)?
+    };
+    match args.format {
+        OutputFormat::Text => println!("{}", report),
+        OutputFormat::Json => println!("{}", report.to_json()),
+    }
+    Ok(())
+}
+
+fn run_describe_key(args: &DescribeKeyArgs) -> Result<()> {
+    let content = read_input(&args.input)?;
+    let description = describe::describe_key(&content, &args.key_name)
+        .ok_or_else(|| anyhow!("Key \"{}\" is not found in {}", args.key_name, args.input))?;
+    match args.format {
+        OutputFormat::Text => print!("{}", description),
+        OutputFormat::Json => println!("{}", description.to_json()),
+    }
+    Ok(())
+}
+
+fn run_print(args: &PrintArgs) -> Result<()> {
+    let content = read_input(&args.input)?;
+    let description = describe::describe_key(&content, &args.key_name).ok_or_else(|| {
+        anyhow!(
+            "Key \"{}\" is not found in {}. Available keys: {}",
+            args.key_name,
+            args.input,
+            describe::key_names(&content).join(", ")
+        )
+    })?;
+    let lines = description
+        .value_lines(args.lang.as_deref())
+        .ok_or_else(|| {
+            anyhow!(
+                "Key \"{}\" has no \"{}\" localization. Available languages: {}",
+                args.key_name,
+                args.lang.as_deref().unwrap_or_default(),
+                description.language_codes().join(", ")
+            )
+        })?;
+    for line in lines {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+fn run_check_android_compat(args: &CheckAndroidCompatArgs) -> Result<()> {
+    let parsed = parser::parse(&args.input).map_err(|err| anyhow!(err))?;
+    let mut generated: Vec<(String, String)> = parsed.to_android_xml_map()?.into_iter().collect();
+    generated.sort();
+
+    let mut count = 0;
+    for (lang, xml) in &generated {
+        for violation in android_compat::check_android_xml(xml) {
+            eprintln!("error: {}: {}", lang, violation);
+            count += 1;
+        }
+    }
+    if count > 0 {
+        return Err(anyhow!("Found {} Android compatibility violations", count));
+    }
+    Ok(())
+}
+
+fn run_validate(args: &ValidateArgs) -> Result<()> {
+    let parsed = parser::parse(&args.input).map_err(|err| anyhow!(err))?;
+    let mut errors = validate::format_string_errors(&parsed);
+    let mut warnings = validate::missing_locale_warnings(&parsed);
+    warnings.extend(validate::max_length_violations(&parsed));
+    if args.strict {
+        errors.append(&mut warnings);
+    }
+    for warning in &warnings {
+        eprintln!("warning: {}", warning);
+    }
+    for error in &errors {
+        eprintln!("error: {}", error);
+    }
+    if !errors.is_empty() {
+        return Err(anyhow!("Validation failed with {} errors", errors.len()));
+    }
+    Ok(())
+}
+
+fn run_check(args: &CheckArgs) -> Result<()> {
+    let (content, _) = fs_util::read_to_string_with_encoding(Path::new(&args.input), None)?;
+    let report = check::check(&content).map_err(|err| anyhow!(err))?;
+    match args.format {
+        OutputFormat::Text => println!("{}", report),
+        OutputFormat::Json => println!("{}", report.to_json()),
+    }
+    if !report.is_ok() {
+        return Err(anyhow!("Check of {} failed", args.input));
+    }
+    Ok(())
+}
+
+fn run_check_format_strings(args: &CheckFormatStringsArgs) -> Result<()> {
+    let parsed = parser::parse(&args.input).map_err(|err| anyhow!(err))?;
+    let errors = validate::format_string_errors(&parsed);
+    for error in &errors {
+        eprintln!("error: {}", error);
+    }
+    if !errors.is_empty() {
+        return Err(anyhow!("Found {} invalid format strings", errors.len()));
+    }
+    Ok(())
+}
+
+fn run_check_html_tags(args: &CheckHtmlTagsArgs) -> Result<()> {
+    let parsed = parser::parse(&args.input).map_err(|err| anyhow!(err))?;
+    for warning in validate::html_tag_warnings(&parsed, &args.allowed_html_tags) {
+        eprintln!("warning: {}", warning);
+    }
+    Ok(())
+}
+
+fn run_merge(args: &MergeArgs) -> Result<()> {
+    let strategy = match args.conflict_strategy {
+        ConflictStrategy::Primary => merge::ConflictStrategy::Primary,
+        ConflictStrategy::Secondary => merge::ConflictStrategy::Secondary,
+        ConflictStrategy::Error => merge::ConflictStrategy::Error,
+    };
+    let primary = read_input(&args.primary)?;
+    let secondary = read_input(&args.secondary)?;
+    let merged = merge::merge(&primary, &secondary, strategy)?;
+    match &args.output {
+        Some(output) => fs::write(output, merged)?,
+        None => print!("{}", merged),
+    }
+    Ok(())
+}
+
+fn run_split(args: &SplitArgs) -> Result<()> {
+    let content = read_input(&args.input)?;
+    let files = split::split_by_prefix(&content, &args.prefix_sep, args.min_keys_per_file);
+    fs::create_dir_all(&args.output_dir)?;
+    for (name, content) in files {
+        fs::write(Path::new(&args.output_dir).join(format!("{}.twine", name)), content)?;
+    }
+    Ok(())
+}
+
+fn run_version(args: &VersionArgs) -> Result<()> {
+    let version = env!("CARGO_PKG_VERSION");
+    match args.format {
+        OutputFormat::Text => println!("utas {}", version),
+        OutputFormat::Json => println!(
+            "{}",
+            json!({
+                "version": version,
+                "git_sha": env!("VERGEN_GIT_SHA"),
+                "build_date": env!("VERGEN_BUILD_DATE"),
+                "rustc_version": env!("VERGEN_RUSTC_SEMVER"),
+            })
+        ),
+    }
+    Ok(())
+}
+
+fn run_show_regex(args: &ShowRegexArgs) -> Result<()> {
+    let info = regex_info::regex_info(&args.regex_name).ok_or_else(|| {
+        anyhow!(
+            "Unknown regex \"{}\". Available regexes: {}",
+            args.regex_name,
+            regex_info::regex_names().join(", ")
+        )
+    })?;
+    print!("{}", info);
+    Ok(())
+}
+
+/// Options which select the platform of generation like its first positional argument
+const PLATFORM_OPTIONS: &[&str] = &["--format", "--target"];
+
+/// Moves the value of a platform option to the first positional argument, e.g.
+/// `utas --format ios in out` becomes `utas ios in out`, `--format xliff2` selects xliff
+/// platform with --xliff-format. Arguments of subcommands are returned as is.
+fn platform_option_as_positional(mut args: Vec<OsString>) -> Vec<OsString> {
+    let first_positional = args
+        .iter()
+        .skip(1)
+        .map(|arg| arg.to_string_lossy())
+        .find(|arg| !arg.starts_with('-'));
+    if let Some(first_positional) = first_positional {
+        if Cli::command().find_subcommand(first_positional.as_ref()).is_some() {
+            return args;
+        }
+    }
+    let mut platform: Option<OsString> = None;
+    let mut index = 1;
+    while index < args.len() {
+        let arg = args[index].to_string_lossy().into_owned();
+        let inline_value = PLATFORM_OPTIONS.iter().find_map(|option| {
+            arg.strip_prefix(option)
+                .and_then(|rest| rest.strip_prefix('='))
+        });
+        if let Some(value) = inline_value {
+            platform = Some(value.into());
+            args.remove(index);
+        } else if PLATFORM_OPTIONS.contains(&arg.as_str()) && index + 1 < args.len() {
+            args.remove(index);
+            platform = Some(args.remove(index));
         } else {
-            None
+            index += 1;
+        }
+    }
+    match platform.as_ref().and_then(|platform| platform.to_str()) {
+        // XLIFF version is selected together with the platform, e.g. `--format xliff2`
+        Some(version @ ("xliff1" | "xliff2")) => {
+            let version = OsString::from(version);
+            args.insert(1.min(args.len()), "xliff".into());
+            args.extend(["--xliff-format".into(), version]);
+        }
+        _ => {
+            if let Some(platform) = platform {
+                args.insert(1.min(args.len()), platform);
+            }
         }
-    }).collect();
+    }
+    args
+}
 
-    let generated = ios_gen::generate(parsed_files)?;
-    generated.write(output_dir,default_lang)?;
+fn run_gen_pipeline(args: &Args, sink: &mut dyn WriteSink) -> Result<()> {
+    if let Some(path) = &args.skip_log {
+        write_skip_log(&args.input_dir, path)?;
+    }
+    let options = gen_options(args)?;
+    let sources = CliSources(args);
+    if args.platform == "all" {
+        for (platform, generator) in generate::GENERATOR_REGISTRY.iter() {
+            let output_dir = Path::new(&args.output_dir).join(platform);
+            generator(&sources, &options, &output_dir, sink)?;
+        }
+        return Ok(());
+    }
+    match generate::GENERATOR_REGISTRY.get(&args.platform) {
+        Some(generator) => generator(&sources, &options, Path::new(&args.output_dir), sink),
+        None => panic!(
+            "Invalid platform parameter. Use {} or all",
+            generate::GENERATOR_REGISTRY.names().join(", ")
+        ),
+    }
+}
+
+fn run_test(args: &Args) -> Result<()> {
+    let generated = tempfile::tempdir()?;
+    let gen_args = Args {
+        output_dir: generated.path().to_string_lossy().into_owned(),
+        ..args.clone()
+    };
+    run_gen_pipeline(&gen_args, &mut sink::FsSink::default())?;
 
+    let result = file::compare_dirs_content(&args.output_dir, generated.path())?;
+    let CompareDirsContentResult::Diffs(diffs) = result else {
+        return Ok(());
+    };
+    for diff in &diffs {
+        match diff {
+            DirDiff::Path { left, right } => eprintln!(
+                "expected {}, generated {}",
+                left.as_deref().unwrap_or("no file"),
+                right.as_deref().unwrap_or("no file")
+            ),
+            DirDiff::FileContent { path, diffs } => {
+                eprintln!("{} differs:", path);
+                for diff in diffs {
+                    eprintln!("  line {}:", diff.line_number);
+                    eprintln!("  - {}", diff.left);
+                    eprintln!("  + {}", diff.right);
+                }
+            }
+        }
+    }
+    Err(anyhow!(
+        "Generated files differ from {}, {} differences found",
+        args.output_dir,
+        diffs.len()
+    ))
+}
+
+fn run_watch(args: &Args) -> Result<()> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let handler_stop = stop.clone();
+    ctrlc::set_handler(move || handler_stop.store(true, Ordering::SeqCst))?;
+
+    let report = || match regenerate(args) {
+        Result::Ok(changed) => {
+            eprintln!("[{}] {} files changed", utc_time(), changed.len());
+            for path in changed {
+                eprintln!("  {}", path.display());
+            }
+        }
+        Err(err) => eprintln!("[{}] error: {}", utc_time(), err),
+    };
+    report();
+    eprintln!("Watching {}, press Ctrl-C to stop", args.input_dir);
+    let debounce = Duration::from_millis(args.watch_debounce);
+    watch::watch_dir(Path::new(&args.input_dir), debounce, &stop, report)?;
     Ok(())
 }
+
+/// Generates resources in memory and prints which files of the output directory would be
+/// created or modified with their content, nothing is written to the file system
+fn run_dry_run(args: &Args) -> Result<()> {
+    let gen_args = Args {
+        skip_log: None,
+        ..args.clone()
+    };
+    let mut sink = sink::MemorySink::default();
+    run_gen_pipeline(&gen_args, &mut sink)?;
+
+    let mut files = sink.files;
+    files.sort_by(|(left, _), (right, _)| left.cmp(right));
+    for (path, content) in files {
+        let status = sink::file_status(&path, &content);
+        println!("{} {}", status.as_str(), path.display());
+        if status != FileStatus::Unchanged {
+            println!("{}", String::from_utf8_lossy(&content));
+        }
+    }
+    Ok(())
+}
+
+/// Generates resources writing only changed files into the output directory, returns their paths
+fn regenerate(args: &Args) -> Result<Vec<PathBuf>> {
+    let mut sink = sink::FsSink::default();
+    run_gen_pipeline(args, &mut sink)?;
+    let changed = sink
+        .written
+        .into_iter()
+        .filter(|(_, status)| *status != FileStatus::Unchanged)
+        .map(|(path, _)| path)
+        .collect();
+    Ok(changed)
+}
+
+/// Current time as `HH:MM:SS` in UTC
+fn utc_time() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600 % 24,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Writes localizations skipped in all parsable input files as a JSON array
+fn write_skip_log(input_dir: impl AsRef<Path>, path: &Path) -> Result<()> {
+    let mut skipped = Vec::new();
+    for src in source_paths(input_dir)? {
+        // Unparsable files are reported by generation itself
+        let file_skipped = parser::parse_with_skipped(&src)
+            .map(|(_, file_skipped)| file_skipped)
+            .unwrap_or_default();
+        skipped.extend(file_skipped.iter().map(parser::SkippedKey::to_json));
+    }
+    let json = serde_json::to_string_pretty(&skipped)?;
+    fs_util::write_atomically(path, json.as_bytes())?;
+    Ok(())
+}
+
+fn run_diff(args: &DiffArgs) -> Result<()> {
+    let old = read_twine_source(&args.old_input, &args.old_input_git)?;
+    let new = read_twine_source(&args.new_input, &args.new_input_git)?;
+    for key_diff in diff::diff_files(&old, &new) {
+        println!("{}", key_diff);
+    }
+    Ok(())
+}
+
+/// Parses twine file either from file system or from Git object store
+fn read_twine_source(path: &Option<String>, git_spec: &Option<String>) -> Result<parser::File> {
+    match (path, git_spec) {
+        (Some(path), _) => parser::parse(path).map_err(|err| anyhow!(err)),
+        (None, Some(git_spec)) => {
+            let content = GitReader::default().read(git_spec)?;
+            let mut temp_file = NamedTempFile::new()?;
+            temp_file.write_all(&content)?;
+            parser::parse(temp_file.path()).map_err(|err| anyhow!(err))
+        }
+        (None, None) => Err(anyhow!("Expected either path or Git spec of twine file")),
+    }
+}
+
+/// Reads text input with encoding detection, guessed encodings are reported
+fn read_input(path: impl AsRef<Path>) -> Result<String> {
+    let path = path.as_ref();
+    let (content, encoding) = fs_util::read_to_string_with_encoding(path, None)?;
+    if encoding.guessed {
+        eprintln!("note: {} is decoded as {}", path.display(), encoding.name);
+    }
+    Ok(content)
+}
+
+/// Parses source file and applies transforms common for all platforms
+fn parse_source(path: impl AsRef<Path>, args: &Args) -> Result<parser::File> {
+    let options = parser::ParseOptions {
+        allow_duplicate_keys: args.allow_duplicate_keys,
+        strict_lang: args.strict_lang,
+    };
+    let mut parsed = parser::parse_with_options(path, &options).map_err(|err| anyhow!(err))?;
+    if args.coerce_plural_to_single {
+        transform::coerce_single_quantity_plurals(&mut parsed);
+    }
+    if !args.only_languages.is_empty() {
+        let languages = parsed.languages();
+        let unknown: Vec<&str> = args
+            .only_languages
+            .iter()
+            .map(String::as_str)
+            .filter(|lang| !languages.contains(lang))
+            .collect();
+        if !unknown.is_empty() {
+            return Err(anyhow!(
+                "Languages are not present in the source file: {}",
+                unknown.join(", ")
+            ));
+        }
+    }
+    if args.validate_only_filtered {
+        transform::retain_languages(&mut parsed, &args.only_languages);
+    }
+    if args.array_delimiter != parser::ARRAY_DELIMITER {
+        transform::split_arrays_by(&mut parsed, &args.array_delimiter);
+    }
+    let violations = validate::max_length_violations(&parsed);
+    if args.enforce_max_length && !violations.is_empty() {
+        return Err(anyhow!(violations.join("\n")));
+    }
+    for violation in violations {
+        eprintln!("warning: {}", violation);
+    }
+    let missing = validate::missing_locale_warnings(&parsed);
+    if args.strict && !missing.is_empty() {
+        return Err(anyhow!(missing.join("\n")));
+    }
+    for warning in missing {
+        eprintln!("warning: {}", warning);
+    }
+    if args.validate_cldr {
+        for key in parsed.sections.iter().flat_map(|section| &section.keys) {
+            for localization in &key.localizations {
+                let lang = &localization.language_code;
+                for warning in validate::validate_plural_quantities(key, lang) {
+                    eprintln!("warning: {}", warning);
+                }
+            }
+        }
+    }
+    let mismatches: Vec<_> = parsed
+        .sections
+        .iter()
+        .flat_map(|section| &section.keys)
+        .flat_map(validate::validate_placeholder_consistency)
+        .collect();
+    if args.ignore_placeholder_mismatch {
+        for mismatch in mismatches {
+            eprintln!("warning: {}", mismatch);
+        }
+    } else if !mismatches.is_empty() {
+        for mismatch in &mismatches {
+            eprintln!("error: {}", mismatch);
+        }
+        return Err(anyhow!(
+            "{} placeholder mismatches found, pass --ignore-placeholder-mismatch to generate anyway",
+            mismatches.len()
+        ));
+    }
+    if !args.only_languages.is_empty() && !args.validate_only_filtered {
+        transform::retain_languages(&mut parsed, &args.only_languages);
+    }
+    if !args.filter_key.is_empty() {
+        parsed.retain_keys_matching_any(&args.filter_key);
+    }
+    Ok(parsed)
+}
+
+/// Generator options of the command line arguments
+fn gen_options(args: &Args) -> Result<generate::GenOptions> {
+    let android = android_gen::Options {
+        package_name: args.android_package_name.clone(),
+        source_comments_lang: args
+            .include_source_comments
+            .then(|| args.source_lang.clone()),
+        resource_type: match args.android_resource_type {
+            AndroidResourceType::Strings => android_gen::ResourceType::Strings,
+            AndroidResourceType::Colors => android_gen::ResourceType::Colors,
+            AndroidResourceType::Dimens => android_gen::ResourceType::Dimens,
+        },
+        xliff_placeholders: args.xliff_placeholders,
+        placeholder_examples: match &args.xliff_examples {
+            Some(path) => serde_json::from_str(&fs::read_to_string(path)?)
+                .map_err(|err| anyhow!("Invalid examples file {}: {}", path.display(), err))?,
+            None => Default::default(),
+        },
+        skip_translatable_tag: args.no_translatable_tag,
+        min_sdk: args.min_sdk.unwrap_or(0),
+        formatted_attr: !args.no_formatted_attr,
+    };
+    Ok(generate::GenOptions {
+        default_lang: args.default_lang.clone(),
+        android,
+        android_merge_into: args.merge_into.clone(),
+        android_flatten_plurals: args.android_flatten_plurals,
+        android_generate_plurals_only: args.android_generate_plurals_only,
+        android_escape_newlines: args.android_escape_newlines,
+        compose_package: args.compose_package.clone(),
+        xliff_format: match args.xliff_format {
+            XliffFormat::Xliff1 => generate::XliffFormat::Xliff1,
+            XliffFormat::Xliff2 => generate::XliffFormat::Xliff2,
+        },
+        json_flat_plurals: args.json_flat_plurals,
+        rails_keep_printf: args.rails_keep_printf,
+        laravel_file: args.laravel_file.clone(),
+        laravel_plural_style: match args.laravel_plural_style {
+            LaravelPluralStyle::Pipe => laravel_gen::PluralStyle::Pipe,
+            LaravelPluralStyle::Ranges => laravel_gen::PluralStyle::Ranges,
+        },
+        kotlin_package: args.kotlin_package.clone(),
+        kotlin_object_name: args.kotlin_object_name.clone(),
+    })
+}
+
+/// Files of the input dir parsed with `parse_source`
+struct CliSources<'a>(&'a Args);
+
+impl CliSources<'_> {
+    fn paths(&self) -> Result<Vec<PathBuf>> {
+        Ok(source_paths(&self.0.input_dir)?
+            .into_iter()
+            // TODO: https://github.com/appKODE/utas/issues/33
+            .filter(|path| path.file_name() != Some(OsStr::new(".DS_Store")))
+            .collect())
+    }
+}
+
+impl generate::Sources for CliSources<'_> {
+    fn files(&self) -> Result<Vec<(String, parser::File)>> {
+        let mut files = Vec::new();
+        for path in self.paths()? {
+            let name = path
+                .file_stem()
+                .and_then(|os_str| os_str.to_str())
+                .ok_or(anyhow!("Cannot extract file name"))?
+                .to_string();
+            files.push((name, parse_source(&path, self.0)?));
+        }
+        Ok(files)
+    }
+
+    /// All files of the input dir in order of their names merged into one, see `merge_sources`
+    fn merged(&self) -> Result<parser::File> {
+        merge_sources(self.paths()?, self.0.allow_merge_overwrite, |path| {
+            parse_source(path, self.0)
+        })
+    }
+}
+
+/// Parses files of `paths` with `parse` in the given order and merges them into one.
+/// Keys defined in several files are an error unless `overwrite` is set, then later
+/// files win.
+fn merge_sources(
+    paths: Vec<PathBuf>,
+    overwrite: bool,
+    parse: impl Fn(&Path) -> Result<parser::File>,
+) -> Result<parser::File> {
+    let mut merged = parser::File { sections: vec![] };
+    for path in paths {
+        let parsed = parse(&path)?;
+        merged = if overwrite {
+            merged.merge_overwriting(parsed)
+        } else {
+            merged
+                .merge(parsed)
+                .map_err(|err| anyhow!("Cannot merge {}: {}", path.display(), err))?
+        };
+    }
+    Ok(merged)
+}
+
+/// Paths of all files of the input dir sorted by name, subdirectories are skipped
+fn source_paths(input_dir: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for src in fs::read_dir(input_dir)? {
+        let src = src?;
+        if src.file_type()?.is_file() {
+            paths.push(src.path());
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
@@ -0,0 +1,195 @@
+use anyhow::{anyhow, Result};
+use indexmap::IndexMap;
+use std::collections::HashMap;
+
+use crate::twine_text::{format_raw_key, raw_sections, set_localizations, RawKey, RawSection};
+
+/// Which value to keep when both files have the same entry of a key with different values
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ConflictStrategy {
+    Primary,
+    Secondary,
+    Error,
+}
+
+/// Combines two twine files keeping formatting of the primary one. Keys and entries
+/// missing in the primary file are taken from the secondary one, keys are appended
+/// to the section of the same name or to a new section at the end of the file.
+pub fn merge(primary: &str, secondary: &str, strategy: ConflictStrategy) -> Result<String> {
+    let primary_sections = raw_sections(primary);
+    let primary_keys: HashMap<&str, &RawKey> = primary_sections
+        .iter()
+        .flat_map(|section| &section.keys)
+        .map(|key| (key.name.as_str(), key))
+        .collect();
+
+    let mut values: IndexMap<String, Vec<(String, String)>> = IndexMap::new();
+    let mut new_keys: IndexMap<String, Vec<RawKey>> = IndexMap::new();
+    let mut conflicts: Vec<String> = Vec::new();
+    for section in raw_sections(secondary) {
+        for key in section.keys {
+            let Some(primary_key) = primary_keys.get(key.name.as_str()) else {
+                new_keys.entry(section.name.clone()).or_default().push(key);
+                continue;
+            };
+            let mut key_values: Vec<(String, String)> = Vec::new();
+            for entry in key.entries {
+                let existing = primary_key
+                    .entries
+                    .iter()
+                    .find(|existing| existing.name == entry.name);
+                match existing {
+                    None => key_values.push((entry.name, entry.value)),
+                    Some(existing) if existing.value == entry.value => {}
+                    Some(_) if strategy == ConflictStrategy::Secondary => {
+                        key_values.push((entry.name, entry.value))
+                    }
+                    Some(_) if strategy == ConflictStrategy::Error => {
+                        conflicts.push(format!("{}: {}", key.name, entry.name))
+                    }
+                    Some(_) => {}
+                }
+            }
+            if !key_values.is_empty() {
+                values.insert(key.name, key_values);
+            }
+        }
+    }
+    if !conflicts.is_empty() {
+        return Err(anyhow!(
+            "Conflicting values of keys:\n{}",
+            conflicts.join("\n")
+        ));
+    }
+
+    let (updated, _) = set_localizations(primary, &values);
+    Ok(append_keys(&updated, new_keys))
+}
+
+fn append_keys(content: &str, new_keys: IndexMap<String, Vec<RawKey>>) -> String {
+    let sections = raw_sections(content);
+    let mut lines: Vec<String> = content.lines().map(|line| line.to_string()).collect();
+    // (line index, lines) to insert into existing sections
+    let mut insertions: Vec<(usize, Vec<String>)> = Vec::new();
+    let mut appended: Vec<String> = Vec::new();
+    for (section_name, keys) in new_keys {
+        let key_lines: Vec<String> = keys.iter().flat_map(format_key).collect();
+        match sections
+            .iter()
+            .find(|section| section.name == section_name && !section.keys.is_empty())
+        {
+            Some(section) => insertions.push((section_end(section), key_lines)),
+            // Keys before any section header go to the top of the file
+            None if section_name.is_empty() => {
+                let mut top: Vec<String> = key_lines.into_iter().skip(1).collect();
+                top.push(String::new());
+                insertions.push((0, top));
+            }
+            None => {
+                appended.push(String::new());
+                appended.push(format!("[[{}]]", section_name));
+                // Section header isn't separated from its first key
+                appended.extend(key_lines.into_iter().skip(1));
+            }
+        }
+    }
+    insertions.sort_by_key(|(line_index, _)| std::cmp::Reverse(*line_index));
+    for (line_index, inserted) in insertions {
+        lines.splice(line_index..line_index, inserted);
+    }
+    if lines.is_empty() && !appended.is_empty() {
+        appended.remove(0);
+    }
+    lines.extend(appended);
+
+    let mut output = lines.join("\n");
+    if content.ends_with('\n') || content.is_empty() {
+        output.push('\n');
+    }
+    output
+}
+
+/// Index of the line after the last entry of the section
+fn section_end(section: &RawSection) -> usize {
+    section
+        .keys
+        .iter()
+        .map(|key| {
+            key.entries
+                .iter()
+                .map(|entry| entry.line_index + 1)
+                .max()
+                .unwrap_or(key.line_number)
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Lines of the key preceded by a blank line
+fn format_key(key: &RawKey) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    lines.extend(format_raw_key(key));
+    lines
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn merges_keys_and_locales_of_secondary_file() -> Result<()> {
+    let primary = "[[General]]
+  [login]
+    en = Login
+    comment = Button title
+
+  [logout]
+    en = Logout
+
+[[Settings]]
+  [theme]
+    en = Theme
+";
+    let secondary = "[[General]]
+  [login]
+    en = Log in
+    de = Anmelden
+
+  [profile]
+    en = Profile
+
+[[Payment]]
+  [pay]
+    en = Pay
+    de = Bezahlen
+";
+
+    let actual = merge(primary, secondary, ConflictStrategy::Primary)?;
+    let expected = "[[General]]
+  [login]
+    en = Login
+    comment = Button title
+    de = Anmelden
+
+  [logout]
+    en = Logout
+
+  [profile]
+    en = Profile
+
+[[Settings]]
+  [theme]
+    en = Theme
+
+[[Payment]]
+  [pay]
+    en = Pay
+    de = Bezahlen
+";
+    assert_eq!(expected, actual);
+
+    let actual = merge(primary, secondary, ConflictStrategy::Secondary)?;
+    assert!(actual.contains("    en = Log in\n"));
+
+    let error = merge(primary, secondary, ConflictStrategy::Error).unwrap_err();
+    assert_eq!(error.to_string(), "Conflicting values of keys:\nlogin: en");
+
+    Ok(())
+}
@@ -0,0 +1,181 @@
+use std::{collections::BTreeMap, io::Write, path::Path};
+
+use crate::android_gen::key_name_supported_in_android;
+use crate::gen_error::GenError;
+use crate::parse::{array_text, File, PluralValue, StringValue};
+#[cfg(test)]
+use crate::parse::{Key, LocalizedString, Section};
+use crate::sink::WriteSink;
+
+/// Directory of the default language resources
+const BASE_DIR: &str = "base";
+
+/// String and plural resources of a single language
+#[derive(PartialEq, Eq, Debug, Default)]
+struct LangResources {
+    strings: Vec<(String, String)>,
+    plurals: Vec<(String, Vec<PluralValue>)>,
+}
+
+/// Resources of moko-resources library for Kotlin Multiplatform, written to
+/// `MR/<lang>/strings.xml` and `MR/<lang>/plurals.xml`
+pub struct GenResult {
+    value: BTreeMap<String, LangResources>,
+}
+
+impl GenResult {
+    /// Writes resources of every language into `dir/MR`, the default language goes to `MR/base`
+    pub fn write(
+        &self,
+        dir: impl AsRef<Path>,
+        default_lang: &Option<String>,
+        sink: &mut dyn WriteSink,
+    ) -> Result<(), GenError> {
+        for (lang, resources) in &self.value {
+            let mut dir_names = vec![lang.as_str()];
+            if default_lang.as_deref() == Some(lang.as_str()) {
+                dir_names.push(BASE_DIR);
+            }
+            for dir_name in dir_names {
+                let subpath = dir.as_ref().join("MR").join(dir_name);
+                let mut file: Vec<u8> = Vec::new();
+                resources.write_strings(&mut file)?;
+                sink.write(&subpath.join("strings.xml"), &file)?;
+                if !resources.plurals.is_empty() {
+                    let mut file: Vec<u8> = Vec::new();
+                    resources.write_plurals(&mut file)?;
+                    sink.write(&subpath.join("plurals.xml"), &file)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl LangResources {
+    fn write_strings<W: Write>(&self, writer: &mut W) -> Result<(), GenError> {
+        writer.write_all("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n".as_bytes())?;
+        writer.write_all("<resources>\n".as_bytes())?;
+        for (name, text) in &self.strings {
+            writer
+                .write_all(format!("  <string name=\"{}\">{}</string>\n", name, text).as_bytes())?;
+        }
+        writer.write_all("</resources>\n".as_bytes())?;
+        Ok(())
+    }
+
+    fn write_plurals<W: Write>(&self, writer: &mut W) -> Result<(), GenError> {
+        writer.write_all("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n".as_bytes())?;
+        writer.write_all("<resources>\n".as_bytes())?;
+        for (name, quantities) in &self.plurals {
+            writer.write_all(format!("  <plural name=\"{}\">\n", name).as_bytes())?;
+            let mut items: Vec<&PluralValue> = quantities.iter().collect();
+            items.sort_by_key(|item| item.quantity_as_cldr_category());
+            for item in items {
+                writer.write_all(
+                    format!(
+                        "    <item quantity=\"{}\">{}</item>\n",
+                        item.quantity, item.text
+                    )
+                    .as_bytes(),
+                )?;
+            }
+            writer.write_all("  </plural>\n".as_bytes())?;
+        }
+        writer.write_all("</resources>\n".as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Groups keys of all sections by language. Like Android resources, a key with any plural
+/// localization is a plural in all languages.
+pub fn generate(source: &File) -> Result<GenResult, GenError> {
+    let mut result: BTreeMap<String, LangResources> = BTreeMap::new();
+    for key in source.sections.iter().flat_map(|section| &section.keys) {
+        if !key_name_supported_in_android(&key.name) {
+            return Err(GenError::InvalidKeyName(key.name.clone()));
+        }
+        let is_plural_key = key
+            .localizations
+            .iter()
+            .any(|localization| matches!(localization.value, StringValue::Plural { .. }));
+        for localization in &key.localizations {
+            let resources = result
+                .entry(localization.language_code.clone())
+                .or_default();
+            match &localization.value {
+                StringValue::Single(text) if is_plural_key => resources.plurals.push((
+                    key.name.clone(),
+                    vec![PluralValue::new("other", text.clone())],
+                )),
+                StringValue::Single(text) => {
+                    resources.strings.push((key.name.clone(), text.clone()))
+                }
+                StringValue::Array(items) => resources
+                    .strings
+                    .push((key.name.clone(), array_text(items))),
+                StringValue::Plural { quantities } => {
+                    if let Some(item) = quantities
+                        .iter()
+                        .find(|item| item.quantity_as_cldr_category().is_none())
+                    {
+                        return Err(GenError::UnsupportedPluralCategory(item.quantity.clone()));
+                    }
+                    resources
+                        .plurals
+                        .push((key.name.clone(), quantities.clone()));
+                }
+            }
+        }
+    }
+    Ok(GenResult { value: result })
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn writes_moko_plurals() -> Result<(), GenError> {
+    let source = File {
+        sections: vec![Section {
+            keys: vec![Key {
+                name: "days".to_string(),
+                localizations: vec![
+                    LocalizedString {
+                        language_code: "en".to_string(),
+                        value: StringValue::Plural {
+                            quantities: vec![
+                                PluralValue::new("other", "%d days"),
+                                PluralValue::new("one", "%d day"),
+                            ],
+                        },
+                    },
+                    LocalizedString {
+                        language_code: "ja".to_string(),
+                        value: StringValue::Single("%d日".to_string()),
+                    },
+                ],
+                max_length: None,
+                comment: None,
+                translatable: true,
+            }],
+        }],
+    };
+
+    let generated = generate(&source)?;
+    let mut buffer: Vec<u8> = Vec::new();
+    generated.value["en"].write_plurals(&mut buffer)?;
+    assert_eq!(
+        String::from_utf8(buffer).unwrap(),
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<resources>
+  <plural name=\"days\">
+    <item quantity=\"one\">%d day</item>
+    <item quantity=\"other\">%d days</item>
+  </plural>
+</resources>
+"
+    );
+    assert_eq!(generated.value["ja"].plurals[0].1[0].quantity, "other");
+    assert!(generated.value["ja"].strings.is_empty());
+
+    Ok(())
+}
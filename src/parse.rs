@@ -3,13 +3,17 @@ use const_format::concatcp;
 use indexmap::{map::Entry, IndexMap};
 use lazy_static::lazy_static;
 use regex::{Captures, Match, Regex};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Error;
-use std::fs::File as FsFile;
-use std::io::{self, BufReader, Write};
-use std::io::{BufRead, BufWriter};
-use std::{borrow::Cow, fmt::format, path::Path};
-use tempfile::NamedTempFile;
+use std::io::{self, BufRead, BufReader, Write};
+use std::io::BufWriter;
+use std::{borrow::Cow, fs, path::Path};
+
+use crate::fs_util::read_to_string_with_encoding;
+use crate::lang::validate_lang_code;
+#[cfg(test)]
+use crate::lang::LangError;
+use crate::parse_error::{MergeError, ParseError, ParseWarning};
 
 // Taken from
 // https://developer.android.com/guide/topics/resources/string-resource.html#StylingWithHTML
@@ -40,53 +44,465 @@ const ANDROID_SUPPORTED_TAGS: &'static [&'static str] = &[
 ];
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema))]
 pub struct File {
     pub sections: Vec<Section>,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema))]
 pub struct Section {
     pub keys: Vec<Key>,
 }
 
 /// Represents a string resource key with its localizations
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema))]
 pub struct Key {
     pub name: String,
     pub localizations: Vec<LocalizedString>,
+    /// Length limit of values from `max_length` attribute, a hint for translators
+    pub max_length: Option<usize>,
+    /// Comment lines right above the key header followed by `comment` attribute, joined with newlines
+    pub comment: Option<String>,
+    /// False if `__translatable = false` attribute is set, e.g. for URLs or app ids which
+    /// are the same in all languages
+    pub translatable: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema))]
 pub struct LocalizedString {
     pub language_code: String,
     pub value: StringValue,
 }
 
 #[derive(PartialEq, Eq, Debug, PartialOrd, Ord, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema))]
 pub enum StringValue {
     Single(String),
     Plural { quantities: Vec<PluralValue> },
+    /// Ordered list of values of a key with `__type = string-array` attribute
+    Array(Vec<String>),
 }
 
 #[derive(PartialEq, Eq, Debug, PartialOrd, Ord, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema))]
 pub struct PluralValue {
     /// quantity can be: "zero", "one", "two", "few", "many", and "other"
     pub quantity: String,
     pub text: String,
 }
 
-pub fn parse<T: AsRef<Path>>(path: T) -> Result<File, String> {
+/// CLDR plural categories, declared in CLDR canonical order
+#[derive(PartialEq, Eq, Hash, Debug, PartialOrd, Ord, Clone, Copy)]
+pub enum CldrCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+/// Language which other keys of the file have, but the key doesn't
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct MissingTranslation {
+    pub key_name: String,
+    pub missing_lang: String,
+}
+
+impl File {
+    /// Language codes of all keys in order of their first appearance
+    pub fn languages(&self) -> Vec<&str> {
+        let mut result: Vec<&str> = Vec::new();
+        for section in &self.sections {
+            for localization in section.keys.iter().flat_map(|key| &key.localizations) {
+                if !result.contains(&localization.language_code.as_str()) {
+                    result.push(&localization.language_code);
+                }
+            }
+        }
+        result
+    }
+
+    /// Appends sections of `other` keeping the order of keys. Fails if both files have
+    /// a key with the same name and kind of values, see `merge_overwriting` to allow it.
+    pub fn merge(self, other: File) -> Result<File, MergeError> {
+        self.merge_with(other, false)
+    }
+
+    /// Same as `merge`, but keys of `other` replace keys of this file with the same name
+    /// in place
+    pub fn merge_overwriting(self, other: File) -> File {
+        self.merge_with(other, true)
+            .expect("Merge with overwriting never fails")
+    }
+
+    fn merge_with(mut self, other: File, overwrite: bool) -> Result<File, MergeError> {
+        for mut section in other.sections {
+            let mut keys = Vec::with_capacity(section.keys.len());
+            for key in section.keys.drain(..) {
+                let existing = self
+                    .sections
+                    .iter_mut()
+                    .flat_map(|section| &mut section.keys)
+                    .find(|existing| {
+                        existing.name == key.name && value_kind(existing) == value_kind(&key)
+                    });
+                match existing {
+                    Some(_) if !overwrite => {
+                        return Err(MergeError::DuplicateKey { name: key.name })
+                    }
+                    Some(existing) => *existing = key,
+                    None => keys.push(key),
+                }
+            }
+            if !keys.is_empty() {
+                self.sections.push(Section { keys });
+            }
+        }
+        Ok(self)
+    }
+
+    /// Removes keys which names don't match glob `pattern`, e.g. `login_*`,
+    /// returns the number of removed keys
+    pub fn retain_keys_matching(&mut self, pattern: &str) -> usize {
+        self.retain_keys_matching_any(&[pattern])
+    }
+
+    /// Same as `retain_keys_matching`, but keeps keys matching any of `patterns`
+    pub fn retain_keys_matching_any<P: AsRef<str>>(&mut self, patterns: &[P]) -> usize {
+        let mut removed = 0;
+        for section in &mut self.sections {
+            let count = section.keys.len();
+            section.keys.retain(|key| {
+                patterns
+                    .iter()
+                    .any(|pattern| glob_match(pattern.as_ref(), &key.name))
+            });
+            removed += count - section.keys.len();
+        }
+        removed
+    }
+
+    /// Every key and language of `languages` which the key has no localization for,
+    /// keys with `__translatable = false` are not expected to be translated
+    pub fn missing_translations(&self) -> Vec<MissingTranslation> {
+        let languages = self.languages();
+        let mut result: Vec<MissingTranslation> = Vec::new();
+        let keys = self.sections.iter().flat_map(|section| &section.keys);
+        for key in keys.filter(|key| key.translatable) {
+            for lang in &languages {
+                let exists = key
+                    .localizations
+                    .iter()
+                    .any(|localization| localization.language_code == *lang);
+                if !exists {
+                    result.push(MissingTranslation {
+                        key_name: key.name.clone(),
+                        missing_lang: lang.to_string(),
+                    });
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Whether `name` matches `pattern` where `*` stands for any sequence of characters
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let Some((prefix, rest)) = pattern.split_once('*') else {
+        return pattern == name;
+    };
+    let Some(mut remaining) = name.strip_prefix(prefix) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = rest.split('*').collect();
+    let suffix = parts.pop().unwrap_or_default();
+    for part in parts {
+        match remaining.find(part) {
+            Some(index) => remaining = &remaining[index + part.len()..],
+            None => return false,
+        }
+    }
+    remaining.ends_with(suffix)
+}
+
+impl CldrCategory {
+    /// Returns `None` if quantity is not one of the CLDR plural categories
+    pub fn from_quantity(quantity: &str) -> Option<CldrCategory> {
+        match quantity {
+            "zero" => Some(CldrCategory::Zero),
+            "one" => Some(CldrCategory::One),
+            "two" => Some(CldrCategory::Two),
+            "few" => Some(CldrCategory::Few),
+            "many" => Some(CldrCategory::Many),
+            "other" => Some(CldrCategory::Other),
+            _ => None,
+        }
+    }
+
+    /// Cardinal plural categories used by the language according to CLDR, region and script
+    /// subtags are ignored. Returns `None` for languages missing in the table.
+    pub fn for_language(lang: &str) -> Option<&'static [CldrCategory]> {
+        use CldrCategory::*;
+        let base = lang.split(['-', '_']).next().unwrap_or(lang);
+        let categories: &'static [CldrCategory] = match base {
+            "ja" | "ko" | "zh" | "vi" | "th" | "id" | "ms" | "lo" | "km" | "my" => &[Other],
+            "en" | "de" | "nl" | "sv" | "da" | "nb" | "nn" | "no" | "fi" | "et" | "el" | "hu"
+            | "tr" | "bg" | "hi" | "bn" | "ka" | "kk" | "az" | "fa" | "ur" | "sw" | "mn" => {
+                &[One, Other]
+            }
+            "fr" | "es" | "it" | "pt" | "ca" => &[One, Many, Other],
+            "ru" | "uk" | "be" | "pl" | "cs" | "sk" | "lt" => &[One, Few, Many, Other],
+            "sr" | "hr" | "bs" | "ro" => &[One, Few, Other],
+            "sl" => &[One, Two, Few, Other],
+            "lv" => &[Zero, One, Other],
+            "he" => &[One, Two, Other],
+            "ga" | "mt" => &[One, Two, Few, Many, Other],
+            "ar" | "cy" => &[Zero, One, Two, Few, Many, Other],
+            _ => return None,
+        };
+        Some(categories)
+    }
+}
+
+impl LocalizedString {
+    pub fn contains_placeholder(&self) -> bool {
+        self.placeholder_count() > 0
+    }
+
+    /// Number of distinct placeholders, e.g. `%1$s` used twice is counted once.
+    /// The largest count among quantities is taken for plurals.
+    pub fn placeholder_count(&self) -> usize {
+        let count = |text: &str| {
+            PLACEHOLDER_OR_PERCENT_RE
+                .find_iter(text)
+                .map(|m| m.as_str())
+                .filter(|placeholder| *placeholder != "%%")
+                .collect::<HashSet<&str>>()
+                .len()
+        };
+        match &self.value {
+            StringValue::Single(text) => count(text),
+            StringValue::Plural { quantities } => quantities
+                .iter()
+                .map(|item| count(&item.text))
+                .max()
+                .unwrap_or(0),
+            StringValue::Array(items) => items.iter().map(|item| count(item)).max().unwrap_or(0),
+        }
+    }
+}
+
+/// Text of array values for platforms without string arrays, items are joined with `, `
+pub(crate) fn array_text(items: &[String]) -> String {
+    items.join(", ")
+}
+
+impl PluralValue {
+    pub fn new(quantity: impl Into<String>, text: impl Into<String>) -> Self {
+        PluralValue {
+            quantity: quantity.into(),
+            text: text.into(),
+        }
+    }
+
+    pub fn quantity(&self) -> &str {
+        &self.quantity
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns `None` if quantity is not one of the CLDR plural categories
+    pub fn quantity_as_cldr_category(&self) -> Option<CldrCategory> {
+        CldrCategory::from_quantity(&self.quantity)
+    }
+}
+
+/// Why a localization was left out of the parsed file
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SkipReason {
+    EmptyValue,
+}
+
+impl SkipReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SkipReason::EmptyValue => "empty_value",
+        }
+    }
+}
+
+/// Localization left out of the parsed file
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct SkippedKey {
+    pub key: String,
+    /// Locale as written in the file, with quantity for plurals, e.g. `en:one`
+    pub locale: String,
+    pub reason: SkipReason,
+    /// 1-based number of the line with the localization
+    pub source_line: Option<usize>,
+}
+
+impl SkippedKey {
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "key": self.key,
+            "locale": self.locale,
+            "reason": self.reason.as_str(),
+            "source_line": self.source_line,
+        })
+    }
+}
+
+/// Builds a `PluralValue`, e.g. `PluralValueBuilder::new("one", "%d apple").build()`
+pub struct PluralValueBuilder {
+    quantity: String,
+    text: String,
+}
+
+impl PluralValueBuilder {
+    pub fn new(quantity: impl Into<String>, text: impl Into<String>) -> Self {
+        PluralValueBuilder {
+            quantity: quantity.into(),
+            text: text.into(),
+        }
+    }
+
+    pub fn quantity(mut self, quantity: impl Into<String>) -> Self {
+        self.quantity = quantity.into();
+        self
+    }
+
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.text = text.into();
+        self
+    }
+
+    pub fn build(self) -> PluralValue {
+        PluralValue::new(self.quantity, self.text)
+    }
+}
+
+impl StringValue {
+    /// Starts building `StringValue::Plural`, e.g.
+    /// `StringValue::plural().quantity("one", "%d apple").quantity("other", "%d apples").build()`
+    pub fn plural() -> PluralValueListBuilder {
+        PluralValueListBuilder::default()
+    }
+}
+
+/// Collects quantities of `StringValue::Plural`, see `StringValue::plural`
+#[derive(Default)]
+pub struct PluralValueListBuilder {
+    quantities: Vec<PluralValue>,
+}
+
+impl PluralValueListBuilder {
+    pub fn quantity(mut self, quantity: impl Into<String>, text: impl Into<String>) -> Self {
+        self.quantities.push(PluralValue::new(quantity, text));
+        self
+    }
+
+    pub fn value(mut self, value: PluralValue) -> Self {
+        self.quantities.push(value);
+        self
+    }
+
+    pub fn build(self) -> StringValue {
+        StringValue::Plural {
+            quantities: self.quantities,
+        }
+    }
+}
+
+/// Options of `parse_with_options`
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Keep the last definition of keys defined more than once instead of failing
+    /// with `ParseError::DuplicateKey`, a warning is printed for every such key
+    pub allow_duplicate_keys: bool,
+    /// Fail with `ParseError::InvalidLanguageCode` instead of printing a warning
+    /// for language codes which are not BCP 47 tags, e.g. `eng` or `zh_CN`
+    pub strict_lang: bool,
+}
+
+pub fn parse<T: AsRef<Path>>(path: T) -> Result<File, ParseError> {
+    parse_with_options(path, &ParseOptions::default())
+}
+
+/// Same as `parse`, but with `options` which relax checks of the content
+pub fn parse_with_options<T: AsRef<Path>>(
+    path: T,
+    options: &ParseOptions,
+) -> Result<File, ParseError> {
+    let (content, _) = read_to_string_with_encoding(path.as_ref(), None)?;
+    let (file, skipped, warnings) = parse_content(&content, options)?;
+    print_skipped(&skipped);
+    for warning in warnings {
+        eprintln!("warning: {}", warning);
+    }
+    Ok(file)
+}
+
+/// Parses twine content which is already in memory, e.g. a fixture embedded in tests
+pub fn parse_str(input: &str) -> Result<File, ParseError> {
+    let (file, skipped) = parse_str_with_skipped(input)?;
+    print_skipped(&skipped);
+    Ok(file)
+}
+
+/// Same as `parse`, but returns skipped localizations instead of printing them
+pub fn parse_with_skipped<T: AsRef<Path>>(
+    path: T,
+) -> Result<(File, Vec<SkippedKey>), ParseError> {
+    let (content, _) = read_to_string_with_encoding(path.as_ref(), None)?;
+    parse_str_with_skipped(&content)
+}
+
+/// Same as `parse_str`, but returns skipped localizations instead of printing them
+pub fn parse_str_with_skipped(content: &str) -> Result<(File, Vec<SkippedKey>), ParseError> {
+    let (file, skipped, _) = parse_content(content, &ParseOptions::default())?;
+    Ok((file, skipped))
+}
+
+/// Same as `parse_with_options`, but returns warnings instead of printing them
+pub fn parse_str_with_warnings(
+    content: &str,
+    options: &ParseOptions,
+) -> Result<(File, Vec<ParseWarning>), ParseError> {
+    let (file, _, warnings) = parse_content(content, options)?;
+    Ok((file, warnings))
+}
+
+/// Parses `content` into the file, skipped localizations and warnings about problems
+/// which `options` allow
+fn parse_content(
+    content: &str,
+    options: &ParseOptions,
+) -> Result<(File, Vec<SkippedKey>, Vec<ParseWarning>), ParseError> {
     let mut default = IniDefault::default();
     default.case_sensitive = true;
     default.delimiters = vec!['='];
-    default.comment_symbols = vec!['#'];
+    // configparser strips comments in the middle of values too, e.g. `#FF0000` colors,
+    // so full line comments are removed by `dedup_keys` instead
+    default.comment_symbols = Vec::new();
     let mut config = Ini::new_from_defaults(default);
 
+    validate_file_integrity(content)?;
+    validate_sections_have_keys(content)?;
+
     // See NOTE_DEDUPLICATING_KEYS
-    let temp_file =
-        NamedTempFile::new().map_err(|_| "failed to create temporary file".to_string())?;
-    dedup_keys(&path, &temp_file).map_err(|error| (error.to_string() + " failed to dedup keys").to_string())?;
-    let map = config.load(temp_file)?;
+    let mut deduped: Vec<u8> = Vec::with_capacity(content.len());
+    dedup_keys(content, &mut deduped)?;
+    let deduped = String::from_utf8(deduped)
+        .map_err(|error| ParseError::InvalidEncoding(error.to_string()))?;
+    let map = config.read(deduped).map_err(ParseError::Syntax)?;
+    let comments = key_comments(content);
 
     // NOTE: twine has this structure
     // [[Section1]]
@@ -109,26 +525,381 @@ pub fn parse<T: AsRef<Path>>(path: T) -> Result<File, String> {
     // [login_screen_title]
     // en = Login
     // ru = Логин
+    let mut skipped: Vec<SkippedKey> = Vec::new();
+    let mut warnings: Vec<ParseWarning> = Vec::new();
+    let mut checked_codes: HashSet<String> = HashSet::new();
     for (resource_key_name, localizations) in map {
-        let key = key_from_locale_value_map(resource_key_name, localizations)?;
+        let empty_locales = localizations.iter().filter(|(locale, value)| {
+            value.is_none() && !KEY_ATTRIBUTES.contains(&locale.as_str())
+        });
+        for (locale, _) in empty_locales {
+            let name = resource_key_name
+                .strip_suffix(DEDUP_SUFFIX)
+                .unwrap_or(&resource_key_name);
+            skipped.push(SkippedKey {
+                key: name.to_string(),
+                locale: locale.clone(),
+                reason: SkipReason::EmptyValue,
+                source_line: find_value_line(content, name, locale),
+            });
+        }
+        let mut key = key_from_locale_value_map(resource_key_name, localizations)?;
+        let lines = comments.get(&key.name).into_iter().flatten().cloned();
+        let lines: Vec<String> = lines.chain(key.comment.take()).collect();
+        if !lines.is_empty() {
+            key.comment = Some(lines.join("\n"));
+        }
+        // A plural and a regular string may share the name, see NOTE_DEDUPLICATING_KEYS
+        let first = section
+            .keys
+            .iter()
+            .position(|other| other.name == key.name && value_kind(other) == value_kind(&key));
+        if let Some(index) = first {
+            let sections = key_section_numbers(content, &key.name);
+            let first_section = sections.first().copied().unwrap_or_default();
+            let second_section = sections.get(1).copied().unwrap_or_default();
+            if !options.allow_duplicate_keys {
+                return Err(ParseError::DuplicateKey {
+                    name: key.name,
+                    first_section,
+                    second_section,
+                });
+            }
+            warnings.push(ParseWarning::DuplicateKey {
+                name: key.name.clone(),
+                first_section,
+                second_section,
+            });
+            section.keys.remove(index);
+        }
+        for localization in &key.localizations {
+            let code = &localization.language_code;
+            if !checked_codes.insert(code.clone()) {
+                continue;
+            }
+            if let Err(error) = validate_lang_code(code) {
+                if options.strict_lang {
+                    return Err(ParseError::InvalidLanguageCode {
+                        key: key.name,
+                        code: code.clone(),
+                        error,
+                    });
+                }
+                warnings.push(ParseWarning::InvalidLanguageCode {
+                    key: key.name.clone(),
+                    code: code.clone(),
+                    error,
+                });
+            }
+        }
         section.keys.push(key);
     }
-    Ok(File {
+    let file = File {
         // For now only supporting a single section, see the comment above
         sections: vec![section],
-    })
+    };
+    Ok((file, skipped, warnings))
+}
+
+/// Kind of values of the key, keys of different kinds may have the same name
+fn value_kind(key: &Key) -> Option<std::mem::Discriminant<StringValue>> {
+    let value = &key.localizations.first()?.value;
+    Some(std::mem::discriminant(value))
+}
+
+/// Numbers of `[[Section]]` blocks of all definitions of the key starting from 1,
+/// 0 for definitions above the first block
+fn key_section_numbers(content: &str, key_name: &str) -> Vec<usize> {
+    let header = format!("[{}]", key_name);
+    let mut section = 0;
+    let mut numbers = Vec::new();
+    for line in content.lines().map(str::trim) {
+        if line.starts_with("[[") {
+            section += 1;
+        } else if line == header {
+            numbers.push(section);
+        }
+    }
+    numbers
+}
+
+/// Parses twine file key by key, so that only the key being parsed is kept in memory,
+/// e.g. `for key in TwineParser::from_path(path)? { ... }`. Yields the same keys as `parse`,
+/// except that comments of keys defined more than once are not shared between them.
+/// Content is expected to be UTF-8.
+pub struct TwineParser<R: BufRead> {
+    lines: io::Lines<R>,
+    line_number: usize,
+    /// Key which lines are being read
+    current: Option<RawKey>,
+    /// Comment lines right above the next key header
+    comments: Vec<String>,
+    /// Last line which is not empty or a comment, checked for truncation at the end
+    last_line: Option<String>,
+    skipped: Vec<SkippedKey>,
+    finished: bool,
+}
+
+/// Key lines as they are written in twine file
+struct RawKey {
+    name: String,
+    localizations: IndexMap<String, Option<String>>,
+    comments: Vec<String>,
+}
+
+impl TwineParser<BufReader<fs::File>> {
+    pub fn from_path<T: AsRef<Path>>(path: T) -> Result<Self, ParseError> {
+        Ok(TwineParser::new(BufReader::new(fs::File::open(path)?)))
+    }
+}
+
+impl<R: BufRead> TwineParser<R> {
+    pub fn new(reader: R) -> Self {
+        TwineParser {
+            lines: reader.lines(),
+            line_number: 0,
+            current: None,
+            comments: Vec::new(),
+            last_line: None,
+            skipped: Vec::new(),
+            finished: false,
+        }
+    }
+
+    /// Localizations left out of keys yielded so far
+    pub fn skipped(&self) -> &[SkippedKey] {
+        &self.skipped
+    }
+
+    /// Reads lines up to the end of the current key, returns `None` at the end of the file
+    fn next_raw_key(&mut self) -> Result<Option<RawKey>, ParseError> {
+        for line in self.lines.by_ref() {
+            let line = line?;
+            self.line_number += 1;
+            let line = match self.line_number {
+                1 => line.trim_start_matches('\u{feff}').trim(),
+                _ => line.trim(),
+            };
+            if line.is_empty() {
+                self.comments.clear();
+                continue;
+            }
+            if let Some(comment) = line.strip_prefix(COMMENT_SYMBOLS) {
+                self.comments.push(comment.trim().to_string());
+                continue;
+            }
+            self.last_line = Some(line.to_string());
+
+            if line.starts_with('[') {
+                let Some(end) = line.rfind(']') else {
+                    return Err(ParseError::Syntax(format!(
+                        "line {}: Found opening bracket for section name but no closing bracket",
+                        self.line_number
+                    )));
+                };
+                let mut comments = std::mem::take(&mut self.comments);
+                // Comments above section headers are not comments of keys
+                if line.starts_with("[[") {
+                    comments.clear();
+                }
+                let next = RawKey {
+                    name: line[1..end].trim().to_string(),
+                    localizations: IndexMap::new(),
+                    comments,
+                };
+                // Keys without values are dropped the same way as by `parse`
+                match self.current.replace(next) {
+                    Some(key) if !key.localizations.is_empty() => return Ok(Some(key)),
+                    _ => continue,
+                }
+            }
+
+            self.comments.clear();
+            let key = self.current.get_or_insert_with(|| RawKey {
+                // configparser puts values above the first header into "default" section
+                name: "default".to_string(),
+                localizations: IndexMap::new(),
+                comments: Vec::new(),
+            });
+            let (locale, value) = match line.split_once('=') {
+                Some((locale, value)) => (locale.trim(), Some(value.trim().to_string())),
+                None => (line, None),
+            };
+            if locale.is_empty() {
+                return Err(ParseError::Syntax(format!(
+                    "line {}: Key cannot be empty",
+                    self.line_number
+                )));
+            }
+            if value.is_none() && !KEY_ATTRIBUTES.contains(&locale) {
+                self.skipped.push(SkippedKey {
+                    key: key.name.clone(),
+                    locale: locale.to_string(),
+                    reason: SkipReason::EmptyValue,
+                    source_line: Some(self.line_number),
+                });
+            }
+            key.localizations.insert(locale.to_string(), value);
+        }
+
+        if let Some(last_line) = self.last_line.take() {
+            validate_last_line(&last_line)?;
+        }
+        let key = self.current.take();
+        Ok(key.filter(|key| !key.localizations.is_empty()))
+    }
+}
+
+impl<R: BufRead> Iterator for TwineParser<R> {
+    type Item = Result<Key, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let raw = match self.next_raw_key() {
+            Ok(Some(raw)) => raw,
+            Ok(None) => {
+                self.finished = true;
+                return None;
+            }
+            Err(err) => {
+                self.finished = true;
+                return Some(Err(err));
+            }
+        };
+        let result = key_from_locale_value_map(raw.name, raw.localizations).map(|mut key| {
+            let lines: Vec<String> = raw.comments.into_iter().chain(key.comment.take()).collect();
+            if !lines.is_empty() {
+                key.comment = Some(lines.join("\n"));
+            }
+            key
+        });
+        Some(result)
+    }
+}
+
+fn print_skipped(skipped: &[SkippedKey]) {
+    for item in skipped {
+        println!(
+            "skipped key \"{}\" for \"{}\" because it's empty",
+            item.locale, item.key
+        );
+    }
+}
+
+/// Finds 1-based number of the line with `locale` of the first key named `key_name`
+fn find_value_line(content: &str, key_name: &str, locale: &str) -> Option<usize> {
+    let header = format!("[{}]", key_name);
+    let mut lines = content.lines().map(str::trim).enumerate();
+    lines.find(|(_, line)| *line == header)?;
+    lines
+        .take_while(|(_, line)| !line.starts_with('['))
+        .find(|(_, line)| {
+            line.split_once('=')
+                .map_or(line == &locale, |(name, _)| name.trim() == locale)
+        })
+        .map(|(index, _)| index + 1)
+}
+
+/// Checks that twine file is not truncated, e.g. by an interrupted write,
+/// configparser would silently parse only the part before the truncation
+pub fn validate_file_integrity(content: &str) -> Result<(), ParseError> {
+    let mut lines = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with(COMMENT_SYMBOLS));
+    match lines.next_back() {
+        Some(last_line) => validate_last_line(last_line),
+        None => Ok(()),
+    }
+}
+
+/// Checks that every `[[Section]]` header is followed by at least one key before the next
+/// section or the end of the file
+fn validate_sections_have_keys(content: &str) -> Result<(), ParseError> {
+    let mut in_empty_section = false;
+    for line in content.lines().map(str::trim) {
+        if line.starts_with("[[") {
+            if in_empty_section {
+                return Err(ParseError::EmptySection);
+            }
+            in_empty_section = true;
+        } else if line.starts_with('[') {
+            in_empty_section = false;
+        }
+    }
+    if in_empty_section {
+        return Err(ParseError::EmptySection);
+    }
+    Ok(())
+}
+
+/// Checks the last line of twine file which is not empty or a comment, see
+/// `validate_file_integrity`
+fn validate_last_line(last_line: &str) -> Result<(), ParseError> {
+    if last_line.starts_with('[') {
+        if !last_line.ends_with(']') {
+            return Err(ParseError::TruncatedFile(format!(
+                "file ends with unclosed section \"{}\"",
+                last_line
+            )));
+        }
+        if !last_line.starts_with("[[") {
+            return Err(ParseError::TruncatedFile(format!(
+                "file ends with key {} which has no values",
+                last_line
+            )));
+        }
+        return Ok(());
+    }
+    match last_line.split_once('=') {
+        None => Err(ParseError::TruncatedFile(format!(
+            "file ends with incomplete line \"{}\"",
+            last_line
+        ))),
+        Some((name, value)) if value.trim().is_empty() => Err(ParseError::TruncatedFile(format!(
+            "file ends with \"{}\" which has no value",
+            name.trim()
+        ))),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Collects consecutive `#` and `;` comment lines right above each key header,
+/// comment symbols are stripped. Comments of the first key with the name are kept.
+fn key_comments(content: &str) -> HashMap<String, Vec<String>> {
+    let mut result: HashMap<String, Vec<String>> = HashMap::new();
+    let mut block: Vec<String> = Vec::new();
+    for line in content.lines().map(str::trim) {
+        if let Some(comment) = line.strip_prefix(COMMENT_SYMBOLS) {
+            block.push(comment.trim().to_string());
+            continue;
+        }
+        if line.starts_with('[') && !line.starts_with("[[") && !block.is_empty() {
+            let name = line.trim_matches(|c| c == '[' || c == ']').to_string();
+            result.entry(name).or_insert(std::mem::take(&mut block));
+        }
+        block.clear();
+    }
+    result
+}
+
+/// JSON Schema of the parsed file representation, generated from its serde representation
+#[cfg(feature = "serde")]
+pub fn file_json_schema() -> String {
+    let schema = schemars::schema_for!(File);
+    // Serializing of generated schema can't fail
+    serde_json::to_string_pretty(&schema).unwrap()
 }
 
 // TODO remove this function and write a custom parser
 // See NOTE_DEDUPLICATING_KEYS
-fn dedup_keys<T: AsRef<Path>, W: Write>(path: &T, temp_file: W) -> io::Result<()> {
-    let f = FsFile::open(path)?;
-    let f = BufReader::new(f);
+fn dedup_keys<W: Write>(content: &str, temp_file: W) -> io::Result<()> {
     let mut of = BufWriter::new(temp_file);
     let mut keys: HashSet<String> = HashSet::new();
 
-    for line in f.lines() {
-        let l = line?;
+    for l in content.lines() {
         let maybe_key = l.trim();
         let out_line: String;
         if keys.iter().any(|x| x == maybe_key) {
@@ -137,6 +908,9 @@ fn dedup_keys<T: AsRef<Path>, W: Write>(path: &T, temp_file: W) -> io::Result<()
                 maybe_key.trim_matches(|c| c == '[' || c == ']'),
                 DEDUP_SUFFIX
             );
+        } else if maybe_key.starts_with(COMMENT_SYMBOLS) {
+            // configparser doesn't handle comments, see `parse`
+            out_line = "\n".to_string();
         } else {
             if maybe_key.starts_with('[') && !maybe_key.starts_with("[[") {
                 keys.insert(maybe_key.to_string());
@@ -149,30 +923,108 @@ fn dedup_keys<T: AsRef<Path>, W: Write>(path: &T, temp_file: W) -> io::Result<()
 }
 
 const DEDUP_SUFFIX: &str = "_dedup";
+const COMMENT_SYMBOLS: [char; 2] = ['#', ';'];
+
+/// Key entries which are not localizations
+pub(crate) const COMMENT_ATTRIBUTE: &str = "comment";
+pub(crate) const MAX_LENGTH_ATTRIBUTE: &str = "max_length";
+pub(crate) const TRANSLATABLE_ATTRIBUTE: &str = "__translatable";
+pub(crate) const TYPE_ATTRIBUTE: &str = "__type";
+/// Key attribute which marks keys having machine translated localizations
+pub const AUTO_TRANSLATED_ATTRIBUTE: &str = "auto_translated";
+/// `__type` of keys which values are lists of items, e.g. `Mon | Tue | Wed`
+pub(crate) const STRING_ARRAY_TYPE: &str = "string-array";
+/// Separator of items of `string-array` values
+pub const ARRAY_DELIMITER: &str = "|";
+pub(crate) const KEY_ATTRIBUTES: &[&str] = &[
+    COMMENT_ATTRIBUTE,
+    "tags",
+    MAX_LENGTH_ATTRIBUTE,
+    AUTO_TRANSLATED_ATTRIBUTE,
+    TRANSLATABLE_ATTRIBUTE,
+    TYPE_ATTRIBUTE,
+];
 
 const PLACEHOLDER_FLAGS_WIDTH_PRECISION_LENGTH: &str =
     r"([-+0#,])?(\d+|\*)?(\.(\d+|\*))?(hh?|ll?|L|z|j|t|q)?";
 const PLACEHOLDER_PARAMETER_FLAGS_WIDTH_PRECISION_LENGTH: &str =
     concatcp!(r"(\d+\$)?", PLACEHOLDER_FLAGS_WIDTH_PRECISION_LENGTH);
-const PLACEHOLDER_TYPES: &str = "[diufFeEgGxXoscpaA@]";
-const PLACEHOLDER_REGEX: &str = concatcp!(
+const PLACEHOLDER_TYPES: &str = "([diufFeEgGxXoscpaA@])";
+pub(crate) const PLACEHOLDER_REGEX: &str = concatcp!(
     "%",
     PLACEHOLDER_PARAMETER_FLAGS_WIDTH_PRECISION_LENGTH,
     PLACEHOLDER_TYPES
 );
-const NON_NUMBERED_PLACEHOLDER_REGEX: &str = concatcp!(
+lazy_static! {
+    /// `PLACEHOLDER_REGEX` which also matches escaped `%%` first so that it isn't taken
+    /// for the start of a placeholder, e.g. `100%%d`. Group 1 is the position, e.g. `1$`,
+    /// and group 7 is the conversion, both are absent for `%%`.
+    pub(crate) static ref PLACEHOLDER_OR_PERCENT_RE: Regex =
+        Regex::new(concatcp!("%%|", PLACEHOLDER_REGEX)).unwrap();
+}
+pub(crate) const NON_NUMBERED_PLACEHOLDER_REGEX: &str = concatcp!(
     "%(",
     PLACEHOLDER_FLAGS_WIDTH_PRECISION_LENGTH,
     PLACEHOLDER_TYPES,
     ")"
 );
-const SINGLE_PERCENT_REGEX: &str = r"([^%][%][^%]|[^%][%]$|^[%]$)";
+pub(crate) const SINGLE_PERCENT_REGEX: &str = r"([^%][%][^%]|[^%][%]$|^[%][^%]|^[%]$)";
+// `%%` goes first so that escaped percent isn't taken as the start of `%@`
+pub(crate) const TWINE_STRING_PLACEHOLDER_REGEX: &str = concatcp!(
+    "%%|%(",
+    PLACEHOLDER_PARAMETER_FLAGS_WIDTH_PRECISION_LENGTH,
+    ")@"
+);
 
 fn key_from_locale_value_map(
     name: String,
     raw_localizations: IndexMap<String, Option<String>>,
-) -> Result<Key, String> {
-    if raw_localizations.keys().any(|l| l.contains(':')) {
+) -> Result<Key, ParseError> {
+    let max_length = match raw_localizations.get(MAX_LENGTH_ATTRIBUTE) {
+        Some(Some(value)) => Some(value.trim().parse::<usize>().map_err(|_| {
+            ParseError::InvalidAttribute {
+                key: name.clone(),
+                name: MAX_LENGTH_ATTRIBUTE.to_string(),
+                value: value.clone(),
+            }
+        })?),
+        _ => None,
+    };
+    let translatable = match raw_localizations.get(TRANSLATABLE_ATTRIBUTE) {
+        Some(Some(value)) if value.trim() == "false" => false,
+        Some(Some(value)) if value.trim() != "true" => {
+            return Err(ParseError::InvalidAttribute {
+                key: name,
+                name: TRANSLATABLE_ATTRIBUTE.to_string(),
+                value: value.clone(),
+            })
+        }
+        _ => true,
+    };
+    let is_array = match raw_localizations.get(TYPE_ATTRIBUTE) {
+        Some(Some(value)) if value.trim() == STRING_ARRAY_TYPE => true,
+        Some(Some(value)) => {
+            return Err(ParseError::InvalidAttribute {
+                key: name,
+                name: TYPE_ATTRIBUTE.to_string(),
+                value: value.clone(),
+            })
+        }
+        _ => false,
+    };
+    check_placeholder_positions(&name, &raw_localizations)?;
+    let comment = raw_localizations.get(COMMENT_ATTRIBUTE).cloned().flatten();
+    let is_plural = raw_localizations.keys().any(|l| l.contains(':'));
+    let mut key = if is_array && is_plural {
+        return Err(ParseError::PluralArray {
+            key: name.strip_suffix(DEDUP_SUFFIX).unwrap_or(&name).to_string(),
+        });
+    } else if is_array {
+        key_from_locale_array_value_map(
+            name.strip_suffix(DEDUP_SUFFIX).unwrap_or(&name),
+            raw_localizations,
+        )
+    } else if is_plural {
         key_from_locale_plural_value_map(
             name.strip_suffix(DEDUP_SUFFIX).unwrap_or(&name),
             raw_localizations,
@@ -182,20 +1034,46 @@ fn key_from_locale_value_map(
             name.strip_suffix(DEDUP_SUFFIX).unwrap_or(&name),
             raw_localizations,
         )
+    }?;
+    key.max_length = max_length;
+    key.comment = comment;
+    key.translatable = translatable;
+    Ok(key)
+}
+
+/// Positions of placeholders like `%1$d` start from 1, `%0$d` fails at runtime
+fn check_placeholder_positions(
+    name: &str,
+    raw_localizations: &IndexMap<String, Option<String>>,
+) -> Result<(), ParseError> {
+    lazy_static! {
+        static ref ZERO_POSITION_RE: Regex = Regex::new(r"(^|[^%])(%%)*%0+\$").unwrap();
+    }
+    let malformed = raw_localizations
+        .iter()
+        .filter(|(locale, _)| !KEY_ATTRIBUTES.contains(&locale.as_str()))
+        .filter_map(|(_, value)| value.as_ref())
+        .find(|value| ZERO_POSITION_RE.is_match(value));
+    match malformed {
+        Some(raw) => Err(ParseError::MalformedPlaceholder {
+            key: name.strip_suffix(DEDUP_SUFFIX).unwrap_or(name).to_string(),
+            raw: raw.clone(),
+        }),
+        None => Ok(()),
     }
 }
 
 fn key_from_locale_single_value_map(
     name: &str,
     raw_localizations: IndexMap<String, Option<String>>,
-) -> Result<Key, String> {
+) -> Result<Key, ParseError> {
     let mut localizations: Vec<LocalizedString> = Vec::with_capacity(raw_localizations.len());
     for (locale_name, string_value_opt) in raw_localizations {
-        if locale_name == "comment" || locale_name == "tags" {
+        if KEY_ATTRIBUTES.contains(&locale_name.as_str()) {
             continue;
         }
+        // Reported by `parse`
         let Some(string_value) = string_value_opt else {
-            println!("skipped key \"{}\" because it's empty", locale_name);
             continue;
         };
         let loc_str = LocalizedString {
@@ -207,6 +1085,41 @@ fn key_from_locale_single_value_map(
     let key = Key {
         name: name.to_string(),
         localizations,
+        max_length: None,
+        comment: None,
+        translatable: true,
+    };
+    Ok(key)
+}
+
+fn key_from_locale_array_value_map(
+    name: &str,
+    raw_localizations: IndexMap<String, Option<String>>,
+) -> Result<Key, ParseError> {
+    let mut localizations: Vec<LocalizedString> = Vec::with_capacity(raw_localizations.len());
+    for (locale_name, string_value_opt) in raw_localizations {
+        if KEY_ATTRIBUTES.contains(&locale_name.as_str()) {
+            continue;
+        }
+        // Reported by `parse`
+        let Some(string_value) = string_value_opt else {
+            continue;
+        };
+        let items = string_value
+            .split(ARRAY_DELIMITER)
+            .map(|item| parse_localized_string_value(item.trim().to_string()))
+            .collect::<Result<Vec<String>, ParseError>>()?;
+        localizations.push(LocalizedString {
+            language_code: locale_name,
+            value: StringValue::Array(items),
+        });
+    }
+    let key = Key {
+        name: name.to_string(),
+        localizations,
+        max_length: None,
+        comment: None,
+        translatable: true,
     };
     Ok(key)
 }
@@ -214,17 +1127,34 @@ fn key_from_locale_single_value_map(
 fn key_from_locale_plural_value_map(
     name: &str,
     raw_localizations: IndexMap<String, Option<String>>,
-) -> Result<Key, String> {
+) -> Result<Key, ParseError> {
     let mut localizations: IndexMap<String, LocalizedString> =
         IndexMap::with_capacity(raw_localizations.len());
     for (locale_name_and_quantity, string_value_opt) in raw_localizations {
+        if KEY_ATTRIBUTES.contains(&locale_name_and_quantity.as_str()) {
+            continue;
+        }
+        // Reported by `parse`
         let Some(string_value) = string_value_opt else {
-            println!("skipped key \"{}\" for \"{}\" because it's empty", locale_name_and_quantity, name);
             continue;
         };
-        let (locale_name, quantity) = locale_name_and_quantity
-            .split_once(':')
-            .unwrap_or_else(|| (&locale_name_and_quantity, "other"));
+        let Some((locale_name, quantity)) = locale_name_and_quantity.split_once(':') else {
+            // Locale without quantity in a plural key, e.g. language with no plural forms
+            if localizations.contains_key(&locale_name_and_quantity) {
+                return Err(ParseError::MixedPluralAndSingle {
+                    key: name.to_string(),
+                    lang: locale_name_and_quantity,
+                });
+            }
+            localizations.insert(
+                locale_name_and_quantity.clone(),
+                LocalizedString {
+                    language_code: locale_name_and_quantity,
+                    value: StringValue::Single(parse_localized_string_value(string_value)?),
+                },
+            );
+            continue;
+        };
         let entry = localizations
             .entry(locale_name.to_string())
             .or_insert(LocalizedString {
@@ -233,23 +1163,28 @@ fn key_from_locale_plural_value_map(
                     quantities: Vec::new(),
                 },
             });
-        let loc_str_value = &mut entry.value;
-        let StringValue::Plural { quantities } = loc_str_value else {
-            continue;
+        let StringValue::Plural { quantities } = &mut entry.value else {
+            return Err(ParseError::MixedPluralAndSingle {
+                key: name.to_string(),
+                lang: locale_name.to_string(),
+            });
         };
-        quantities.push(PluralValue {
-            quantity: quantity.to_string(),
-            text: parse_localized_string_value(string_value)?,
-        });
+        quantities.push(PluralValue::new(
+            quantity,
+            parse_localized_string_value(string_value)?,
+        ));
     }
     let key = Key {
         name: name.to_string(),
         localizations: localizations.into_iter().map(|(_, value)| value).collect(),
+        max_length: None,
+        comment: None,
+        translatable: true,
     };
     Ok(key)
 }
 
-fn parse_localized_string_value(raw_value: String) -> Result<String, String> {
+pub(crate) fn parse_localized_string_value(raw_value: String) -> Result<String, ParseError> {
     lazy_static! {
         static ref PLACEHOLDER_REGEX_RE: Regex = Regex::new(PLACEHOLDER_REGEX).unwrap();
     }
@@ -264,22 +1199,19 @@ fn parse_localized_string_value(raw_value: String) -> Result<String, String> {
     Ok(value)
 }
 
-fn convert_twine_string_placeholder(raw_value: &str) -> Cow<str> {
+pub(crate) fn convert_twine_string_placeholder(raw_value: &str) -> Cow<'_, str> {
     lazy_static! {
-        static ref TWINE_STRING_REPLACE_REGEX: Regex = Regex::new(
-            format!(
-                r"%({})@",
-                PLACEHOLDER_PARAMETER_FLAGS_WIDTH_PRECISION_LENGTH
-            )
-            .as_str()
-        )
-        .unwrap();
+        static ref TWINE_STRING_REPLACE_REGEX: Regex =
+            Regex::new(TWINE_STRING_PLACEHOLDER_REGEX).unwrap();
     }
     // TODO @dz @Parse avoid allocating new string if there's no match
-    TWINE_STRING_REPLACE_REGEX.replace_all(&raw_value, r"%${1}s")
+    TWINE_STRING_REPLACE_REGEX.replace_all(raw_value, |caps: &Captures| match caps.get(1) {
+        Some(parameters) => format!("%{}s", parameters.as_str()),
+        None => "%%".to_string(),
+    })
 }
 
-fn maybe_add_positional_numbers(input: &str) -> Cow<str> {
+pub(crate) fn maybe_add_positional_numbers(input: &str) -> Cow<'_, str> {
     lazy_static! {
         static ref NON_NUMBERED_PLACEHOLDER_REGEX_RE: Regex =
             Regex::new(NON_NUMBERED_PLACEHOLDER_REGEX).unwrap();
@@ -327,8 +1259,11 @@ fn percent_start(m: &Match) -> usize {
 }
 
 fn maybe_escape_characters(input: &str) -> Cow<str> {
-    let needs_escaping =
-        input.contains("&") || input.contains("<") || input.contains("'") || input.contains("\"");
+    let needs_escaping = input.contains("&")
+        || input.contains("<")
+        || input.contains("'")
+        || input.contains("\"")
+        || input.contains('\\');
     if needs_escaping {
         if ANDROID_SUPPORTED_TAGS
             .iter()
@@ -345,11 +1280,52 @@ fn maybe_escape_characters(input: &str) -> Cow<str> {
 }
 
 fn escape_with_no_html_tags(input: &str) -> String {
-    return input
-        .replace("&", "&amp;")
+    escape_ampersands(&escape_lone_backslashes(input))
         .replace("<", "&lt;")
         .replace("'", "\\'")
-        .replace("\"", "\\\"");
+        .replace("\"", "\\\"")
+}
+
+/// Escapes `&` unless it starts one of the predefined XML entities like `&amp;` or a numeric
+/// reference like `&#38;` or `&#x26;`, so that pre-escaped values aren't escaped twice.
+/// Other named entities like `&nbsp;` are not defined in XML and get escaped.
+fn escape_ampersands(input: &str) -> Cow<'_, str> {
+    lazy_static! {
+        static ref AMPERSAND_OR_ENTITY_RE: Regex =
+            Regex::new(r"&(amp|lt|gt|quot|apos|#[0-9]+|#[xX][0-9a-fA-F]+);|&").unwrap();
+    }
+    AMPERSAND_OR_ENTITY_RE.replace_all(input, |caps: &Captures| match caps.get(1) {
+        Some(_) => caps[0].to_string(),
+        None => "&amp;".to_string(),
+    })
+}
+
+/// Characters which form escape sequences with preceding backslash in Android resources
+const ANDROID_ESCAPED_CHARACTERS: &[char] = &['n', 't', '\\', '\'', '"', '@', '?', 'u'];
+
+/// Escapes backslashes which are not a part of escape sequence recognized by Android
+/// resource parser, otherwise they would be dropped together with the next character
+fn escape_lone_backslashes(input: &str) -> Cow<'_, str> {
+    if !input.contains('\\') {
+        return Cow::Borrowed(input);
+    }
+    let mut result = String::with_capacity(input.len() + 1);
+    let mut chars = input.chars().peekable();
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            result.push(char);
+            continue;
+        }
+        match chars.peek() {
+            Some(next) if ANDROID_ESCAPED_CHARACTERS.contains(next) => {
+                result.push(char);
+                result.push(*next);
+                chars.next();
+            }
+            _ => result.push_str("\\\\"),
+        }
+    }
+    Cow::Owned(result)
 }
 
 fn escape_input_with_html_tags(input: &str) -> Cow<str> {
@@ -486,6 +1462,50 @@ fn replaces_percent_with_double_percent() {
     );
 }
 
+#[test]
+fn replaces_leading_percent_unless_placeholder() {
+    let result = maybe_replace_single_percent_with_double_percent("%d value");
+    assert_eq!(result, "%d value");
+
+    let result = maybe_replace_single_percent_with_double_percent("% value");
+    assert_eq!(result, "%% value");
+
+    let result = maybe_replace_single_percent_with_double_percent("%value");
+    assert_eq!(result, "%%value");
+
+    let result = maybe_replace_single_percent_with_double_percent("%% value");
+    assert_eq!(result, "%% value");
+}
+
+#[test]
+fn numbers_quad_integer_placeholders_like_long_long() {
+    let input = "%qd items, %qi, %qu, %qo and %qx".to_string();
+    let result = parse_localized_string_value(input).unwrap();
+    assert_eq!(result, "%1$qd items, %2$qi, %3$qu, %4$qo and %5$qx");
+
+    let input = "%lld items, %lli, %llu, %llo and %llx".to_string();
+    let result = parse_localized_string_value(input).unwrap();
+    assert_eq!(result, "%1$lld items, %2$lli, %3$llu, %4$llo and %5$llx");
+
+    let input = "%qd of %lld".to_string();
+    let result = parse_localized_string_value(input).unwrap();
+    assert_eq!(result, "%1$qd of %2$lld");
+
+    let input = "%qd left".to_string();
+    let result = parse_localized_string_value(input).unwrap();
+    assert_eq!(result, "%qd left");
+}
+
+#[test]
+fn escapes_backslashes_not_forming_escape_sequences() {
+    let input = r"C:\Users\Public\tmp and \n new line, \t tab, \\ slash".to_string();
+    let result = parse_localized_string_value(input).unwrap();
+    assert_eq!(
+        result,
+        r"C:\\Users\\Public\tmp and \n new line, \t tab, \\ slash"
+    );
+}
+
 #[test]
 fn parses_single_quotes_with_proper_escaping() {
     let input = "Я очень люблю одинарные ' кавычки '".to_string();
@@ -510,31 +1530,39 @@ fn replaces_percent_with_double_percent_wihout_placeholders() {
     );
 }
 
+#[test]
+fn keeps_escaped_entities_without_double_escaping() {
+    let input = "Terms &amp; conditions, &#169; 2024 &#x2122; &".to_string();
+    let result = parse_localized_string_value(input).unwrap();
+    assert_eq!(result, "Terms &amp; conditions, &#169; 2024 &#x2122; &amp;");
+
+    let input = "Fish &amp; chips & <b>peas &amp; beans</b> &chips".to_string();
+    let result = parse_localized_string_value(input).unwrap();
+    assert_eq!(result, "Fish &amp; chips &amp; <b>peas &amp; beans</b> &amp;chips");
+
+    let input = "Tom&Jerry; &nbsp;x".to_string();
+    let result = parse_localized_string_value(input).unwrap();
+    assert_eq!(result, "Tom&amp;Jerry; &amp;nbsp;x");
+}
+
+#[test]
+fn replaces_lone_percent_with_double_percent() {
+    let result = parse_localized_string_value("%".to_string()).unwrap();
+    assert_eq!(result, "%%");
+}
+
 #[test]
 fn parses_plural_form_keys() {
-    let mut input = IndexMap::new();
-    input.insert(
-        "en:one".to_string(),
-        Some("%d ruble %d bear and 1 vodka".to_string()),
-    );
-    input.insert(
-        "en:many".to_string(),
-        Some("%d rubles %d bears and 1 vodka".to_string()),
-    );
-    input.insert(
-        "ru:one".to_string(),
-        Some("%d рубль %d медведь и 1 водка".to_string()),
-    );
-    input.insert(
-        "ru:zero".to_string(),
-        Some("нет рублей нет медведей и 1 водка".to_string()),
-    );
-    input.insert(
-        "ru:other".to_string(),
-        Some("много рублей много медведей и 2 водки".to_string()),
-    );
-    let result = key_from_locale_value_map("receipt_example".to_string(), input).unwrap();
-    let loc = result.localizations;
+    let input = "[[Receipts]]
+  [receipt_example]
+    en:one = %d ruble %d bear and 1 vodka
+    en:many = %d rubles %d bears and 1 vodka
+    ru:one = %d рубль %d медведь и 1 водка
+    ru:zero = нет рублей нет медведей и 1 водка
+    ru:other = много рублей много медведей и 2 водки
+";
+    let mut result = parse_str(input).unwrap();
+    let loc = result.sections.remove(0).keys.remove(0).localizations;
 
     assert_eq!(loc.len(), 2);
     assert_eq!(loc[0].language_code, "en".to_string());
@@ -555,7 +1583,7 @@ fn parses_plural_form_keys() {
                 }
             )
         }
-        StringValue::Single(_) => panic!("expected plural value"),
+        _ => panic!("expected plural value"),
     }
     assert_eq!(loc[1].language_code, "ru".to_string());
     match &loc[1].value {
@@ -582,28 +1610,21 @@ fn parses_plural_form_keys() {
                 }
             )
         }
-        StringValue::Single(_) => panic!("expected plural value"),
+        _ => panic!("expected plural value"),
     }
 }
 
 #[test]
 fn parses_plural_keys_when_some_locales_miss_quantity() {
-    let mut input = IndexMap::new();
-    input.insert(
-        "en:one".to_string(),
-        Some("%d ruble %d bear and 1 vodka".to_string()),
-    );
-    input.insert(
-        "en:many".to_string(),
-        Some("%d rubles %d bears and 1 vodka".to_string()),
-    );
-    input.insert(
-        "ru".to_string(),
-        Some("%d рубль %d медведь и 1 водка".to_string()),
-    );
-    input.insert("uz".to_string(), Some("оглы углы %d маглы".to_string()));
-    let result = key_from_locale_value_map("receipt_example".to_string(), input).unwrap();
-    let loc = result.localizations;
+    let input = "[[Receipts]]
+  [receipt_example]
+    en:one = %d ruble %d bear and 1 vodka
+    en:many = %d rubles %d bears and 1 vodka
+    ru = %d рубль %d медведь и 1 водка
+    uz = оглы углы %d маглы
+";
+    let mut result = parse_str(input).unwrap();
+    let loc = result.sections.remove(0).keys.remove(0).localizations;
 
     assert_eq!(loc.len(), 3);
     assert_eq!(loc[0].language_code, "en".to_string());
@@ -624,33 +1645,470 @@ fn parses_plural_keys_when_some_locales_miss_quantity() {
                 }
             )
         }
-        StringValue::Single(_) => panic!("expected plural value"),
+        _ => panic!("expected plural value"),
     }
     assert_eq!(loc[1].language_code, "ru".to_string());
-    match &loc[1].value {
-        StringValue::Plural { quantities } => {
-            assert_eq!(
-                quantities[0],
-                PluralValue {
-                    quantity: "other".to_string(),
-                    text: "%1$d рубль %2$d медведь и 1 водка".to_string()
-                }
-            );
-        }
-        StringValue::Single(_) => panic!("expected plural value"),
-    }
+    assert_eq!(
+        loc[1].value,
+        StringValue::Single("%1$d рубль %2$d медведь и 1 водка".to_string())
+    );
     assert_eq!(loc[2].language_code, "uz".to_string());
-    match &loc[2].value {
-        StringValue::Plural { quantities } => {
-            assert_eq!(
-                quantities[0],
-                PluralValue {
-                    quantity: "other".to_string(),
-                    text: "оглы углы %d маглы".to_string()
-                }
-            );
+    assert_eq!(
+        loc[2].value,
+        StringValue::Single("оглы углы %d маглы".to_string())
+    );
+}
+
+#[test]
+fn skips_key_attributes() {
+    let input = "[[Buttons]]
+  [login]
+    en = Login
+    comment = Button title
+    auto_translated = true
+";
+    let mut parsed = parse_str(input).unwrap();
+    let result = parsed.sections.remove(0).keys.remove(0);
+
+    assert_eq!(result.localizations.len(), 1);
+    assert_eq!(result.localizations[0].language_code, "en".to_string());
+}
+
+#[test]
+fn parses_max_length_attribute() {
+    let input = "[[Buttons]]
+  [login]
+    en = Login
+    max_length = 12
+";
+    let mut parsed = parse_str(input).unwrap();
+    let result = parsed.sections.remove(0).keys.remove(0);
+
+    assert_eq!(result.max_length, Some(12));
+    assert_eq!(result.localizations.len(), 1);
+
+    assert!(matches!(
+        parse_str(&input.replace("12", "twelve")),
+        Err(ParseError::InvalidAttribute { key, value, .. }) if key == "login" && value == "twelve"
+    ));
+}
+
+#[test]
+fn parses_translatable_attribute() {
+    let input = "[[Links]]
+  [privacy_url]
+    en = https://example.com/privacy
+    __translatable = false
+  [login]
+    en = Login
+";
+    let mut parsed = parse_str(input).unwrap();
+    let keys = &mut parsed.sections[0].keys;
+    keys.sort_by(|a, b| a.name.cmp(&b.name));
+
+    assert!(keys[0].translatable);
+    assert!(!keys[1].translatable);
+    assert_eq!(keys[1].localizations.len(), 1);
+
+    assert!(matches!(
+        parse_str(&input.replace("= false", "= no")),
+        Err(ParseError::InvalidAttribute { key, value, .. }) if key == "privacy_url" && value == "no"
+    ));
+}
+
+#[test]
+fn finds_missing_translations() {
+    let input = "[[Login]]
+  [title]
+    en = Login
+    de = Anmelden
+    fr = Connexion
+  [logout]
+    fr = Déconnexion
+  [help_url]
+    en = https://example.com/help
+    __translatable = false
+";
+    let mut parsed = parse_str(input).unwrap();
+    parsed.sections[0].keys.sort_by(|a, b| b.name.cmp(&a.name));
+
+    assert_eq!(parsed.languages(), vec!["en", "de", "fr"]);
+    let missing = |lang: &str| MissingTranslation {
+        key_name: "logout".to_string(),
+        missing_lang: lang.to_string(),
+    };
+    assert_eq!(
+        parsed.missing_translations(),
+        vec![missing("en"), missing("de")]
+    );
+}
+
+#[test]
+fn warns_about_invalid_language_codes_unless_strict() {
+    let input = "[[Login]]
+  [title]
+    eng = Login
+    de = Anmelden
+  [logout]
+    eng = Logout
+";
+    let (_, _, warnings) = parse_content(input, &ParseOptions::default()).unwrap();
+    assert!(matches!(
+        &warnings[..],
+        [ParseWarning::InvalidLanguageCode { code, error: LangError::LongLanguage { .. }, .. }] if code == "eng"
+    ));
+
+    let options = ParseOptions {
+        strict_lang: true,
+        ..Default::default()
+    };
+    assert!(matches!(
+        parse_content(input, &options),
+        Err(ParseError::InvalidLanguageCode { code, .. }) if code == "eng"
+    ));
+}
+
+#[test]
+fn retains_keys_matching_glob_patterns() {
+    let input = "[[Login]]
+  [login_title]
+    en = Login
+  [login_button_ok]
+    en = OK
+  [settings_title]
+    en = Settings
+  [logout]
+    en = Logout
+";
+    let names = |file: &File| {
+        let mut names: Vec<String> = file.sections[0]
+            .keys
+            .iter()
+            .map(|key| key.name.clone())
+            .collect();
+        names.sort();
+        names
+    };
+
+    let mut parsed = parse_str(input).unwrap();
+    assert_eq!(parsed.retain_keys_matching("login_*"), 2);
+    assert_eq!(names(&parsed), vec!["login_button_ok", "login_title"]);
+
+    let mut parsed = parse_str(input).unwrap();
+    assert_eq!(parsed.retain_keys_matching_any(&["*_title", "logout"]), 1);
+    assert_eq!(
+        names(&parsed),
+        vec!["login_title", "logout", "settings_title"]
+    );
+
+    assert!(glob_match("login_*_ok", "login_button_ok"));
+    assert!(!glob_match("login_*_ok", "login_ok"));
+    assert!(glob_match("*", ""));
+}
+
+#[test]
+fn fails_on_plural_and_single_values_of_the_same_language() {
+    for input in [
+        "[[Days]]\n  [days]\n    ru:one = %d день\n    ru = дни\n",
+        "[[Days]]\n  [days]\n    ru = дни\n    ru:one = %d день\n",
+    ] {
+        assert!(
+            matches!(
+                parse_str(input),
+                Err(ParseError::MixedPluralAndSingle { key, lang }) if key == "days" && lang == "ru"
+            ),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn merges_files() {
+    let first = || parse_str("[[Login]]\n  [title]\n    en = Login\n").unwrap();
+    let second =
+        || parse_str("[[Login]]\n  [title]\n    en = Sign in\n  [days]\n    en = Days\n").unwrap();
+    let plural = || parse_str("[[Days]]\n  [days]\n    en:one = %d day\n").unwrap();
+
+    assert_eq!(
+        first().merge(second()).unwrap_err(),
+        MergeError::DuplicateKey {
+            name: "title".to_string()
         }
-        StringValue::Single(_) => panic!("expected plural value"),
+    );
+
+    let merged = first().merge_overwriting(second());
+    assert_eq!(merged.sections.len(), 2);
+    assert_eq!(merged.sections[0].keys[0].name, "title");
+    assert_eq!(
+        merged.sections[0].keys[0].localizations[0].value,
+        StringValue::Single("Sign in".to_string())
+    );
+    assert_eq!(merged.sections[1].keys[0].name, "days");
+    assert_eq!(merged.sections[1].keys.len(), 1);
+
+    let merged = first().merge(plural()).unwrap();
+    let names: Vec<&str> = merged
+        .sections
+        .iter()
+        .flat_map(|section| &section.keys)
+        .map(|key| key.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["title", "days"]);
+}
+
+#[test]
+fn fails_on_duplicate_keys_unless_allowed() {
+    let input = "[[Login]]
+  [title]
+    en = Login
+  [days]
+    en = Days
+[[Settings]]
+  [days]
+    en:one = %d day
+    en:other = %d days
+  [title]
+    en = Settings
+";
+    assert!(matches!(
+        parse_str(input),
+        Err(ParseError::DuplicateKey { name, first_section: 1, second_section: 2 }) if name == "title"
+    ));
+
+    let options = ParseOptions {
+        allow_duplicate_keys: true,
+        ..Default::default()
+    };
+    let (parsed, _, warnings) = parse_content(input, &options).unwrap();
+    let keys = &parsed.sections[0].keys;
+    assert_eq!(
+        warnings,
+        vec![ParseWarning::DuplicateKey {
+            name: "title".to_string(),
+            first_section: 1,
+            second_section: 2
+        }]
+    );
+    assert_eq!(keys.len(), 3);
+    let title = keys.iter().find(|key| key.name == "title").unwrap();
+    assert_eq!(
+        title.localizations[0].value,
+        StringValue::Single("Settings".to_string())
+    );
+}
+
+#[test]
+fn parses_string_array_type() {
+    let input = "[[Calendar]]
+  [week_days]
+    en = Mon | Tue | %s day
+    __type = string-array
+";
+    let mut parsed = parse_str(input).unwrap();
+    let result = parsed.sections.remove(0).keys.remove(0);
+
+    assert_eq!(
+        result.localizations[0].value,
+        StringValue::Array(vec![
+            "Mon".to_string(),
+            "Tue".to_string(),
+            "%s day".to_string()
+        ])
+    );
+
+    let with_plurals = input.replace("en =", "en:other =");
+    assert!(matches!(
+        parse_str(&with_plurals),
+        Err(ParseError::PluralArray { key }) if key == "week_days"
+    ));
+    assert!(matches!(
+        parse_str(&input.replace("string-array", "list")),
+        Err(ParseError::InvalidAttribute { value, .. }) if value == "list"
+    ));
+}
+
+#[test]
+fn parses_multi_line_comments_of_keys() -> Result<(), ParseError> {
+    let content = "[[Section]]
+  ; Title of the login screen,
+  ; keep it short
+  [login]
+    en = Login
+    comment = Shown in the toolbar
+
+  # Unrelated comment
+
+  [logout]
+    en = Logout; see you
+
+  [primary_color]
+    en = #FF0000
+";
+    let parsed = parse_str(content)?;
+    let keys = &parsed.sections[0].keys;
+    assert_eq!(keys.len(), 3);
+    assert_eq!(
+        keys[0].comment,
+        Some("Title of the login screen,\nkeep it short\nShown in the toolbar".to_string())
+    );
+    assert_eq!(keys[1].comment, None);
+    assert_eq!(keys[1].localizations.len(), 1);
+    assert_eq!(
+        keys[1].localizations[0].value,
+        StringValue::Single("Logout; see you".to_string())
+    );
+    assert_eq!(
+        keys[2].localizations[0].value,
+        StringValue::Single("#FF0000".to_string())
+    );
+    Ok(())
+}
+
+#[test]
+fn returns_skipped_empty_values_with_lines() -> Result<(), ParseError> {
+    let content = "[[Section]]
+  [login]
+    en = Login
+    ru
+  [days]
+    en:one = %d day
+    en:other
+    ru:one = %d день
+";
+    let (parsed, skipped) = parse_str_with_skipped(content)?;
+    assert_eq!(parsed.sections[0].keys[0].localizations.len(), 1);
+    assert_eq!(
+        skipped,
+        vec![
+            SkippedKey {
+                key: "login".to_string(),
+                locale: "ru".to_string(),
+                reason: SkipReason::EmptyValue,
+                source_line: Some(4),
+            },
+            SkippedKey {
+                key: "days".to_string(),
+                locale: "en:other".to_string(),
+                reason: SkipReason::EmptyValue,
+                source_line: Some(7),
+            },
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn converts_twine_string_placeholders_adjacent_to_other_specifiers() {
+    assert_eq!(convert_twine_string_placeholder("%%@"), "%%@");
+    assert_eq!(convert_twine_string_placeholder("100%%%@"), "100%%%s");
+    assert_eq!(convert_twine_string_placeholder("%1$@%2$@"), "%1$s%2$s");
+    assert_eq!(convert_twine_string_placeholder("%@%@"), "%s%s");
+    assert_eq!(convert_twine_string_placeholder("%-10@"), "%-10s");
+}
+
+#[test]
+fn converts_plural_quantity_to_cldr_category() {
+    let quantity = |q: &str| PluralValue {
+        quantity: q.to_string(),
+        text: "".to_string(),
+    };
+    assert_eq!(quantity("zero").quantity_as_cldr_category(), Some(CldrCategory::Zero));
+    assert_eq!(quantity("few").quantity_as_cldr_category(), Some(CldrCategory::Few));
+    assert_eq!(quantity("other").quantity_as_cldr_category(), Some(CldrCategory::Other));
+    assert_eq!(quantity("Other").quantity_as_cldr_category(), None);
+    assert_eq!(quantity("several").quantity_as_cldr_category(), None);
+}
+
+#[test]
+fn returns_cldr_categories_of_language() {
+    use CldrCategory::*;
+    assert_eq!(CldrCategory::for_language("en"), Some(&[One, Other][..]));
+    assert_eq!(CldrCategory::for_language("pt-BR"), Some(&[One, Many, Other][..]));
+    assert_eq!(CldrCategory::for_language("zh_Hans"), Some(&[Other][..]));
+    assert_eq!(CldrCategory::for_language("tlh"), None);
+}
+
+#[test]
+fn streams_the_same_keys_as_batch_parsing() -> Result<(), ParseError> {
+    let content = "[[Section]]
+  # Title of login screen
+  [login]
+    en = Login
+    ru
+    comment = Button
+  [days]
+    en:one = %d day
+    en:other = %d days
+    ru:one = %d день
+
+[[Other]]
+  [percent]
+    en = %d%% done
+";
+    let (batch, batch_skipped) = parse_str_with_skipped(content)?;
+
+    let mut parser = TwineParser::new(content.as_bytes());
+    let streamed: Vec<Key> = parser.by_ref().collect::<Result<_, _>>()?;
+
+    assert_eq!(
+        format!("{:?}", streamed),
+        format!("{:?}", batch.sections[0].keys)
+    );
+    assert_eq!(parser.skipped(), batch_skipped.as_slice());
+
+    let truncated = "[[Src]]\n  [login]\n    en = Login\n  [logout]\n";
+    // Keys before the truncation are yielded before the error is found
+    let results: Vec<_> = TwineParser::new(truncated.as_bytes()).collect();
+    assert!(matches!(
+        results[..],
+        [Ok(_), Err(ParseError::TruncatedFile(_))]
+    ));
+    Ok(())
+}
+
+#[test]
+fn validates_file_integrity() {
+    let complete = "[[Src]]\n  [login]\n    en = Login\n    ru = Войти\n\n";
+    assert!(validate_file_integrity(complete).is_ok());
+    assert!(validate_file_integrity("").is_ok());
+    assert!(validate_file_integrity("[[Src]]\n").is_ok());
+
+    assert!(validate_file_integrity("[[Src]]\n  [login]\n    en = Login\n  [logo").is_err());
+    assert!(validate_file_integrity("[[Src]]\n  [login]\n    en = Login\n  [logout]\n").is_err());
+    assert!(validate_file_integrity("[[Src]]\n  [login]\n    en = Login\n    ru =").is_err());
+    assert!(validate_file_integrity("[[Src]]\n  [login]\n    en = Login\n    r").is_err());
+}
+
+#[test]
+fn returns_typed_parse_errors() {
+    let truncated = parse_str("[[Src]]\n  [login]\n    en = Login\n  [logo");
+    assert!(matches!(truncated, Err(ParseError::TruncatedFile(_))));
+
+    let malformed = parse_str("[[Src]]\n  [count]\n    en = %1$d of %0$d\n");
+    assert!(matches!(
+        malformed,
+        Err(ParseError::MalformedPlaceholder { key, raw }) if key == "count" && raw == "%1$d of %0$d"
+    ));
+    assert!(parse_str("[[Src]]\n  [discount]\n    en = 100%%0$ off\n").is_ok());
+
+    let empty_section = parse_str("[[Auth]]\n\n[[Src]]\n  [login]\n    en = Login\n");
+    assert!(matches!(empty_section, Err(ParseError::EmptySection)));
+    let empty_last_section = parse_str("[[Src]]\n  [login]\n    en = Login\n[[Empty]]\n");
+    assert!(matches!(empty_last_section, Err(ParseError::EmptySection)));
+
+    let missing = parse("no/such/file.twine");
+    assert!(matches!(missing, Err(ParseError::Io(_))));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn generates_file_json_schema() {
+    let schema: serde_json::Value = serde_json::from_str(&file_json_schema()).unwrap();
+
+    assert_eq!(schema["title"], "File");
+    for definition in ["Section", "Key", "LocalizedString", "StringValue", "PluralValue"] {
+        assert!(schema["definitions"].get(definition).is_some(), "{}", definition);
     }
 }
 
@@ -668,3 +2126,51 @@ fn parses_plural_keys_when_some_locales_miss_quantity() {
 // The correct way would be to ditch configparser-rs (which is made for INI files),
 // and instead parse by ourselves: walk txt file line-by-line and as each key
 // is read, produce StringValue from it (stream-like parsing)
+
+#[test]
+fn counts_distinct_placeholders() {
+    let single = |text: &str| LocalizedString {
+        language_code: "en".to_string(),
+        value: StringValue::Single(text.to_string()),
+    };
+    assert_eq!(single("%1$s sent %2$d, %1$s").placeholder_count(), 2);
+    assert_eq!(single("100%%d").placeholder_count(), 0);
+    assert!(!single("100%% off").contains_placeholder());
+    assert!(single("Hi %@").contains_placeholder());
+
+    let plural = LocalizedString {
+        language_code: "en".to_string(),
+        value: StringValue::plural()
+            .quantity("one", "One day")
+            .quantity("other", "%d days of %d")
+            .build(),
+    };
+    assert_eq!(plural.placeholder_count(), 1);
+}
+
+#[test]
+fn builds_plural_values() {
+    let one = PluralValue {
+        quantity: "one".to_string(),
+        text: "%d apple".to_string(),
+    };
+    assert_eq!(PluralValue::new("one", "%d apple"), one);
+    assert_eq!(PluralValueBuilder::new("one", "%d apple").build(), one);
+    assert_eq!(
+        PluralValueBuilder::new("other", "%d apple")
+            .quantity("one")
+            .build(),
+        one
+    );
+
+    let value = StringValue::plural()
+        .value(one.clone())
+        .quantity("other", "%d apples")
+        .build();
+    assert_eq!(
+        value,
+        StringValue::Plural {
+            quantities: vec![one, PluralValue::new("other", "%d apples")]
+        }
+    );
+}
@@ -0,0 +1,163 @@
+use std::{fmt, io};
+
+use crate::lang::LangError;
+
+/// Errors which can happen while parsing twine files
+#[derive(Debug)]
+pub enum ParseError {
+    Io(io::Error),
+    /// File content can't be decoded, e.g. unknown encoding label or invalid bytes
+    InvalidEncoding(String),
+    /// Key is defined more than once
+    DuplicateKey {
+        name: String,
+        first_section: usize,
+        second_section: usize,
+    },
+    /// Section has no keys
+    EmptySection,
+    /// Placeholder which can't be formatted, e.g. `%0$d` since positions start from 1
+    MalformedPlaceholder { key: String, raw: String },
+    /// File ends in the middle of a key, e.g. because of an interrupted write
+    TruncatedFile(String),
+    /// Key with `__type = string-array` attribute has plural quantities
+    PluralArray { key: String },
+    /// Language of a plural key has both `lang:quantity` entries and a `lang` entry
+    MixedPluralAndSingle { key: String, lang: String },
+    /// Key attribute like `max_length` has a value of a wrong type
+    InvalidAttribute {
+        key: String,
+        name: String,
+        value: String,
+    },
+    /// Language code is not a BCP 47 tag, reported with `ParseOptions::strict_lang`
+    InvalidLanguageCode {
+        key: String,
+        code: String,
+        error: LangError,
+    },
+    /// Content is not a valid INI file
+    Syntax(String),
+}
+
+/// Problems of twine files which are reported without failing the parsing
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum ParseWarning {
+    /// Key is defined more than once, the last definition is kept
+    DuplicateKey {
+        name: String,
+        first_section: usize,
+        second_section: usize,
+    },
+    /// Language code is not a BCP 47 tag, reported once for the first key with the code
+    InvalidLanguageCode {
+        key: String,
+        code: String,
+        error: LangError,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io(err) => write!(f, "I/O error: {}", err),
+            ParseError::InvalidEncoding(message) => write!(f, "Invalid encoding: {}", message),
+            ParseError::DuplicateKey {
+                name,
+                first_section,
+                second_section,
+            } => write!(
+                f,
+                "Duplicate key \"{}\" in sections {} and {}",
+                name, first_section, second_section
+            ),
+            ParseError::EmptySection => write!(f, "Section has no keys"),
+            ParseError::MalformedPlaceholder { key, raw } => {
+                write!(f, "Malformed placeholder in \"{}\" of key \"{}\"", raw, key)
+            }
+            ParseError::TruncatedFile(message) => write!(f, "Truncated file: {}", message),
+            ParseError::PluralArray { key } => {
+                write!(f, "Key \"{}\" has both string-array type and plurals", key)
+            }
+            ParseError::MixedPluralAndSingle { key, lang } => write!(
+                f,
+                "Key \"{}\" has both plural quantities and a single value of \"{}\"",
+                key, lang
+            ),
+            ParseError::InvalidAttribute { key, name, value } => {
+                write!(f, "Invalid {} \"{}\" of key \"{}\"", name, value, key)
+            }
+            ParseError::InvalidLanguageCode { key, code, error } => {
+                write!(
+                    f,
+                    "Invalid language code \"{}\" of key \"{}\": {}",
+                    code, key, error
+                )
+            }
+            ParseError::Syntax(message) => write!(f, "Syntax error: {}", message),
+        }
+    }
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseWarning::DuplicateKey {
+                name,
+                first_section,
+                second_section,
+            } => write!(
+                f,
+                "Duplicate key \"{}\" in sections {} and {}, keeping the last definition",
+                name, first_section, second_section
+            ),
+            ParseWarning::InvalidLanguageCode { key, code, error } => {
+                write!(
+                    f,
+                    "Invalid language code \"{}\" of key \"{}\": {}",
+                    code, key, error
+                )
+            }
+        }
+    }
+}
+
+/// Errors of merging keys of several source files into one
+#[derive(PartialEq, Eq, Debug)]
+pub enum MergeError {
+    /// Both files have a key with the name and the same kind of values
+    DuplicateKey { name: String },
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeError::DuplicateKey { name } => {
+                write!(f, "Key \"{}\" is defined in several source files", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            // `read_to_string_with_encoding` reports undecodable content and unknown labels so
+            io::ErrorKind::InvalidData | io::ErrorKind::InvalidInput => {
+                ParseError::InvalidEncoding(err.to_string())
+            }
+            _ => ParseError::Io(err),
+        }
+    }
+}
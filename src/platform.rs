@@ -0,0 +1,355 @@
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+use crate::parse::{
+    array_text, convert_twine_string_placeholder, parse_localized_string_value, Key,
+    LocalizedString, PluralValue, StringValue, PLACEHOLDER_OR_PERCENT_RE,
+};
+
+/// Target platform of localized strings, parsed values are stored in Android format
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Platform {
+    Android,
+    Ios,
+    /// ARB files with ICU message placeholders, e.g. `{arg1}`
+    Flutter,
+    /// i18next JSON with `{{arg1}}` interpolation
+    I18n,
+}
+
+impl StringValue {
+    /// Converts placeholders and escaping of the value for the platform.
+    /// Plurals are formatted as ICU plural message, e.g. `{count, plural, one {…} other {…}}`
+    pub fn to_platform_format(&self, platform: Platform) -> String {
+        match self {
+            StringValue::Single(text) => text_to_platform_format(text, platform),
+            StringValue::Array(items) => text_to_platform_format(&array_text(items), platform),
+            StringValue::Plural { quantities } => {
+                let mut items: Vec<&PluralValue> = quantities.iter().collect();
+                items.sort_by_key(|item| item.quantity_as_cldr_category());
+                let items: Vec<String> = items
+                    .iter()
+                    .map(|item| {
+                        format!(
+                            "{} {{{}}}",
+                            item.quantity,
+                            text_to_platform_format(
+                                &plural_form_text(&item.text, platform),
+                                platform
+                            )
+                        )
+                    })
+                    .collect();
+                format!("{{count, plural, {}}}", items.join(" "))
+            }
+        }
+    }
+}
+
+/// Text of plural form with the count replaced by the plural argument of ICU message,
+/// e.g. `{count}` for Flutter which would otherwise take the count twice
+pub(crate) fn plural_form_text(text: &str, platform: Platform) -> String {
+    match platform {
+        Platform::Flutter => replace_count_placeholder(text, "{count}"),
+        _ => text.to_string(),
+    }
+}
+
+impl LocalizedString {
+    /// Converts placeholders and escaping of the value from one platform format to another,
+    /// e.g. `%@` of iOS strings becomes `%s` for Android
+    pub fn translate_placeholders(&mut self, from: Platform, to: Platform) {
+        let convert =
+            |text: &str| text_to_platform_format(&text_from_platform_format(text, from), to);
+        match &mut self.value {
+            StringValue::Single(text) => *text = convert(text),
+            StringValue::Plural { quantities } => {
+                for item in quantities {
+                    item.text = convert(&item.text);
+                }
+            }
+            StringValue::Array(items) => {
+                for item in items {
+                    *item = convert(item);
+                }
+            }
+        }
+    }
+}
+
+impl Key {
+    /// Returns a copy of the localization with placeholders converted for the platform,
+    /// `None` if the key has no such language
+    pub fn localization_for_platform(
+        &self,
+        lang: &str,
+        platform: Platform,
+    ) -> Option<LocalizedString> {
+        let mut localization = self
+            .localizations
+            .iter()
+            .find(|localization| localization.language_code == lang)?
+            .clone();
+        localization.translate_placeholders(Platform::Android, platform);
+        Some(localization)
+    }
+}
+
+/// Converts text of the platform to Android format used for parsed values
+fn text_from_platform_format(text: &str, platform: Platform) -> String {
+    lazy_static! {
+        static ref NAMED_PLACEHOLDER_RE: Regex = Regex::new(r"\{\{?arg(\d+)\}\}?").unwrap();
+    }
+    let text = match platform {
+        Platform::Android => return text.to_string(),
+        Platform::Ios => convert_twine_string_placeholder(text).to_string(),
+        Platform::Flutter | Platform::I18n => NAMED_PLACEHOLDER_RE
+            .replace_all(text, "%${1}$$s")
+            .to_string(),
+    };
+    // Escapes characters and numbers placeholders the same way as twine values
+    parse_localized_string_value(text.clone()).unwrap_or(text)
+}
+
+/// Converts text in Android format for the platform
+pub(crate) fn text_to_platform_format(text: &str, platform: Platform) -> String {
+    match platform {
+        Platform::Android => text.to_string(),
+        Platform::Ios => to_ios_text(text),
+        Platform::Flutter => {
+            to_named_placeholders(&decode_android_escapes(&unescape_android(text)), "{", "}")
+        }
+        Platform::I18n => to_named_placeholders(&unescape_android(text), "{{", "}}"),
+    }
+}
+
+/// Captures of printf placeholders of the text, escaped `%%` is skipped. Group 1 is
+/// the position, e.g. `1$`, and group 7 is the conversion.
+pub(crate) fn placeholders(text: &str) -> impl Iterator<Item = Captures<'_>> {
+    PLACEHOLDER_OR_PERCENT_RE
+        .captures_iter(text)
+        .filter(|caps| caps.get(7).is_some())
+}
+
+/// Replaces printf placeholders of the text with results of `replace`, escaped `%%` is kept
+pub(crate) fn replace_placeholders(
+    text: &str,
+    mut replace: impl FnMut(&Captures) -> String,
+) -> String {
+    PLACEHOLDER_OR_PERCENT_RE
+        .replace_all(text, |caps: &Captures| {
+            if caps.get(7).is_some() {
+                replace(caps)
+            } else {
+                caps[0].to_string()
+            }
+        })
+        .into_owned()
+}
+
+/// Reverts Android specific escaping done while parsing and brings back `%@` placeholders
+pub(crate) fn to_ios_text(text: &str) -> String {
+    let text = unescape_android(text);
+    replace_placeholders(&text, |caps| {
+        let placeholder = &caps[0];
+        match placeholder.strip_suffix('s') {
+            Some(prefix) => prefix.to_string() + "@",
+            None => placeholder.to_string(),
+        }
+    })
+}
+
+pub(crate) fn unescape_android(text: &str) -> String {
+    text.replace("\\'", "'")
+        .replace("\\\"", "\"")
+        .replace("&lt;", "<")
+        .replace("&amp;", "&")
+}
+
+/// Turns escape sequences which are left in parsed values for Android into characters
+pub(crate) fn decode_android_escapes(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            result.push(char);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some(escaped @ ('\\' | '@' | '?')) => result.push(escaped),
+            Some(other) => {
+                result.push(char);
+                result.push(other);
+            }
+            None => result.push(char),
+        }
+    }
+    result
+}
+
+/// Replaces printf placeholders with `{argN}`, N is the positional number if present
+/// or the order of the placeholder otherwise. `%%` is turned into `%`.
+pub(crate) fn to_named_placeholders(text: &str, open: &str, close: &str) -> String {
+    to_named_placeholders_with_prefix(text, open, "arg", close)
+}
+
+/// Same as `to_named_placeholders` with `prefix` instead of `arg`, e.g. `{{param1}}`
+pub(crate) fn to_named_placeholders_with_prefix(
+    text: &str,
+    open: &str,
+    prefix: &str,
+    close: &str,
+) -> String {
+    let mut index = 0;
+    let replaced = replace_placeholders(text, |caps| {
+        index += 1;
+        let number = caps
+            .get(1)
+            .and_then(|position| position.as_str().trim_end_matches('$').parse().ok())
+            .unwrap_or(index);
+        format!("{}{}{}{}", open, prefix, number, close)
+    });
+    replaced.replace("%%", "%")
+}
+
+/// Replaces the count of plural form with `count`, that is the placeholder with position 1
+/// or the first one if placeholders have no positions, e.g. `%{count}` of Rails
+pub(crate) fn replace_count_placeholder(text: &str, count: &str) -> String {
+    let mut index = 0;
+    replace_placeholders(text, |caps| {
+        index += 1;
+        match caps.get(1).map(|position| position.as_str()) {
+            Some("1$") => count.to_string(),
+            None if index == 1 => count.to_string(),
+            _ => caps[0].to_string(),
+        }
+    })
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn converts_single_value_to_platform_format() {
+    let value =
+        StringValue::Single("Hello, %1$s! You\\'ve got %2$d &lt;b>coins&lt;/b>".to_string());
+
+    assert_eq!(
+        value.to_platform_format(Platform::Android),
+        "Hello, %1$s! You\\'ve got %2$d &lt;b>coins&lt;/b>"
+    );
+    assert_eq!(
+        value.to_platform_format(Platform::Ios),
+        "Hello, %1$@! You've got %2$d <b>coins</b>"
+    );
+    assert_eq!(
+        value.to_platform_format(Platform::Flutter),
+        "Hello, {arg1}! You've got {arg2} <b>coins</b>"
+    );
+    assert_eq!(
+        value.to_platform_format(Platform::I18n),
+        "Hello, {{arg1}}! You've got {{arg2}} <b>coins</b>"
+    );
+}
+
+#[test]
+fn keeps_escaped_percent_before_conversion_characters() {
+    let value = StringValue::Single("100%%d of %s, %%s".to_string());
+
+    assert_eq!(value.to_platform_format(Platform::Ios), "100%%d of %@, %%s");
+    assert_eq!(
+        value.to_platform_format(Platform::Flutter),
+        "100%d of {arg1}, %s"
+    );
+    assert_eq!(
+        replace_count_placeholder("100%%d, %d", "{count}"),
+        "100%%d, {count}"
+    );
+}
+
+#[test]
+fn converts_plural_value_to_icu_message() {
+    let value = StringValue::Plural {
+        quantities: vec![
+            PluralValue {
+                quantity: "other".to_string(),
+                text: "%d days".to_string(),
+            },
+            PluralValue {
+                quantity: "one".to_string(),
+                text: "%d day".to_string(),
+            },
+        ],
+    };
+
+    assert_eq!(
+        value.to_platform_format(Platform::Flutter),
+        "{count, plural, one {{count} day} other {{count} days}}"
+    );
+}
+
+#[test]
+fn translates_placeholders_between_ios_and_android() {
+    let mut localization = LocalizedString {
+        language_code: "en".to_string(),
+        value: StringValue::Single("%@ has %d <b>coins</b>".to_string()),
+    };
+
+    localization.translate_placeholders(Platform::Ios, Platform::Android);
+    assert_eq!(
+        localization.value,
+        StringValue::Single("%1$s has %2$d <b>coins</b>".to_string())
+    );
+
+    localization.translate_placeholders(Platform::Android, Platform::Ios);
+    assert_eq!(
+        localization.value,
+        StringValue::Single("%1$@ has %2$d <b>coins</b>".to_string())
+    );
+}
+
+#[test]
+fn translates_placeholders_from_flutter_plurals() {
+    let mut localization = LocalizedString {
+        language_code: "en".to_string(),
+        value: StringValue::Plural {
+            quantities: vec![PluralValue {
+                quantity: "other".to_string(),
+                text: "{arg1} won {arg2} days".to_string(),
+            }],
+        },
+    };
+
+    localization.translate_placeholders(Platform::Flutter, Platform::Android);
+    assert_eq!(
+        localization.value,
+        StringValue::Plural {
+            quantities: vec![PluralValue {
+                quantity: "other".to_string(),
+                text: "%1$s won %2$s days".to_string(),
+            }],
+        }
+    );
+}
+
+#[test]
+fn returns_localization_for_platform() {
+    let key = Key {
+        name: "greeting".to_string(),
+        localizations: vec![LocalizedString {
+            language_code: "en".to_string(),
+            value: StringValue::Single("Hello, %1$s".to_string()),
+        }],
+        max_length: None,
+        comment: None,
+        translatable: true,
+    };
+
+    let actual = key.localization_for_platform("en", Platform::Ios).unwrap();
+    assert_eq!(actual.value, StringValue::Single("Hello, %1$@".to_string()));
+    assert_eq!(
+        key.localizations[0].value,
+        StringValue::Single("Hello, %1$s".to_string())
+    );
+    assert!(key.localization_for_platform("de", Platform::Ios).is_none());
+}
@@ -0,0 +1,626 @@
+use indexmap::IndexMap;
+use std::path::Path;
+
+use crate::gen_error::GenError;
+use crate::parse::{array_text, File, PluralValue, StringValue, COMMENT_ATTRIBUTE};
+use crate::platform::unescape_android;
+use crate::sink::WriteSink;
+use crate::twine_text::{raw_sections, set_localizations};
+
+/// Source values of a single twine key
+struct SourceKey {
+    name: String,
+    line_number: usize,
+    comment: Option<String>,
+    single: Option<String>,
+    /// (quantity, text) pairs in file order
+    plurals: Vec<(String, String)>,
+}
+
+/// Generates gettext template from twine file content, `source_lang` values become
+/// `msgid`s and key names become `msgctxt`s.
+/// Plural keys use `one` quantity as `msgid` and `other` as `msgid_plural`.
+pub fn export_pot(content: &str, source_file_name: &str, source_lang: &str) -> String {
+    let mut result = String::new();
+    result.push_str("msgid \"\"\n");
+    result.push_str("msgstr \"\"\n");
+    result.push_str("\"Content-Type: text/plain; charset=UTF-8\\n\"\n");
+
+    for key in source_keys(content, source_lang) {
+        let Some(entry) = format_entry(&key, source_file_name) else {
+            continue;
+        };
+        result.push('\n');
+        result.push_str(&entry);
+    }
+    result
+}
+
+fn source_keys(content: &str, source_lang: &str) -> Vec<SourceKey> {
+    let raw_keys = raw_sections(content)
+        .into_iter()
+        .flat_map(|section| section.keys);
+    let mut result: Vec<SourceKey> = Vec::new();
+    for raw_key in raw_keys {
+        let mut key = SourceKey {
+            name: raw_key.name,
+            line_number: raw_key.line_number,
+            comment: None,
+            single: None,
+            plurals: Vec::new(),
+        };
+        for entry in raw_key.entries {
+            if entry.name == COMMENT_ATTRIBUTE {
+                key.comment = Some(entry.value);
+            } else if entry.name == source_lang {
+                key.single = Some(entry.value);
+            } else if let Some((lang, quantity)) = entry.name.split_once(':') {
+                if lang == source_lang {
+                    key.plurals.push((quantity.to_string(), entry.value));
+                }
+            }
+        }
+        result.push(key);
+    }
+    result
+}
+
+fn format_entry(key: &SourceKey, source_file_name: &str) -> Option<String> {
+    let mut result = String::new();
+    if let Some(comment) = &key.comment {
+        result.push_str(&extracted_comment(comment));
+    }
+    result.push_str(&format!("#: {}:{}\n", source_file_name, key.line_number));
+    result.push_str(&format!("msgctxt {}\n", quote(&key.name)));
+
+    if key.plurals.is_empty() {
+        result.push_str(&format!("msgid {}\n", quote(key.single.as_ref()?)));
+        result.push_str("msgstr \"\"\n");
+    } else {
+        let quantity_text = |quantity: &str| {
+            key.plurals
+                .iter()
+                .find(|(item_quantity, _)| item_quantity == quantity)
+                .map(|(_, text)| text)
+        };
+        let (_, first_text) = &key.plurals[0];
+        let singular = quantity_text("one").unwrap_or(first_text);
+        let plural = quantity_text("other").unwrap_or(singular);
+        result.push_str(&format!("msgid {}\n", quote(singular)));
+        result.push_str(&format!("msgid_plural {}\n", quote(plural)));
+        result.push_str("msgstr[0] \"\"\n");
+        result.push_str("msgstr[1] \"\"\n");
+    }
+    Some(result)
+}
+
+/// Writes gettext template of all keys of the file to `output_path`
+pub fn write_pot(
+    file: &File,
+    output_path: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<(), GenError> {
+    sink.write(output_path, generate_po(file, None)?.as_bytes())?;
+    Ok(())
+}
+
+/// Writes translations of `lang` as .po file to `output_path`
+pub fn write_po(
+    file: &File,
+    lang: &str,
+    output_path: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<(), GenError> {
+    sink.write(output_path, generate_po(file, Some(lang))?.as_bytes())?;
+    Ok(())
+}
+
+/// Writes `<lang>.po` of every language of the file into `output_dir`
+pub fn write_po_all(
+    file: &File,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<(), GenError> {
+    for lang in file_languages(file) {
+        write_po(file, &lang, &output_dir.join(format!("{}.po", lang)), sink)?;
+    }
+    Ok(())
+}
+
+/// Generates .po content with translations of `lang`, or a template if it's `None`.
+/// Values of the first language of the file become `msgid`s and key names become
+/// `msgctxt`s, the same as `export_pot` does, so the result can be imported back with `import_po`.
+fn generate_po(file: &File, lang: Option<&str>) -> Result<String, GenError> {
+    let languages = file_languages(file);
+    let Some(source_lang) = languages.first() else {
+        return Err(GenError::EmptyFile);
+    };
+    let (categories, plural_forms) = gettext_plural_forms(lang.unwrap_or_default());
+    let mut result = String::new();
+    result.push_str("msgid \"\"\n");
+    result.push_str("msgstr \"\"\n");
+    result.push_str("\"MIME-Version: 1.0\\n\"\n");
+    result.push_str("\"Content-Type: text/plain; charset=UTF-8\\n\"\n");
+    result.push_str("\"Content-Transfer-Encoding: 8bit\\n\"\n");
+    if let Some(lang) = lang {
+        result.push_str(&format!("\"Language: {}\\n\"\n", lang));
+    }
+    result.push_str(&format!("\"Plural-Forms: {}\\n\"\n", plural_forms));
+
+    for key in file.sections.iter().flat_map(|section| &section.keys) {
+        let value_of = |lang: &str| {
+            key.localizations
+                .iter()
+                .find(|str| str.language_code == lang)
+                .map(|str| &str.value)
+        };
+        let Some(source) =
+            value_of(source_lang).or(key.localizations.first().map(|str| &str.value))
+        else {
+            continue;
+        };
+        let target = lang.and_then(value_of);
+
+        result.push('\n');
+        if let Some(comment) = &key.comment {
+            result.push_str(&extracted_comment(comment));
+        }
+        result.push_str(&format!("msgctxt {}\n", quote(&key.name)));
+        match source {
+            StringValue::Single(text) => {
+                let translation = match target {
+                    Some(StringValue::Single(text)) => unescape_android(text),
+                    _ => String::new(),
+                };
+                result.push_str(&format!("msgid {}\n", quote(&unescape_android(text))));
+                result.push_str(&format!("msgstr {}\n", quote(&translation)));
+            }
+            StringValue::Array(items) => {
+                let translation = match target {
+                    Some(StringValue::Array(items)) => unescape_android(&array_text(items)),
+                    _ => String::new(),
+                };
+                let text = unescape_android(&array_text(items));
+                result.push_str(&format!("msgid {}\n", quote(&text)));
+                result.push_str(&format!("msgstr {}\n", quote(&translation)));
+            }
+            StringValue::Plural { quantities } => {
+                let quantity_text = |items: &[PluralValue], quantity: &str| {
+                    items
+                        .iter()
+                        .find(|item| item.quantity == quantity)
+                        .map(|item| unescape_android(&item.text))
+                };
+                let singular = quantity_text(quantities, "one")
+                    .or_else(|| quantities.first().map(|item| unescape_android(&item.text)))
+                    .unwrap_or_default();
+                let plural = quantity_text(quantities, "other").unwrap_or(singular.clone());
+                result.push_str(&format!("msgid {}\n", quote(&singular)));
+                result.push_str(&format!("msgid_plural {}\n", quote(&plural)));
+                let translations: &[PluralValue] = match target {
+                    Some(StringValue::Plural { quantities }) => quantities,
+                    _ => &[],
+                };
+                for (index, category) in categories.iter().enumerate() {
+                    let translation = quantity_text(translations, category).unwrap_or_default();
+                    result.push_str(&format!("msgstr[{}] {}\n", index, quote(&translation)));
+                }
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Language codes of the file in order of their first appearance
+fn file_languages(file: &File) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    for str in file
+        .sections
+        .iter()
+        .flat_map(|section| &section.keys)
+        .flat_map(|key| &key.localizations)
+    {
+        if !result.contains(&str.language_code) {
+            result.push(str.language_code.clone());
+        }
+    }
+    result
+}
+
+/// Translated entry of .po file
+#[derive(Default)]
+struct PoEntry {
+    context: Option<String>,
+    id: String,
+    id_plural: Option<String>,
+    /// `msgstr` or `msgstr[n]` values by index
+    strs: Vec<String>,
+    fuzzy: bool,
+}
+
+/// Merges translations of .po file into twine file content as `lang` localizations.
+/// Entries are matched to keys by `msgctxt`, `msgstr[n]` are mapped to CLDR categories
+/// by gettext plural forms of the language. Fuzzy entries are skipped unless `include_fuzzy` is set.
+/// Returns updated content and names of keys which were not found in it.
+pub fn import_po(
+    po_content: &str,
+    twine_content: &str,
+    lang: &str,
+    include_fuzzy: bool,
+) -> (String, Vec<String>) {
+    let categories = gettext_plural_categories(lang);
+    let mut values: IndexMap<String, Vec<(String, String)>> = IndexMap::new();
+    for entry in parse_po(po_content) {
+        let Some(name) = entry.context else {
+            continue;
+        };
+        if entry.id.is_empty() || (entry.fuzzy && !include_fuzzy) {
+            continue;
+        }
+        let key_values: Vec<(String, String)> = if entry.id_plural.is_some() {
+            entry
+                .strs
+                .iter()
+                .zip(categories)
+                .filter(|(text, _)| !text.is_empty())
+                .map(|(text, category)| (format!("{}:{}", lang, category), text.clone()))
+                .collect()
+        } else {
+            entry
+                .strs
+                .first()
+                .filter(|text| !text.is_empty())
+                .map(|text| vec![(lang.to_string(), text.clone())])
+                .unwrap_or_default()
+        };
+        if !key_values.is_empty() {
+            values.insert(name, key_values);
+        }
+    }
+    set_localizations(twine_content, &values)
+}
+
+/// CLDR categories of `msgstr[n]` for commonly used gettext Plural-Forms of the language
+fn gettext_plural_categories(lang: &str) -> &'static [&'static str] {
+    gettext_plural_forms(lang).0
+}
+
+/// CLDR categories of `msgstr[n]` and the matching `Plural-Forms` header value of the language
+fn gettext_plural_forms(lang: &str) -> (&'static [&'static str], &'static str) {
+    let base = lang.split(['-', '_']).next().unwrap_or(lang);
+    match base {
+        "ja" | "ko" | "zh" | "vi" | "th" | "id" | "ms" | "tr" | "ka" | "kk" => {
+            (&["other"], "nplurals=1; plural=0;")
+        }
+        "ru" | "uk" | "be" | "sr" | "hr" | "bs" => (
+            &["one", "few", "many"],
+            "nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : \
+             n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2);",
+        ),
+        "pl" => (
+            &["one", "few", "many"],
+            "nplurals=3; plural=(n==1 ? 0 : \
+             n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2);",
+        ),
+        "cs" | "sk" => (
+            &["one", "few", "other"],
+            "nplurals=3; plural=(n==1 ? 0 : n>=2 && n<=4 ? 1 : 2);",
+        ),
+        "lt" => (
+            &["one", "few", "other"],
+            "nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : \
+             n%10>=2 && (n%100<10 || n%100>=20) ? 1 : 2);",
+        ),
+        "ar" => (
+            &["zero", "one", "two", "few", "many", "other"],
+            "nplurals=6; plural=(n==0 ? 0 : n==1 ? 1 : n==2 ? 2 : \
+             n%100>=3 && n%100<=10 ? 3 : n%100>=11 ? 4 : 5);",
+        ),
+        _ => (&["one", "other"], "nplurals=2; plural=(n != 1);"),
+    }
+}
+
+/// `#.` lines of .po entry, one per line of the key comment
+fn extracted_comment(comment: &str) -> String {
+    comment
+        .lines()
+        .map(|line| format!("#. {}\n", line))
+        .collect()
+}
+
+/// Field of .po entry which continuation lines (`"..."`) are appended to
+enum Field {
+    Context,
+    Id,
+    IdPlural,
+    Str(usize),
+}
+
+fn parse_po(content: &str) -> Vec<PoEntry> {
+    let mut result: Vec<PoEntry> = Vec::new();
+    let mut current = PoEntry::default();
+    let mut field: Option<Field> = None;
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with('#') {
+            // Comments start a new entry
+            if field.is_some() {
+                result.push(std::mem::take(&mut current));
+                field = None;
+            }
+            if line.starts_with("#,") && line.contains("fuzzy") {
+                current.fuzzy = true;
+            }
+            continue;
+        }
+        if line.starts_with('"') {
+            append_po_text(&mut current, &field, &unquote(line));
+            continue;
+        }
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let new_field = match keyword {
+            "msgctxt" => Field::Context,
+            "msgid" => Field::Id,
+            "msgid_plural" => Field::IdPlural,
+            "msgstr" => Field::Str(0),
+            _ => match keyword
+                .strip_prefix("msgstr[")
+                .and_then(|index| index.strip_suffix(']'))
+                .and_then(|index| index.parse().ok())
+            {
+                Some(index) => Field::Str(index),
+                None => continue,
+            },
+        };
+        // Entry without comments starts with msgctxt or msgid right after msgstr
+        let starts_entry = matches!(new_field, Field::Context | Field::Id);
+        if starts_entry && matches!(field, Some(Field::Str(_))) {
+            result.push(std::mem::take(&mut current));
+        }
+        field = Some(new_field);
+        append_po_text(&mut current, &field, &unquote(rest));
+    }
+    if field.is_some() {
+        result.push(current);
+    }
+    result
+}
+
+fn append_po_text(entry: &mut PoEntry, field: &Option<Field>, text: &str) {
+    match field {
+        Some(Field::Context) => entry.context.get_or_insert_with(String::new).push_str(text),
+        Some(Field::Id) => entry.id.push_str(text),
+        Some(Field::IdPlural) => entry
+            .id_plural
+            .get_or_insert_with(String::new)
+            .push_str(text),
+        Some(Field::Str(index)) => {
+            if entry.strs.len() <= *index {
+                entry.strs.resize(index + 1, String::new());
+            }
+            entry.strs[*index].push_str(text);
+        }
+        None => {}
+    }
+}
+
+fn unquote(text: &str) -> String {
+    let text = text.trim();
+    let text = text.strip_prefix('"').unwrap_or(text);
+    let text = text.strip_suffix('"').unwrap_or(text);
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            result.push(char);
+            continue;
+        }
+        // Twine keeps escape sequences like \n as is, only quoting is reverted
+        match chars.next() {
+            Some(quoted @ ('"' | '\\')) => result.push(quoted),
+            Some(other) => {
+                result.push(char);
+                result.push(other);
+            }
+            None => {}
+        }
+    }
+    result
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn exports_source_values_to_pot() {
+    let content = "[[Section]]
+  [login]
+    en = Login \"now\"
+    ru = Вход
+    comment = Button title
+
+  [days]
+    en:one = %d day
+    en:other = %d days
+    ru:one = %d день
+
+  [only_ru]
+    ru = Только русский
+";
+
+    let actual = export_pot(content, "strings.twine", "en");
+    let expected = "msgid \"\"
+msgstr \"\"
+\"Content-Type: text/plain; charset=UTF-8\\n\"
+
+#. Button title
+#: strings.twine:2
+msgctxt \"login\"
+msgid \"Login \\\"now\\\"\"
+msgstr \"\"
+
+#: strings.twine:7
+msgctxt \"days\"
+msgid \"%d day\"
+msgid_plural \"%d days\"
+msgstr[0] \"\"
+msgstr[1] \"\"
+";
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn imports_translations_from_po() {
+    let twine = "[[Section]]
+  [login]
+    en = Login
+
+  [days]
+    en:one = %d day
+    en:other = %d days
+
+  [settings]
+    en = Settings
+";
+    let po = "msgid \"\"
+msgstr \"\"
+\"Content-Type: text/plain; charset=UTF-8\\n\"
+
+#: strings.twine:2
+msgctxt \"login\"
+msgid \"Login\"
+msgstr \"Вход \"
+\"в систему\"
+
+msgctxt \"days\"
+msgid \"%d day\"
+msgid_plural \"%d days\"
+msgstr[0] \"%d день\"
+msgstr[1] \"%d дня\"
+msgstr[2] \"%d дней\"
+
+#, fuzzy
+msgctxt \"settings\"
+msgid \"Settings\"
+msgstr \"Настройки\"
+
+msgctxt \"unknown\"
+msgid \"Unknown\"
+msgstr \"Неизвестно\"
+";
+
+    let (actual, missing) = import_po(po, twine, "ru", false);
+    let expected = "[[Section]]
+  [login]
+    en = Login
+    ru = Вход в систему
+
+  [days]
+    en:one = %d day
+    en:other = %d days
+    ru:one = %d день
+    ru:few = %d дня
+    ru:many = %d дней
+
+  [settings]
+    en = Settings
+";
+    assert_eq!(expected, actual);
+    assert_eq!(missing, vec!["unknown".to_string()]);
+
+    let (actual, _) = import_po(po, twine, "ru", true);
+    assert!(actual.contains("ru = Настройки"));
+}
+
+#[test]
+fn generates_po_of_parsed_file() {
+    let content = "[[Section]]
+  [login]
+    en = Login \"now\" & go
+    ru = Войти
+    comment = Button title
+
+  [days]
+    en:one = %d day
+    en:other = %d days
+    ru:one = %d день
+    ru:few = %d дня
+    ru:many = %d дней
+";
+    let file = crate::parse::parse_str(content).unwrap();
+
+    let actual = generate_po(&file, Some("ru")).unwrap();
+    let expected = "msgid \"\"
+msgstr \"\"
+\"MIME-Version: 1.0\\n\"
+\"Content-Type: text/plain; charset=UTF-8\\n\"
+\"Content-Transfer-Encoding: 8bit\\n\"
+\"Language: ru\\n\"
+\"Plural-Forms: nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : \
+n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2);\\n\"
+
+#. Button title
+msgctxt \"login\"
+msgid \"Login \\\"now\\\" & go\"
+msgstr \"Войти\"
+
+msgctxt \"days\"
+msgid \"%d day\"
+msgid_plural \"%d days\"
+msgstr[0] \"%d день\"
+msgstr[1] \"%d дня\"
+msgstr[2] \"%d дней\"
+";
+    assert_eq!(expected, actual);
+
+    let template = generate_po(&file, None).unwrap();
+    assert!(template
+        .contains("msgid \"%d day\"\nmsgid_plural \"%d days\"\nmsgstr[0] \"\"\nmsgstr[1] \"\"\n"));
+    assert!(!template.contains("Language:"));
+
+    let dir = tempfile::tempdir().unwrap();
+    write_po_all(&file, dir.path(), &mut crate::sink::FsSink::default()).unwrap();
+    assert!(dir.path().join("en.po").is_file());
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("ru.po")).unwrap(),
+        actual
+    );
+}
+
+#[test]
+fn po_plural_forms_header_matches_plural_entries() {
+    let content = "[[Section]]
+  [days]
+    en:one = %d day
+    en:other = %d days
+    ru:one = %d день
+    ru:few = %d дня
+    ru:many = %d дней
+";
+    let file = crate::parse::parse_str(content).unwrap();
+
+    for (lang, nplurals) in [(Some("ru"), 3), (Some("en"), 2), (None, 2)] {
+        let po = generate_po(&file, lang).unwrap();
+        assert!(po.contains(&format!("\"Plural-Forms: nplurals={}; plural=", nplurals)));
+        assert!(po.contains(&format!("msgstr[{}] ", nplurals - 1)));
+        assert!(!po.contains(&format!("msgstr[{}] ", nplurals)));
+    }
+}
+
+#[test]
+fn prefixes_every_comment_line() {
+    let content = "[[Section]]
+  # First line
+  # Second line
+  [login]
+    en = Login
+";
+    let file = crate::parse::parse_str(content).unwrap();
+
+    let po = generate_po(&file, Some("en")).unwrap();
+    assert!(po.contains("#. First line\n#. Second line\nmsgctxt \"login\"\n"));
+}
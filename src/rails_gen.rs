@@ -0,0 +1,177 @@
+use indexmap::IndexMap;
+use std::path::Path;
+
+use crate::gen_error::GenError;
+use crate::parse::{File, PluralValue, StringValue};
+#[cfg(test)]
+use crate::parse::Section;
+use crate::platform::{
+    decode_android_escapes, replace_count_placeholder, to_named_placeholders_with_prefix,
+    unescape_android,
+};
+use crate::sink::WriteSink;
+#[cfg(test)]
+use crate::test_tools::key;
+
+/// Words which YAML 1.1 parsers like Psych read as booleans or null when not quoted
+const YAML_RESERVED_WORDS: &[&str] = &[
+    "y", "n", "yes", "no", "true", "false", "on", "off", "null", "~",
+];
+
+/// Writes `<lang>.yml` of every language into `output_dir` in Rails i18n structure, e.g.
+/// `en: { key_name: "value" }` with plurals nested as `key_name: { one: "...", other: "..." }`.
+/// Placeholders become Rails interpolations `%{argN}`, the count of plurals becomes `%{count}`.
+pub fn write_rails_yaml(
+    file: &File,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<(), GenError> {
+    write(file, output_dir, false, sink)
+}
+
+/// Same as `write_rails_yaml`, but printf placeholders are kept as is
+pub fn write_rails_yaml_keep_printf(
+    file: &File,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<(), GenError> {
+    write(file, output_dir, true, sink)
+}
+
+fn write(
+    file: &File,
+    output_dir: &Path,
+    keep_printf: bool,
+    sink: &mut dyn WriteSink,
+) -> Result<(), GenError> {
+    for (lang, content) in generate(file, keep_printf)? {
+        let path = output_dir.join(format!("{}.yml", lang));
+        sink.write(&path, content.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Generates content of .yml files keyed by language code in order of their first appearance
+fn generate(file: &File, keep_printf: bool) -> Result<IndexMap<String, String>, GenError> {
+    if file.sections.is_empty() {
+        return Err(GenError::EmptyFile);
+    }
+
+    let mut result: IndexMap<String, String> = IndexMap::new();
+    for key in file.sections.iter().flat_map(|section| &section.keys) {
+        for str in &key.localizations {
+            let content = result
+                .entry(str.language_code.clone())
+                .or_insert_with(|| format!("{}:\n", to_yaml_key(&str.language_code)));
+            match &str.value {
+                StringValue::Single(text) => {
+                    let value = to_rails_text(text, keep_printf, false);
+                    push_line(content, 1, &key.name, Some(&value));
+                }
+                StringValue::Array(items) => {
+                    push_line(content, 1, &key.name, None);
+                    for item in items {
+                        let value = to_rails_text(item, keep_printf, false);
+                        content.push_str(&format!("    - {}\n", serde_json::Value::from(value)));
+                    }
+                }
+                StringValue::Plural { quantities } => {
+                    push_line(content, 1, &key.name, None);
+                    let mut items: Vec<&PluralValue> = quantities.iter().collect();
+                    items.sort_by_key(|item| item.quantity_as_cldr_category());
+                    for item in items {
+                        let value = to_rails_text(&item.text, keep_printf, true);
+                        push_line(content, 2, &item.quantity, Some(&value));
+                    }
+                }
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Pushes `key: "value"` or `key:` of a nested mapping if there's no value
+fn push_line(content: &mut String, depth: usize, key: &str, value: Option<&str>) {
+    content.push_str(&"  ".repeat(depth));
+    content.push_str(&to_yaml_key(key));
+    content.push(':');
+    if let Some(value) = value {
+        content.push(' ');
+        // JSON string escapes are valid in YAML double quoted scalars
+        content.push_str(&serde_json::Value::from(value).to_string());
+    }
+    content.push('\n');
+}
+
+fn to_yaml_key(key: &str) -> String {
+    let is_plain = !key.is_empty()
+        && key
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || matches!(char, '_' | '-' | '.'))
+        && !YAML_RESERVED_WORDS.contains(&key.to_lowercase().as_str());
+    if is_plain {
+        key.to_string()
+    } else {
+        serde_json::Value::from(key).to_string()
+    }
+}
+
+/// Reverts Android specific escaping and replaces placeholders with Rails interpolations.
+/// The first placeholder of plural forms is the count which Rails passes as `%{count}`.
+fn to_rails_text(text: &str, keep_printf: bool, is_plural_form: bool) -> String {
+    let text = decode_android_escapes(&unescape_android(text));
+    if keep_printf {
+        return text;
+    }
+    let text = if is_plural_form {
+        replace_count_placeholder(&text, "%{count}")
+    } else {
+        text
+    };
+    to_named_placeholders_with_prefix(&text, "%{", "arg", "}")
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn generate_rails_yaml_per_lang() -> Result<(), GenError> {
+    let single = |text: &str| StringValue::Single(text.to_string());
+    let keys = vec![
+        key(
+            "greeting",
+            vec![
+                ("en", single("Hi %1$s, it\\'s \"%2$d\" &amp; more\\n\\\\")),
+                ("no", single("Hei %1$s")),
+            ],
+        ),
+        key(
+            "days",
+            vec![(
+                "en",
+                StringValue::plural()
+                    .quantity("other", "%1$d days of %2$s")
+                    .quantity("one", "%1$d day of %2$s")
+                    .build(),
+            )],
+        ),
+    ];
+    let source = File {
+        sections: vec![Section { keys }],
+    };
+
+    let actual = generate(&source, false)?;
+    assert_eq!(
+        actual["en"],
+        r#"en:
+  greeting: "Hi %{arg1}, it's \"%{arg2}\" & more\n\\"
+  days:
+    one: "%{count} day of %{arg2}"
+    other: "%{count} days of %{arg2}"
+"#
+    );
+    assert_eq!(actual["no"], "\"no\":\n  greeting: \"Hei %{arg1}\"\n");
+
+    let actual = generate(&source, true)?;
+    assert!(actual["en"].contains("    one: \"%1$d day of %2$s\"\n"));
+
+    Ok(())
+}
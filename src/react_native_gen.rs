@@ -0,0 +1,142 @@
+use indexmap::IndexMap;
+use serde_json::{Map, Value};
+use std::path::Path;
+
+use crate::gen_error::GenError;
+use crate::parse::{File, PluralValue, StringValue};
+#[cfg(test)]
+use crate::parse::Section;
+use crate::platform::{to_named_placeholders_with_prefix, unescape_android};
+use crate::sink::WriteSink;
+#[cfg(test)]
+use crate::test_tools::key;
+
+/// How quantities of plural keys are appended to the key name
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum PluralSuffix {
+    /// `key_one`, `key_other`, used by i18next and i18n-js
+    #[default]
+    Underscore,
+    /// `key.one`, `key.other`
+    Dot,
+}
+
+impl PluralSuffix {
+    fn key_name(&self, name: &str, quantity: &str) -> String {
+        match self {
+            PluralSuffix::Underscore => format!("{}_{}", name, quantity),
+            PluralSuffix::Dot => format!("{}.{}", name, quantity),
+        }
+    }
+}
+
+/// Writes `<lang>.json` of every language into `output_dir`
+pub fn write(
+    file: &File,
+    output_dir: &Path,
+    plural_suffix: PluralSuffix,
+    sink: &mut dyn WriteSink,
+) -> Result<(), GenError> {
+    for (lang, values) in generate(file, plural_suffix)? {
+        let path = output_dir.join(format!("{}.json", lang));
+        // Serializing of serde_json::Value can't fail
+        let content = serde_json::to_string_pretty(&values).unwrap() + "\n";
+        sink.write(&path, content.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Generates flat `{ "key": "value" }` objects keyed by language code in order of their first
+/// appearance. Every quantity of plural keys becomes a separate key with the suffix.
+fn generate(file: &File, plural_suffix: PluralSuffix) -> Result<IndexMap<String, Value>, GenError> {
+    if file.sections.is_empty() {
+        return Err(GenError::EmptyFile);
+    }
+
+    let mut result: IndexMap<String, Map<String, Value>> = IndexMap::new();
+    for key in file.sections.iter().flat_map(|section| &section.keys) {
+        for str in &key.localizations {
+            let values = result.entry(str.language_code.clone()).or_default();
+            match &str.value {
+                StringValue::Single(text) => {
+                    values.insert(key.name.clone(), Value::String(to_react_native_text(text)));
+                }
+                StringValue::Array(items) => {
+                    let items = items
+                        .iter()
+                        .map(|item| Value::String(to_react_native_text(item)));
+                    values.insert(key.name.clone(), Value::Array(items.collect()));
+                }
+                StringValue::Plural { quantities } => {
+                    let mut items: Vec<&PluralValue> = quantities.iter().collect();
+                    items.sort_by_key(|item| item.quantity_as_cldr_category());
+                    for item in items {
+                        values.insert(
+                            plural_suffix.key_name(&key.name, &item.quantity),
+                            Value::String(to_react_native_text(&item.text)),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(result
+        .into_iter()
+        .map(|(lang, values)| (lang, Value::Object(values)))
+        .collect())
+}
+
+/// Reverts Android specific escaping and replaces placeholders with `{{paramN}}`
+fn to_react_native_text(text: &str) -> String {
+    to_named_placeholders_with_prefix(&unescape_android(text), "{{", "param", "}}")
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn generate_json_per_lang() -> Result<(), GenError> {
+    use serde_json::json;
+
+    let keys = vec![
+        key(
+            "greeting",
+            vec![
+                (
+                    "en",
+                    StringValue::Single("Hi %1$s, it\\'s %2$d &amp; more".to_string()),
+                ),
+                ("de", StringValue::Single("Hallo %1$s".to_string())),
+            ],
+        ),
+        key(
+            "days",
+            vec![(
+                "en",
+                StringValue::plural()
+                    .quantity("other", "%d days")
+                    .quantity("one", "%d day")
+                    .build(),
+            )],
+        ),
+    ];
+    let source = File {
+        sections: vec![Section { keys }],
+    };
+
+    let actual = generate(&source, PluralSuffix::Underscore)?;
+    assert_eq!(actual.keys().collect::<Vec<_>>(), vec!["en", "de"]);
+    assert_eq!(
+        actual["en"],
+        json!({
+            "greeting": "Hi {{param1}}, it's {{param2}} & more",
+            "days_one": "{{param1}} day",
+            "days_other": "{{param1}} days",
+        })
+    );
+    assert_eq!(actual["de"], json!({ "greeting": "Hallo {{param1}}" }));
+
+    let actual = generate(&source, PluralSuffix::Dot)?;
+    assert_eq!(actual["en"]["days.one"], "{{param1}} day");
+
+    Ok(())
+}
@@ -0,0 +1,117 @@
+use regex::Regex;
+use std::fmt;
+
+use crate::parse::{
+    NON_NUMBERED_PLACEHOLDER_REGEX, PLACEHOLDER_REGEX, SINGLE_PERCENT_REGEX,
+    TWINE_STRING_PLACEHOLDER_REGEX,
+};
+
+/// Regex used while parsing twine files, for debugging of placeholder detection
+#[derive(PartialEq, Eq, Debug)]
+pub struct RegexInfo {
+    pub name: &'static str,
+    pub pattern: &'static str,
+    pub description: &'static str,
+    /// Sample values to show matches on
+    pub samples: &'static [&'static str],
+}
+
+const REGEXES: &[RegexInfo] = &[
+    RegexInfo {
+        name: "placeholder",
+        pattern: PLACEHOLDER_REGEX,
+        description: "Printf placeholder: `%`, optional position `N$`, flags, width, precision, \
+            length modifier and conversion. Values with such placeholders get positional \
+            numbers added if they have none.",
+        samples: &[
+            "Hello %1$s, you have %2$d new messages",
+            "%.2f%% done",
+            "%lld items",
+        ],
+    },
+    RegexInfo {
+        name: "non-numbered-placeholder",
+        pattern: NON_NUMBERED_PLACEHOLDER_REGEX,
+        description: "Printf placeholder without position. Positions are added to all of them \
+            in order of appearance when a value has more than one placeholder.",
+        samples: &["%s of %d", "%1$s of %2$d", "%-5.2f"],
+    },
+    RegexInfo {
+        name: "single-percent",
+        pattern: SINGLE_PERCENT_REGEX,
+        description: "Lone `%` together with the surrounding characters. Values which contain \
+            placeholders get such percents doubled, `%%` is left as is.",
+        samples: &["100% done", "%", "100%% done"],
+    },
+    RegexInfo {
+        name: "twine-string-placeholder",
+        pattern: TWINE_STRING_PLACEHOLDER_REGEX,
+        description: "iOS object placeholder `%@` with optional position, flags and width, \
+            which is converted to `%s`. Escaped `%%` is matched first to be skipped.",
+        samples: &["Hello, %@!", "%1$@ and %2$@", "100%%@"],
+    },
+];
+
+/// Names of all regexes available for `regex_info`
+pub fn regex_names() -> Vec<&'static str> {
+    REGEXES.iter().map(|info| info.name).collect()
+}
+
+pub fn regex_info(name: &str) -> Option<&'static RegexInfo> {
+    REGEXES.iter().find(|info| info.name == name)
+}
+
+impl RegexInfo {
+    /// Matched substrings of every sample
+    pub fn sample_matches(&self) -> Vec<(&'static str, Vec<&'static str>)> {
+        // Patterns are constants which are compiled while parsing as well
+        let regex = Regex::new(self.pattern).unwrap();
+        self.samples
+            .iter()
+            .map(|sample| {
+                let matches = regex.find_iter(sample).map(|found| found.as_str());
+                (*sample, matches.collect())
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for RegexInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.name)?;
+        writeln!(f, "  pattern: {}", self.pattern)?;
+        writeln!(f, "  {}", self.description)?;
+        writeln!(f, "  examples:")?;
+        for (sample, matches) in self.sample_matches() {
+            if matches.is_empty() {
+                writeln!(f, "    {:?} -> no matches", sample)?;
+            } else {
+                let matches: Vec<String> = matches.iter().map(|m| format!("{:?}", m)).collect();
+                writeln!(f, "    {:?} -> {}", sample, matches.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn show_placeholder_matches() {
+    let info = regex_info("placeholder").unwrap();
+
+    assert_eq!(
+        info.sample_matches(),
+        vec![
+            (
+                "Hello %1$s, you have %2$d new messages",
+                vec!["%1$s", "%2$d"]
+            ),
+            ("%.2f%% done", vec!["%.2f"]),
+            ("%lld items", vec!["%lld"]),
+        ]
+    );
+    assert!(info
+        .to_string()
+        .starts_with(&format!("placeholder\n  pattern: {}\n", PLACEHOLDER_REGEX)));
+    assert!(regex_info("unknown").is_none());
+}
@@ -0,0 +1,114 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Destination of generated files, e.g. the file system or memory for dry runs
+pub trait WriteSink {
+    /// Writes `content` to `path` creating parent directories if needed
+    fn write(&mut self, path: &Path, content: &[u8]) -> io::Result<()>;
+}
+
+/// Writes files to the file system, files which already have the same content are not
+/// rewritten
+#[derive(Default)]
+pub struct FsSink {
+    /// Paths of all written files with their statuses in order of writing
+    pub written: Vec<(PathBuf, FileStatus)>,
+}
+
+impl WriteSink for FsSink {
+    fn write(&mut self, path: &Path, content: &[u8]) -> io::Result<()> {
+        let status = file_status(path, content);
+        if status != FileStatus::Unchanged {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, content)?;
+        }
+        self.written.push((path.to_path_buf(), status));
+        Ok(())
+    }
+}
+
+/// Keeps written files in memory in order of writing, nothing is written to the file system.
+/// Writing the same path again replaces its content.
+#[derive(Default)]
+pub struct MemorySink {
+    pub files: Vec<(PathBuf, Vec<u8>)>,
+}
+
+impl WriteSink for MemorySink {
+    fn write(&mut self, path: &Path, content: &[u8]) -> io::Result<()> {
+        match self.files.iter_mut().find(|(written, _)| written == path) {
+            Some((_, existing)) => *existing = content.to_vec(),
+            None => self.files.push((path.to_path_buf(), content.to_vec())),
+        }
+        Ok(())
+    }
+}
+
+/// Generated file compared with the file which is on disk already
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum FileStatus {
+    Created,
+    Modified,
+    Unchanged,
+}
+
+impl FileStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileStatus::Created => "created",
+            FileStatus::Modified => "modified",
+            FileStatus::Unchanged => "unchanged",
+        }
+    }
+}
+
+/// Status of writing `content` to `path` compared with the file at `path`
+pub fn file_status(path: &Path, content: &[u8]) -> FileStatus {
+    match fs::read(path) {
+        Ok(existing) if existing == content => FileStatus::Unchanged,
+        Ok(_) => FileStatus::Modified,
+        Err(_) => FileStatus::Created,
+    }
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn writes_only_changed_files() -> io::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let strings = dir.path().join("strings.xml");
+    let de_strings = dir.path().join("values-de/strings.xml");
+
+    let mut sink = FsSink::default();
+    sink.write(&strings, b"en")?;
+    sink.write(&de_strings, b"de")?;
+    assert_eq!(
+        sink.written,
+        vec![
+            (strings.clone(), FileStatus::Created),
+            (de_strings.clone(), FileStatus::Created)
+        ]
+    );
+    assert_eq!(fs::read_to_string(&de_strings)?, "de");
+
+    let mut sink = FsSink::default();
+    sink.write(&strings, b"en!")?;
+    sink.write(&de_strings, b"de")?;
+    assert_eq!(
+        sink.written,
+        vec![
+            (strings.clone(), FileStatus::Modified),
+            (de_strings.clone(), FileStatus::Unchanged)
+        ]
+    );
+    assert_eq!(fs::read_to_string(&strings)?, "en!");
+
+    let mut sink = MemorySink::default();
+    sink.write(&strings, b"en?")?;
+    sink.write(&strings, b"en")?;
+    assert_eq!(sink.files, vec![(strings.clone(), b"en".to_vec())]);
+    assert_eq!(fs::read_to_string(&strings)?, "en!");
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use indexmap::IndexMap;
+
+use crate::twine_text::{format_raw_key, raw_sections, RawKey};
+
+/// Name of the file for keys without prefix or with rare prefixes
+pub const MISC_FILE_NAME: &str = "misc";
+
+/// Groups keys of twine file content by name prefix before `separator`, e.g. `login`
+/// of `login_title`. Keys without prefix and groups smaller than `min_keys_per_file`
+/// go to [`MISC_FILE_NAME`]. Returns content of each group by its name, sections
+/// of the keys are kept.
+pub fn split_by_prefix(
+    content: &str,
+    separator: &str,
+    min_keys_per_file: usize,
+) -> IndexMap<String, String> {
+    // group name -> section name -> keys
+    let mut groups: IndexMap<String, IndexMap<String, Vec<RawKey>>> = IndexMap::new();
+    for section in raw_sections(content) {
+        for key in section.keys {
+            let prefix = match key.name.split_once(separator) {
+                Some((prefix, _)) if !prefix.is_empty() && !separator.is_empty() => prefix,
+                _ => MISC_FILE_NAME,
+            };
+            groups
+                .entry(prefix.to_string())
+                .or_default()
+                .entry(section.name.clone())
+                .or_default()
+                .push(key);
+        }
+    }
+
+    let mut misc: IndexMap<String, Vec<RawKey>> =
+        groups.shift_remove(MISC_FILE_NAME).unwrap_or_default();
+    let mut result: IndexMap<String, String> = IndexMap::new();
+    for (prefix, sections) in groups {
+        let count: usize = sections.values().map(Vec::len).sum();
+        if count >= min_keys_per_file {
+            result.insert(prefix, format_sections(&sections));
+            continue;
+        }
+        for (section_name, keys) in sections {
+            misc.entry(section_name).or_default().extend(keys);
+        }
+    }
+    if !misc.is_empty() {
+        result.insert(MISC_FILE_NAME.to_string(), format_sections(&misc));
+    }
+    result
+}
+
+fn format_sections(sections: &IndexMap<String, Vec<RawKey>>) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    for (name, keys) in sections {
+        if !name.is_empty() {
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.push(format!("[[{}]]", name));
+        }
+        for (index, key) in keys.iter().enumerate() {
+            if index > 0 {
+                lines.push(String::new());
+            }
+            lines.extend(format_raw_key(key));
+        }
+    }
+    let mut output = lines.join("\n");
+    output.push('\n');
+    output
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn splits_keys_by_prefix() {
+    let content = "[[General]]
+  [login_title]
+    en = Login
+
+  [login_button]
+    en = Log in
+
+  [settings_title]
+    en = Settings
+
+[[Other]]
+  [login_hint]
+    en = Enter your email
+
+  [cancel]
+    en = Cancel
+";
+
+    let actual = split_by_prefix(content, "_", 2);
+    assert_eq!(
+        actual.keys().collect::<Vec<_>>(),
+        vec![&"login".to_string(), &"misc".to_string()]
+    );
+    assert_eq!(
+        actual["login"],
+        "[[General]]
+  [login_title]
+    en = Login
+
+  [login_button]
+    en = Log in
+
+[[Other]]
+  [login_hint]
+    en = Enter your email
+"
+    );
+    assert_eq!(
+        actual["misc"],
+        "[[Other]]
+  [cancel]
+    en = Cancel
+
+[[General]]
+  [settings_title]
+    en = Settings
+"
+    );
+}
@@ -0,0 +1,30 @@
+use crate::parse::{Key, LocalizedString, PluralValue, StringValue};
+
+/// Allows `key("name", vec![("en", StringValue::Single(..))])` in fixtures
+impl From<(&str, StringValue)> for LocalizedString {
+    fn from((lang, value): (&str, StringValue)) -> Self {
+        LocalizedString {
+            language_code: lang.to_string(),
+            value,
+        }
+    }
+}
+
+pub(crate) fn plain_str(lang: &str, txt: &str) -> LocalizedString {
+    LocalizedString::from((lang, StringValue::Single(txt.to_string())))
+}
+
+pub(crate) fn plurals(lang: &str, quantities: Vec<PluralValue>) -> LocalizedString {
+    LocalizedString::from((lang, StringValue::Plural { quantities }))
+}
+
+/// Translatable key without comment and max length
+pub(crate) fn key<L: Into<LocalizedString>>(name: &str, localizations: Vec<L>) -> Key {
+    Key {
+        name: name.to_string(),
+        localizations: localizations.into_iter().map(Into::into).collect(),
+        max_length: None,
+        comment: None,
+        translatable: true,
+    }
+}
@@ -0,0 +1,426 @@
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+#[cfg(test)]
+use crate::parse::{maybe_add_positional_numbers, Section};
+use crate::parse::{File, Key, LocalizedString, PluralValue, StringValue, ARRAY_DELIMITER};
+#[cfg(test)]
+use crate::test_tools::{key, plain_str, plurals};
+
+/// Replaces every plural key with a set of single keys suffixed with `_<quantity>`,
+/// e.g. `receipt_example` becomes `receipt_example_one` and `receipt_example_other`
+pub fn flatten_plurals(file: &mut File) {
+    for section in &mut file.sections {
+        let keys = std::mem::take(&mut section.keys);
+        for key in keys {
+            section.keys.extend(flatten_key(key));
+        }
+    }
+}
+
+/// Converts every plural with exactly one quantity into a single value with that quantity's text
+pub fn coerce_single_quantity_plurals(file: &mut File) {
+    for key in file
+        .sections
+        .iter_mut()
+        .flat_map(|section| &mut section.keys)
+    {
+        for str in &mut key.localizations {
+            if let StringValue::Plural { quantities } = &mut str.value {
+                if quantities.len() == 1 {
+                    let item = quantities.remove(0);
+                    str.value = StringValue::Single(item.text);
+                }
+            }
+        }
+    }
+}
+
+/// Converts every single value into a plural with the only `other` quantity
+pub fn convert_singles_to_plurals(file: &mut File) {
+    for key in file
+        .sections
+        .iter_mut()
+        .flat_map(|section| &mut section.keys)
+    {
+        for str in &mut key.localizations {
+            if let StringValue::Single(text) = &mut str.value {
+                str.value = StringValue::Plural {
+                    quantities: vec![PluralValue::new("other", std::mem::take(text))],
+                };
+            }
+        }
+    }
+}
+
+/// Removes localizations of languages not listed in `languages`, keys left without
+/// localizations are removed too
+pub fn retain_languages(file: &mut File, languages: &[String]) {
+    for section in &mut file.sections {
+        for key in &mut section.keys {
+            key.localizations
+                .retain(|str| languages.contains(&str.language_code));
+        }
+        section.keys.retain(|key| !key.localizations.is_empty());
+    }
+}
+
+/// Replaces literal line breaks in values with `\n` escape sequences, Android collapses
+/// whitespace of string resources so literal line breaks are lost
+pub fn escape_newlines(file: &mut File) {
+    let escape = |text: &mut String| {
+        if text.contains('\n') {
+            *text = text.replace("\r\n", "\\n").replace('\n', "\\n");
+        }
+    };
+    for key in file
+        .sections
+        .iter_mut()
+        .flat_map(|section| &mut section.keys)
+    {
+        for str in &mut key.localizations {
+            match &mut str.value {
+                StringValue::Single(text) => escape(text),
+                StringValue::Plural { quantities } => quantities
+                    .iter_mut()
+                    .for_each(|item| escape(&mut item.text)),
+                StringValue::Array(items) => items.iter_mut().for_each(escape),
+            }
+        }
+    }
+}
+
+/// Splits items of array values by `delimiter` instead of the default `|` one,
+/// items split while parsing are joined back first
+pub fn split_arrays_by(file: &mut File, delimiter: &str) {
+    for str in file
+        .sections
+        .iter_mut()
+        .flat_map(|section| &mut section.keys)
+        .flat_map(|key| &mut key.localizations)
+    {
+        if let StringValue::Array(items) = &mut str.value {
+            *items = items
+                .join(ARRAY_DELIMITER)
+                .split(delimiter)
+                .map(|item| item.trim().to_string())
+                .collect();
+        }
+    }
+}
+
+/// Strips HTML tags, including ones with attributes and self-closing ones like `<br/>`,
+/// for platforms which don't support markup. XML entities escaped while parsing are
+/// unescaped first, so `&lt;b>` is stripped as well.
+pub fn remove_html_tags(value: &str) -> String {
+    lazy_static! {
+        static ref TAG_RE: Regex = Regex::new(r"</?[a-zA-Z][^<>]*>").unwrap();
+    }
+    let unescaped = value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'");
+    // `&amp;` goes last so that `&amp;lt;` stays literal `&lt;` instead of becoming a tag
+    TAG_RE.replace_all(&unescaped, "").replace("&amp;", "&")
+}
+
+/// Removes positional numbers from placeholders, e.g. `%1$s %2$d` becomes `%s %d`.
+/// Inverse of numbering done while parsing, `%%` is kept as is.
+pub fn strip_positional_numbers(value: &str) -> String {
+    lazy_static! {
+        static ref POSITION_RE: Regex = Regex::new(r"%%|%\d+\$").unwrap();
+    }
+    POSITION_RE
+        .replace_all(value, |caps: &regex::Captures| match &caps[0] {
+            "%%" => "%%",
+            _ => "%",
+        })
+        .to_string()
+}
+
+fn flatten_key(key: Key) -> Vec<Key> {
+    if !key
+        .localizations
+        .iter()
+        .any(|str| matches!(str.value, StringValue::Plural { .. }))
+    {
+        return vec![key];
+    }
+
+    let mut singles: Vec<LocalizedString> = Vec::new();
+    let mut flattened: IndexMap<String, Key> = IndexMap::new();
+    for str in key.localizations {
+        match str.value {
+            StringValue::Single(_) | StringValue::Array(_) => singles.push(str),
+            StringValue::Plural { quantities } => {
+                for item in quantities {
+                    let name = format!("{}_{}", key.name, item.quantity);
+                    flattened
+                        .entry(name.clone())
+                        .or_insert(Key {
+                            name,
+                            localizations: Vec::new(),
+                            max_length: key.max_length,
+                            comment: key.comment.clone(),
+                            translatable: key.translatable,
+                        })
+                        .localizations
+                        .push(LocalizedString {
+                            language_code: str.language_code.clone(),
+                            value: StringValue::Single(item.text),
+                        });
+                }
+            }
+        }
+    }
+
+    let mut result: Vec<Key> = Vec::with_capacity(flattened.len() + 1);
+    if !singles.is_empty() {
+        result.push(Key {
+            name: key.name,
+            localizations: singles,
+            max_length: key.max_length,
+            comment: key.comment,
+            translatable: key.translatable,
+        });
+    }
+    result.extend(flattened.into_values());
+    result
+}
+
+// -----------------------------  test tools ------------------------------
+#[cfg(test)]
+fn key_summary(file: &File) -> Vec<(String, Vec<(String, StringValue)>)> {
+    file.sections[0]
+        .keys
+        .iter()
+        .map(|key| {
+            (
+                key.name.clone(),
+                key.localizations
+                    .iter()
+                    .map(|str| (str.language_code.clone(), str.value.clone()))
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn flatten_plurals_into_single_keys() {
+    let keys = vec![
+        key("chicken", vec![plain_str("en", "Chicken")]),
+        key(
+            "cows",
+            vec![
+                plurals(
+                    "en",
+                    vec![PluralValue::new("one", "%d cow"), PluralValue::new("other", "%d cows")],
+                ),
+                plurals(
+                    "ru",
+                    vec![
+                        PluralValue::new("one", "%d корова"),
+                        PluralValue::new("few", "%d коровы"),
+                    ],
+                ),
+            ],
+        ),
+    ];
+    let mut file = File {
+        sections: vec![Section { keys }],
+    };
+
+    flatten_plurals(&mut file);
+
+    let single = |lang: &str, text: &str| (lang.to_string(), StringValue::Single(text.to_string()));
+    assert_eq!(
+        key_summary(&file),
+        vec![
+            ("chicken".to_string(), vec![single("en", "Chicken")]),
+            (
+                "cows_one".to_string(),
+                vec![single("en", "%d cow"), single("ru", "%d корова")]
+            ),
+            ("cows_other".to_string(), vec![single("en", "%d cows")]),
+            ("cows_few".to_string(), vec![single("ru", "%d коровы")]),
+        ]
+    );
+}
+
+#[test]
+fn coerce_single_quantity_plurals_to_single() {
+    let keys = vec![
+        key(
+            "songs",
+            vec![
+                plurals("mn", vec![PluralValue::new("other", "%d дуу")]),
+                plurals(
+                    "en",
+                    vec![
+                        PluralValue::new("one", "%d song"),
+                        PluralValue::new("other", "%d songs"),
+                    ],
+                ),
+            ],
+        ),
+        key("chicken", vec![plain_str("en", "Chicken")]),
+    ];
+    let mut file = File {
+        sections: vec![Section { keys }],
+    };
+
+    coerce_single_quantity_plurals(&mut file);
+
+    assert_eq!(
+        key_summary(&file),
+        vec![
+            (
+                "songs".to_string(),
+                vec![
+                    ("mn".to_string(), StringValue::Single("%d дуу".to_string())),
+                    (
+                        "en".to_string(),
+                        StringValue::Plural {
+                            quantities: vec![
+                                PluralValue::new("one", "%d song"),
+                                PluralValue::new("other", "%d songs")
+                            ]
+                        }
+                    ),
+                ]
+            ),
+            (
+                "chicken".to_string(),
+                vec![("en".to_string(), StringValue::Single("Chicken".to_string()))]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn convert_singles_to_other_quantity_plurals() {
+    let keys = vec![
+        key("chicken", vec![plain_str("en", "Chicken")]),
+        key(
+            "cows",
+            vec![plurals("en", vec![PluralValue::new("one", "%d cow")])],
+        ),
+    ];
+    let mut file = File {
+        sections: vec![Section { keys }],
+    };
+
+    convert_singles_to_plurals(&mut file);
+
+    let plural = |lang: &str, quantity: &str, text: &str| {
+        (
+            lang.to_string(),
+            StringValue::Plural {
+                quantities: vec![PluralValue::new(quantity, text)],
+            },
+        )
+    };
+    assert_eq!(
+        key_summary(&file),
+        vec![
+            (
+                "chicken".to_string(),
+                vec![plural("en", "other", "Chicken")]
+            ),
+            ("cows".to_string(), vec![plural("en", "one", "%d cow")]),
+        ]
+    );
+}
+
+#[test]
+fn retain_only_listed_languages() {
+    let keys = vec![
+        key(
+            "chicken",
+            vec![plain_str("en", "Chicken"), plain_str("fr", "Poulet")],
+        ),
+        key("cow", vec![plain_str("fr", "Vache")]),
+    ];
+    let mut file = File {
+        sections: vec![Section { keys }],
+    };
+
+    retain_languages(&mut file, &["en".to_string(), "de".to_string()]);
+
+    assert_eq!(
+        key_summary(&file),
+        vec![(
+            "chicken".to_string(),
+            vec![("en".to_string(), StringValue::Single("Chicken".to_string()))]
+        )]
+    );
+}
+
+#[test]
+fn escape_literal_newlines() {
+    let keys = vec![
+        key("intro", vec![plain_str("en", "Line 1\nLine 2\r\nLine 3")]),
+        key(
+            "cows",
+            vec![plurals("en", vec![PluralValue::new("other", "%d\ncows")])],
+        ),
+    ];
+    let mut file = File {
+        sections: vec![Section { keys }],
+    };
+
+    escape_newlines(&mut file);
+
+    assert_eq!(
+        key_summary(&file),
+        vec![
+            (
+                "intro".to_string(),
+                vec![(
+                    "en".to_string(),
+                    StringValue::Single("Line 1\\nLine 2\\nLine 3".to_string())
+                )]
+            ),
+            (
+                "cows".to_string(),
+                vec![(
+                    "en".to_string(),
+                    StringValue::Plural {
+                        quantities: vec![PluralValue::new("other", "%d\\ncows")]
+                    }
+                )]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn remove_html_tags_from_value() {
+    assert_eq!(remove_html_tags("<b>Bold</b> text"), "Bold text");
+    assert_eq!(
+        remove_html_tags("<a href=\"https://example.com\">Terms</a> apply"),
+        "Terms apply"
+    );
+    assert_eq!(remove_html_tags("Line<br/>break<br />"), "Linebreak");
+    assert_eq!(remove_html_tags("&lt;i>Escaped&lt;/i> tags"), "Escaped tags");
+    assert_eq!(remove_html_tags("1 &lt; 2 &amp; 3 > 2"), "1 < 2 & 3 > 2");
+    assert_eq!(remove_html_tags("&amp;lt;b> literal"), "&lt;b> literal");
+}
+
+#[test]
+fn strip_positional_numbers_from_placeholders() {
+    assert_eq!(strip_positional_numbers("%1$s %2$d"), "%s %d");
+    assert_eq!(strip_positional_numbers("%2$.2f of %1$@"), "%.2f of %@");
+    assert_eq!(strip_positional_numbers("100%% %1$s"), "100%% %s");
+    assert_eq!(strip_positional_numbers("%%1$s"), "%%1$s");
+    for value in ["%s and %d", "%s, %.2f, %@ and %ld", "%d%% of %s"] {
+        assert_eq!(
+            strip_positional_numbers(&maybe_add_positional_numbers(value)),
+            value
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use anyhow::{anyhow, Result};
+
+use crate::parse::AUTO_TRANSLATED_ATTRIBUTE;
+use crate::twine_text::{map_key_blocks, parse_entry, Entry};
+
+/// Machine translation provider
+pub trait Translator {
+    fn translate(&self, text: &str, source_lang: &str, target_lang: &str) -> Result<String>;
+}
+
+/// https://www.deepl.com/docs-api/translate-text
+pub struct DeepL {
+    api_key: String,
+}
+
+impl DeepL {
+    pub fn new(api_key: String) -> Self {
+        DeepL { api_key }
+    }
+
+    fn endpoint(&self) -> &'static str {
+        // Keys of DeepL API Free plan have ":fx" suffix and have to use separate host
+        if self.api_key.ends_with(":fx") {
+            "https://api-free.deepl.com/v2/translate"
+        } else {
+            "https://api.deepl.com/v2/translate"
+        }
+    }
+}
+
+impl Translator for DeepL {
+    fn translate(&self, text: &str, source_lang: &str, target_lang: &str) -> Result<String> {
+        let response = ureq::post(self.endpoint())
+            .set("Authorization", &format!("DeepL-Auth-Key {}", self.api_key))
+            .send_form(&[
+                ("text", text),
+                ("source_lang", &source_lang.to_uppercase()),
+                ("target_lang", &target_lang.to_uppercase()),
+            ])?
+            .into_string()?;
+        let response: serde_json::Value = serde_json::from_str(&response)?;
+        response["translations"][0]["text"]
+            .as_str()
+            .map(|text| text.to_string())
+            .ok_or(anyhow!("Unexpected DeepL response: {}", response))
+    }
+}
+
+/// Fills missing `target_lang` localizations of twine file content by translating
+/// `source_lang` values. Plural quantities are translated one by one (`en:one` -> `de:one`).
+/// Existing localizations are replaced only if `overwrite_existing` is set.
+/// Every key with translated values gets `auto_translated = true` attribute.
+pub fn translate_missing(
+    content: &str,
+    translator: &dyn Translator,
+    source_lang: &str,
+    target_lang: &str,
+    overwrite_existing: bool,
+) -> Result<String> {
+    map_key_blocks(content, |block| {
+        translate_block(
+            block,
+            translator,
+            source_lang,
+            target_lang,
+            overwrite_existing,
+        )
+    })
+}
+
+fn translate_block(
+    block: &[&str],
+    translator: &dyn Translator,
+    source_lang: &str,
+    target_lang: &str,
+    overwrite_existing: bool,
+) -> Result<Vec<String>> {
+    let mut lines: Vec<String> = block.iter().map(|line| line.to_string()).collect();
+    let entries: Vec<Entry> = block
+        .iter()
+        .enumerate()
+        .filter_map(|(line_index, line)| parse_entry(line_index, line))
+        .collect();
+
+    let mut added: Vec<String> = Vec::new();
+    let mut translated_any = false;
+    for entry in &entries {
+        let (lang, quantity) = match entry.name.split_once(':') {
+            Some((lang, quantity)) => (lang, Some(quantity)),
+            None => (entry.name.as_str(), None),
+        };
+        if lang != source_lang {
+            continue;
+        }
+        let target_name = match quantity {
+            Some(quantity) => format!("{}:{}", target_lang, quantity),
+            None => target_lang.to_string(),
+        };
+        let existing = entries.iter().find(|entry| entry.name == target_name);
+        if existing.is_some() && !overwrite_existing {
+            continue;
+        }
+        let translated = translator.translate(&entry.value, source_lang, target_lang)?;
+        let line = format!("{}{} = {}", entry.indent, target_name, translated);
+        translated_any = true;
+        match existing {
+            Some(existing) => lines[existing.line_index] = line,
+            None => added.push(line),
+        }
+    }
+
+    let already_marked = entries
+        .iter()
+        .any(|entry| entry.name == AUTO_TRANSLATED_ATTRIBUTE);
+    if translated_any && !already_marked {
+        let indent = entries
+            .first()
+            .map(|entry| entry.indent.clone())
+            .unwrap_or_default();
+        added.push(format!("{}{} = true", indent, AUTO_TRANSLATED_ATTRIBUTE));
+    }
+
+    // New lines go right after the last entry so that blank lines between keys are kept
+    let insert_at = entries
+        .last()
+        .map(|entry| entry.line_index + 1)
+        .unwrap_or(1);
+    lines.splice(insert_at..insert_at, added);
+    Ok(lines)
+}
+
+// -----------------------------  test tools ------------------------------
+#[cfg(test)]
+struct FakeTranslator;
+
+#[cfg(test)]
+impl Translator for FakeTranslator {
+    fn translate(&self, text: &str, _source_lang: &str, target_lang: &str) -> Result<String> {
+        Ok(format!("{} ({})", text, target_lang))
+    }
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn translates_only_missing_localizations() -> Result<()> {
+    let content = "[[Section]]
+  [login]
+    en = Login
+    ru = Вход
+
+  [logout]
+    en = Logout
+    de = Abmelden
+
+  [days]
+    en:one = %d day
+    en:other = %d days
+";
+
+    let actual = translate_missing(content, &FakeTranslator, "en", "de", false)?;
+    let expected = "[[Section]]
+  [login]
+    en = Login
+    ru = Вход
+    de = Login (de)
+    auto_translated = true
+
+  [logout]
+    en = Logout
+    de = Abmelden
+
+  [days]
+    en:one = %d day
+    en:other = %d days
+    de:one = %d day (de)
+    de:other = %d days (de)
+    auto_translated = true
+";
+    assert_eq!(expected, actual);
+
+    Ok(())
+}
+
+#[test]
+fn translates_existing_localizations_if_overwrite_is_set() -> Result<()> {
+    let content = "[[Section]]
+  [logout]
+    en = Logout
+    de = Abmelden
+    comment = Button title";
+
+    let actual = translate_missing(content, &FakeTranslator, "en", "de", true)?;
+    let expected = "[[Section]]
+  [logout]
+    en = Logout
+    de = Logout (de)
+    comment = Button title
+    auto_translated = true";
+    assert_eq!(expected, actual);
+
+    Ok(())
+}
@@ -0,0 +1,562 @@
+use anyhow::Result;
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use std::collections::HashSet;
+
+use crate::parse::{
+    maybe_add_positional_numbers, CldrCategory, ARRAY_DELIMITER, KEY_ATTRIBUTES, PLACEHOLDER_REGEX,
+    STRING_ARRAY_TYPE, TYPE_ATTRIBUTE,
+};
+
+/// `name = value` line of twine file content
+pub(crate) struct Entry {
+    pub(crate) line_index: usize,
+    pub(crate) name: String,
+    pub(crate) value: String,
+    pub(crate) indent: String,
+}
+
+/// Replaces or adds localizations in twine file content. `values` maps key names
+/// to `(entry name, value)` pairs, e.g. `("de:one", "%d Tag")`.
+/// Returns updated content and names of keys which were not found in it.
+pub fn set_localizations(
+    content: &str,
+    values: &IndexMap<String, Vec<(String, String)>>,
+) -> (String, Vec<String>) {
+    let mut found: HashSet<String> = HashSet::new();
+    // Closure never fails
+    let updated = map_key_blocks(content, |block| {
+        let name = key_header_name(block[0]);
+        let Some(key_values) = values.get(name) else {
+            return Ok(block.iter().map(|line| line.to_string()).collect());
+        };
+        found.insert(name.to_string());
+        Ok(set_block_values(block, key_values))
+    })
+    .unwrap();
+    let missing = values
+        .keys()
+        .filter(|name| !found.contains(*name))
+        .cloned()
+        .collect();
+    (updated, missing)
+}
+
+/// Applies `transform` to lines of each key (header included), other lines are kept as is
+pub(crate) fn map_key_blocks(
+    content: &str,
+    mut transform: impl FnMut(&[&str]) -> Result<Vec<String>>,
+) -> Result<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut result: Vec<String> = Vec::with_capacity(lines.len());
+
+    let mut block_start = 0;
+    while block_start < lines.len() {
+        let mut block_end = block_start + 1;
+        while block_end < lines.len() && !is_header(lines[block_end]) {
+            block_end += 1;
+        }
+        let block = &lines[block_start..block_end];
+        if is_key_header(block[0]) {
+            result.extend(transform(block)?);
+        } else {
+            result.extend(block.iter().map(|line| line.to_string()));
+        }
+        block_start = block_end;
+    }
+
+    let mut output = result.join("\n");
+    if content.ends_with('\n') {
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+fn set_block_values(block: &[&str], values: &[(String, String)]) -> Vec<String> {
+    let mut lines: Vec<String> = block.iter().map(|line| line.to_string()).collect();
+    let entries: Vec<Entry> = block
+        .iter()
+        .enumerate()
+        .filter_map(|(line_index, line)| parse_entry(line_index, line))
+        .collect();
+    let indent = entries
+        .first()
+        .map(|entry| entry.indent.clone())
+        .unwrap_or_default();
+
+    let mut added: Vec<String> = Vec::new();
+    for (name, value) in values {
+        let line = format!("{}{} = {}", indent, name, value);
+        match entries.iter().find(|entry| &entry.name == name) {
+            Some(existing) => lines[existing.line_index] = line,
+            None => added.push(line),
+        }
+    }
+
+    let insert_at = entries
+        .last()
+        .map(|entry| entry.line_index + 1)
+        .unwrap_or(1);
+    lines.splice(insert_at..insert_at, added);
+    lines
+}
+
+pub(crate) fn parse_entry(line_index: usize, line: &str) -> Option<Entry> {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with('#') || trimmed.starts_with(';') || is_header(line) {
+        return None;
+    }
+    let (name, value) = trimmed.split_once('=')?;
+    Some(Entry {
+        line_index,
+        name: name.trim().to_string(),
+        value: value.trim().to_string(),
+        indent: line[..line.len() - trimmed.len()].to_string(),
+    })
+}
+
+pub(crate) fn is_header(line: &str) -> bool {
+    line.trim_start().starts_with('[')
+}
+
+pub(crate) fn is_key_header(line: &str) -> bool {
+    is_header(line) && !line.trim_start().starts_with("[[")
+}
+
+pub(crate) fn key_header_name(line: &str) -> &str {
+    line.trim().trim_matches(['[', ']'])
+}
+
+/// Key of twine file content with its unparsed entries
+pub(crate) struct RawKey {
+    pub(crate) name: String,
+    /// 1-based number of the key header line
+    pub(crate) line_number: usize,
+    pub(crate) entries: Vec<Entry>,
+}
+
+/// `[[name]]` section of twine file content
+pub(crate) struct RawSection {
+    pub(crate) name: String,
+    pub(crate) keys: Vec<RawKey>,
+}
+
+/// Reads keys and their entries as they are written in twine file content, keys
+/// before the first `[[section]]` header go to a section with empty name
+pub(crate) fn raw_sections(content: &str) -> Vec<RawSection> {
+    let mut result: Vec<RawSection> = Vec::new();
+    for (line_index, line) in content.lines().enumerate() {
+        if is_key_header(line) {
+            if result.is_empty() {
+                result.push(RawSection {
+                    name: String::new(),
+                    keys: Vec::new(),
+                });
+            }
+            let keys = &mut result.last_mut().unwrap().keys;
+            keys.push(RawKey {
+                name: key_header_name(line).to_string(),
+                line_number: line_index + 1,
+                entries: Vec::new(),
+            });
+        } else if is_header(line) {
+            result.push(RawSection {
+                name: key_header_name(line).to_string(),
+                keys: Vec::new(),
+            });
+        } else if let Some(entry) = parse_entry(line_index, line) {
+            let key = result
+                .last_mut()
+                .and_then(|section| section.keys.last_mut());
+            if let Some(key) = key {
+                key.entries.push(entry);
+            }
+        }
+    }
+    result
+}
+
+const KEY_INDENT: &str = "  ";
+const ENTRY_INDENT: &str = "    ";
+
+/// Lines of the key header and its entries with canonical indentation
+pub(crate) fn format_raw_key(key: &RawKey) -> Vec<String> {
+    let mut lines = vec![format!("{}[{}]", KEY_INDENT, key.name)];
+    lines.extend(
+        key.entries
+            .iter()
+            .map(|entry| format!("{}{} = {}", ENTRY_INDENT, entry.name, entry.value)),
+    );
+    lines
+}
+
+/// Lines of a new key with `(entry name, value)` entries and canonical indentation
+pub(crate) fn format_key(name: &str, entries: &[(String, String)]) -> Vec<String> {
+    let mut lines = vec![format!("{}[{}]", KEY_INDENT, name)];
+    lines.extend(entries.iter().map(|(name, value)| match value.is_empty() {
+        true => format!("{}{} =", ENTRY_INDENT, name),
+        false => format!("{}{} = {}", ENTRY_INDENT, name, value),
+    }));
+    lines
+}
+
+/// Lines of a key with comments preceding it and each of its entries
+struct FormattedKey {
+    comments: Vec<String>,
+    name: String,
+    /// (comments, name, value)
+    entries: Vec<(Vec<String>, String, String)>,
+}
+
+struct FormattedSection {
+    comments: Vec<String>,
+    name: Option<String>,
+    keys: Vec<FormattedKey>,
+}
+
+/// Rewrites twine file content with canonical formatting: keys are indented with 2 spaces
+/// and entries with 4, keys are separated by blank lines, localizations are sorted by
+/// language and CLDR plural category and followed by key attributes. Comments are kept
+/// above the element they precede.
+pub fn reformat(content: &str) -> String {
+    let (mut sections, comments) = read_formatted(content);
+    write_formatted(&mut sections, comments)
+}
+
+/// Same as `reformat`, but also sorts keys of each section by name, normalizes language codes
+/// to BCP 47 case and separators, e.g. `zh_hant_tw` to `zh-Hant-TW`, and normalizes values:
+/// numeric references to XML special characters become named entities, e.g. `&#38;` becomes
+/// `&amp;`, and values with several placeholders without positions get them numbered.
+pub fn canonicalize(content: &str) -> String {
+    let (mut sections, comments) = read_formatted(content);
+    for section in &mut sections {
+        section
+            .keys
+            .sort_by(|left, right| left.name.cmp(&right.name));
+        for key in &mut section.keys {
+            let is_array = key.entries.iter().any(|(_, name, value)| {
+                name == TYPE_ATTRIBUTE && value.trim() == STRING_ARRAY_TYPE
+            });
+            for (_, name, value) in &mut key.entries {
+                if KEY_ATTRIBUTES.contains(&name.as_str()) {
+                    continue;
+                }
+                *name = match name.split_once(':') {
+                    Some((lang, quantity)) => {
+                        format!("{}:{}", normalize_language_code(lang), quantity)
+                    }
+                    None => normalize_language_code(name),
+                };
+                // Placeholders of array items are numbered per item
+                *value = if is_array {
+                    let items: Vec<String> =
+                        value.split(ARRAY_DELIMITER).map(normalize_value).collect();
+                    items.join(&format!(" {} ", ARRAY_DELIMITER))
+                } else {
+                    normalize_value(value)
+                };
+            }
+        }
+    }
+    write_formatted(&mut sections, comments)
+}
+
+/// Sections of twine file content and trailing comments which don't precede any element
+fn read_formatted(content: &str) -> (Vec<FormattedSection>, Vec<String>) {
+    let mut sections: Vec<FormattedSection> = Vec::new();
+    let mut comments: Vec<String> = Vec::new();
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if is_key_header(line) {
+            if sections.is_empty() {
+                sections.push(FormattedSection {
+                    comments: Vec::new(),
+                    name: None,
+                    keys: Vec::new(),
+                });
+            }
+            sections.last_mut().unwrap().keys.push(FormattedKey {
+                comments: std::mem::take(&mut comments),
+                name: key_header_name(line).to_string(),
+                entries: Vec::new(),
+            });
+            continue;
+        }
+        if is_header(line) {
+            sections.push(FormattedSection {
+                comments: std::mem::take(&mut comments),
+                name: Some(key_header_name(line).to_string()),
+                keys: Vec::new(),
+            });
+            continue;
+        }
+        let key = sections
+            .last_mut()
+            .and_then(|section| section.keys.last_mut());
+        match (key, parse_entry(0, line)) {
+            (Some(key), Some(entry)) => {
+                key.entries
+                    .push((std::mem::take(&mut comments), entry.name, entry.value));
+            }
+            // Comments and lines which don't belong to any key
+            _ => comments.push(trimmed.to_string()),
+        }
+    }
+    (sections, comments)
+}
+
+fn write_formatted(sections: &mut [FormattedSection], comments: Vec<String>) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    for section in sections {
+        if let Some(name) = &section.name {
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.append(&mut section.comments);
+            lines.push(format!("[[{}]]", name));
+        }
+        for (index, key) in section.keys.iter_mut().enumerate() {
+            if index > 0 || (section.name.is_none() && !lines.is_empty()) {
+                lines.push(String::new());
+            }
+            lines.extend(
+                key.comments
+                    .iter()
+                    .map(|comment| format!("{}{}", KEY_INDENT, comment)),
+            );
+            lines.push(format!("{}[{}]", KEY_INDENT, key.name));
+            key.entries
+                .sort_by(|(_, left, _), (_, right, _)| entry_order(left).cmp(&entry_order(right)));
+            for (comments, name, value) in &key.entries {
+                lines.extend(
+                    comments
+                        .iter()
+                        .map(|comment| format!("{}{}", ENTRY_INDENT, comment)),
+                );
+                lines.push(format!("{}{} = {}", ENTRY_INDENT, name, value));
+            }
+        }
+    }
+    if !comments.is_empty() {
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.extend(comments);
+    }
+
+    let mut output = lines.join("\n");
+    if !output.is_empty() {
+        output.push('\n');
+    }
+    output
+}
+
+/// Lowercase language, titlecase script and uppercase region separated by `-`, e.g. `en-US`
+fn normalize_language_code(code: &str) -> String {
+    code.split(['-', '_'])
+        .enumerate()
+        .map(|(index, subtag)| {
+            let is_alphabetic = subtag.chars().all(|char| char.is_ascii_alphabetic());
+            match subtag.len() {
+                _ if index == 0 => subtag.to_lowercase(),
+                4 if is_alphabetic => {
+                    let (first, rest) = subtag.split_at(1);
+                    format!("{}{}", first.to_uppercase(), rest.to_lowercase())
+                }
+                2 if is_alphabetic => subtag.to_uppercase(),
+                _ => subtag.to_lowercase(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("-")
+}
+
+fn normalize_value(value: &str) -> String {
+    lazy_static! {
+        static ref XML_CHAR_REFERENCE_RE: Regex =
+            Regex::new(r"&#(38|60|62|34|39|[xX]26|[xX]3[cCeE]|[xX]22|[xX]27);").unwrap();
+        static ref PLACEHOLDER_RE: Regex = Regex::new(PLACEHOLDER_REGEX).unwrap();
+    }
+    let value = XML_CHAR_REFERENCE_RE.replace_all(value.trim(), |caps: &Captures| {
+        let entity = match caps[1].to_lowercase().as_str() {
+            "38" | "x26" => "&amp;",
+            "60" | "x3c" => "&lt;",
+            "62" | "x3e" => "&gt;",
+            "34" | "x22" => "&quot;",
+            _ => "&apos;",
+        };
+        entity.to_string()
+    });
+    // Positions aren't added to values which already have some, e.g. `%1$s of %d`
+    let has_positions = PLACEHOLDER_RE
+        .captures_iter(&value)
+        .any(|caps| caps.get(1).is_some());
+    if has_positions {
+        return value.into_owned();
+    }
+    maybe_add_positional_numbers(&value).into_owned()
+}
+
+/// Localizations go first by language, singles before plurals which are sorted by
+/// CLDR category, then key attributes in their declaration order
+fn entry_order(name: &str) -> (usize, &str, usize, &str) {
+    if let Some(index) = KEY_ATTRIBUTES
+        .iter()
+        .position(|attribute| *attribute == name)
+    {
+        return (index + 1, "", 0, "");
+    }
+    match name.split_once(':') {
+        Some((lang, quantity)) => {
+            // Unknown quantities go after CLDR categories
+            let category_order = CldrCategory::from_quantity(quantity)
+                .map(|category| category as usize + 1)
+                .unwrap_or(usize::MAX);
+            (0, lang, category_order, quantity)
+        }
+        None => (0, name, 0, ""),
+    }
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn sets_localizations_of_existing_keys() {
+    let content = "[[Section]]
+  [login]
+    en = Login
+    de = Anmelden alt
+
+  [days]
+    en:one = %d day
+    en:other = %d days
+";
+    let mut values = IndexMap::new();
+    values.insert(
+        "login".to_string(),
+        vec![("de".to_string(), "Anmelden".to_string())],
+    );
+    values.insert(
+        "days".to_string(),
+        vec![
+            ("de:one".to_string(), "%d Tag".to_string()),
+            ("de:other".to_string(), "%d Tage".to_string()),
+        ],
+    );
+    values.insert(
+        "unknown".to_string(),
+        vec![("de".to_string(), "Unbekannt".to_string())],
+    );
+
+    let (actual, missing) = set_localizations(content, &values);
+    let expected = "[[Section]]
+  [login]
+    en = Login
+    de = Anmelden
+
+  [days]
+    en:one = %d day
+    en:other = %d days
+    de:one = %d Tag
+    de:other = %d Tage
+";
+    assert_eq!(expected, actual);
+    assert_eq!(missing, vec!["unknown".to_string()]);
+}
+
+#[test]
+fn reads_raw_sections() {
+    let content = "[[Auth]]
+  [login]
+    en = Login
+    # comment line
+    ru = Вход
+[[Settings]]
+  [title]
+    en = Settings
+";
+    let sections = raw_sections(content);
+
+    assert_eq!(sections.len(), 2);
+    assert_eq!(sections[0].name, "Auth");
+    assert_eq!(sections[0].keys[0].name, "login");
+    assert_eq!(sections[0].keys[0].line_number, 2);
+    let names: Vec<_> = sections[0].keys[0]
+        .entries
+        .iter()
+        .map(|entry| entry.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["en", "ru"]);
+    assert_eq!(sections[1].name, "Settings");
+    assert_eq!(sections[1].keys[0].entries[0].value, "Settings");
+}
+
+#[test]
+fn reformats_to_canonical_form() {
+    let content = "# Auth strings
+[[Auth]]
+[login]
+  ru = Вход
+        en = Login
+  comment = Button title
+
+
+    [days]
+  # Russian plurals
+  ru:many = %d дней
+  ru:one = %d день
+  en:other = %d days
+  en:one = %d day
+[[Settings]]
+  [title]
+    en = Settings
+";
+    let expected = "# Auth strings
+[[Auth]]
+  [login]
+    en = Login
+    ru = Вход
+    comment = Button title
+
+  [days]
+    en:one = %d day
+    en:other = %d days
+    ru:one = %d день
+    # Russian plurals
+    ru:many = %d дней
+
+[[Settings]]
+  [title]
+    en = Settings
+";
+    let actual = reformat(content);
+    assert_eq!(expected, actual);
+    assert_eq!(expected, reformat(&actual));
+}
+
+#[test]
+fn canonicalizes_keys_language_codes_and_values() {
+    let content = "[[Auth]]
+  [title]
+    EN_us = Sign in &#38; go
+  [days]
+    zh_hant:other = %d 天 %s
+    en:one =  %d day of %s
+    comment = Days &#60;count
+";
+    let expected = "[[Auth]]
+  [days]
+    en:one = %1$d day of %2$s
+    zh-Hant:other = %1$d 天 %2$s
+    comment = Days &#60;count
+
+  [title]
+    en-US = Sign in &amp; go
+";
+    let actual = canonicalize(content);
+    assert_eq!(expected, actual);
+    assert_eq!(expected, canonicalize(&actual));
+}
@@ -0,0 +1,235 @@
+use serde_json::{Map, Value};
+use std::path::Path;
+
+use crate::gen_error::GenError;
+use crate::parse::{array_text, File, PluralValue, StringValue};
+#[cfg(test)]
+use crate::parse::Section;
+use crate::platform::{placeholders, replace_placeholders, unescape_android};
+use crate::sink::WriteSink;
+#[cfg(test)]
+use crate::test_tools::key;
+
+/// Format of Unity Localization string table
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum TableFormat {
+    /// `Strings.csv` with `Key,Type,Flavor,<lang1>,<lang2>,…` columns
+    Csv,
+    /// `Strings.json` array of objects with the same fields as CSV columns
+    Json,
+}
+
+/// Row of string table
+#[derive(PartialEq, Eq, Debug)]
+struct Entry {
+    key: String,
+    /// `Text` or `Plural`
+    entry_type: &'static str,
+    /// `Smart` if the value uses Smart String syntax and must be marked as Smart in Unity
+    flavor: &'static str,
+    /// Values in the order of table languages
+    values: Vec<String>,
+}
+
+/// Writes string table of all keys into `output_dir`. Printf placeholders become Smart String
+/// placeholders, e.g. `%1$s` becomes `{0}`, and plurals become `{0:plural:…|…}` with forms
+/// in CLDR order.
+pub fn write(
+    file: &File,
+    output_dir: &Path,
+    format: TableFormat,
+    sink: &mut dyn WriteSink,
+) -> Result<(), GenError> {
+    let (langs, entries) = generate(file)?;
+    let (file_name, content) = match format {
+        TableFormat::Csv => ("Strings.csv", to_csv(&langs, &entries)),
+        TableFormat::Json => ("Strings.json", to_json(&langs, &entries)),
+    };
+    sink.write(&output_dir.join(file_name), content.as_bytes())?;
+    Ok(())
+}
+
+/// Returns languages in order of their first appearance and entries of all keys
+fn generate(file: &File) -> Result<(Vec<String>, Vec<Entry>), GenError> {
+    if file.sections.is_empty() {
+        return Err(GenError::EmptyFile);
+    }
+    let keys: Vec<_> = file
+        .sections
+        .iter()
+        .flat_map(|section| &section.keys)
+        .collect();
+    let mut langs: Vec<String> = Vec::new();
+    for str in keys.iter().flat_map(|key| &key.localizations) {
+        if !langs.contains(&str.language_code) {
+            langs.push(str.language_code.clone());
+        }
+    }
+
+    let mut entries = Vec::with_capacity(keys.len());
+    for key in keys {
+        let is_plural = key
+            .localizations
+            .iter()
+            .any(|str| matches!(str.value, StringValue::Plural { .. }));
+        let mut is_smart = is_plural;
+        let values = langs
+            .iter()
+            .map(|lang| {
+                let value = key
+                    .localizations
+                    .iter()
+                    .find(|str| &str.language_code == lang)
+                    .map(|str| &str.value);
+                match value {
+                    None => String::new(),
+                    Some(StringValue::Single(text)) => {
+                        is_smart |= placeholders(text).next().is_some();
+                        to_smart_text(text, false)
+                    }
+                    Some(StringValue::Plural { quantities }) => to_smart_plural(quantities),
+                    Some(StringValue::Array(items)) => {
+                        let text = array_text(items);
+                        is_smart |= placeholders(&text).next().is_some();
+                        to_smart_text(&text, false)
+                    }
+                }
+            })
+            .collect();
+        entries.push(Entry {
+            key: key.name.clone(),
+            entry_type: if is_plural { "Plural" } else { "Text" },
+            flavor: if is_smart { "Smart" } else { "" },
+            values,
+        });
+    }
+    Ok((langs, entries))
+}
+
+/// Replaces printf placeholders with zero based Smart String ones. Inside of plural forms
+/// the first placeholder is the count which is the current value `{}` of the plural scope.
+fn to_smart_text(text: &str, is_plural_form: bool) -> String {
+    let text = unescape_android(text);
+    let mut index = 0;
+    replace_placeholders(&text, |caps| {
+        index += 1;
+        let number: usize = caps
+            .get(1)
+            .and_then(|position| position.as_str().trim_end_matches('$').parse().ok())
+            .unwrap_or(index);
+        if is_plural_form && number == 1 {
+            "{}".to_string()
+        } else {
+            format!("{{{}}}", number - 1)
+        }
+    })
+    .replace("%%", "%")
+}
+
+fn to_smart_plural(quantities: &[PluralValue]) -> String {
+    let mut items: Vec<&PluralValue> = quantities.iter().collect();
+    items.sort_by_key(|item| item.quantity_as_cldr_category());
+    let forms: Vec<String> = items
+        .iter()
+        .map(|item| to_smart_text(&item.text, true))
+        .collect();
+    format!("{{0:plural:{}}}", forms.join("|"))
+}
+
+fn to_csv(langs: &[String], entries: &[Entry]) -> String {
+    let mut result = String::new();
+    let header: Vec<&str> = ["Key", "Type", "Flavor"]
+        .into_iter()
+        .chain(langs.iter().map(String::as_str))
+        .collect();
+    push_csv_row(&mut result, &header);
+    for entry in entries {
+        let row: Vec<&str> = [entry.key.as_str(), entry.entry_type, entry.flavor]
+            .into_iter()
+            .chain(entry.values.iter().map(String::as_str))
+            .collect();
+        push_csv_row(&mut result, &row);
+    }
+    result
+}
+
+fn push_csv_row(result: &mut String, fields: &[&str]) {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect();
+    result.push_str(&fields.join(","));
+    result.push('\n');
+}
+
+fn to_json(langs: &[String], entries: &[Entry]) -> String {
+    let rows: Vec<Value> = entries
+        .iter()
+        .map(|entry| {
+            let mut row = Map::new();
+            row.insert("Key".to_string(), Value::from(entry.key.as_str()));
+            row.insert("Type".to_string(), Value::from(entry.entry_type));
+            row.insert("Flavor".to_string(), Value::from(entry.flavor));
+            for (lang, value) in langs.iter().zip(&entry.values) {
+                row.insert(lang.clone(), Value::from(value.as_str()));
+            }
+            Value::Object(row)
+        })
+        .collect();
+    // Serializing of serde_json::Value can't fail
+    serde_json::to_string_pretty(&rows).unwrap() + "\n"
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn generate_csv_table() -> Result<(), GenError> {
+    let single = |text: &str| StringValue::Single(text.to_string());
+    let keys = vec![
+        key(
+            "title",
+            vec![("en", single("Hello, \"world\"")), ("ru", single("Привет"))],
+        ),
+        key("greeting", vec![("en", single("Hi %1$s, 100%% %2$d"))]),
+        key(
+            "apples",
+            vec![
+                (
+                    "en",
+                    StringValue::plural()
+                        .quantity("other", "%d apples")
+                        .quantity("one", "%d apple")
+                        .build(),
+                ),
+                (
+                    "ru",
+                    StringValue::plural()
+                        .quantity("one", "%d яблоко")
+                        .quantity("few", "%d яблока")
+                        .quantity("many", "%d яблок")
+                        .build(),
+                ),
+            ],
+        ),
+    ];
+    let source = File {
+        sections: vec![Section { keys }],
+    };
+
+    let (langs, entries) = generate(&source)?;
+    let actual = to_csv(&langs, &entries);
+
+    let expected = "Key,Type,Flavor,en,ru
+title,Text,,\"Hello, \"\"world\"\"\",Привет
+greeting,Text,Smart,\"Hi {0}, 100% {1}\",
+apples,Plural,Smart,{0:plural:{} apple|{} apples},{0:plural:{} яблоко|{} яблока|{} яблок}
+";
+    assert_eq!(expected, actual);
+
+    Ok(())
+}
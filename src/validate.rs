@@ -0,0 +1,653 @@
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::parse::{
+    CldrCategory, File, Key, LocalizedString, StringValue, PLACEHOLDER_OR_PERCENT_RE,
+};
+#[cfg(test)]
+use crate::parse::{PluralValue, Section};
+use crate::platform::unescape_android;
+
+const INTEGER_TYPES: &str = "diuoxX";
+const FLOAT_TYPES: &str = "fFeEgGaA";
+
+/// Lists values longer than `max_length` of their keys, plurals are checked per quantity
+pub fn max_length_violations(file: &File) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    for key in file.sections.iter().flat_map(|section| &section.keys) {
+        let Some(max_length) = key.max_length else {
+            continue;
+        };
+        for localization in &key.localizations {
+            for (name, text) in named_texts(localization) {
+                let length = unescape_android(text).chars().count();
+                if length > max_length {
+                    result.push(format!(
+                        "\"{}\" of key \"{}\" is {} characters long, max_length is {}",
+                        name, key.name, length, max_length
+                    ));
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Lists keys which miss any locale that appears in other keys of the file
+pub fn missing_locale_warnings(file: &File) -> Vec<String> {
+    let mut missing: IndexMap<String, Vec<String>> = IndexMap::new();
+    for item in file.missing_translations() {
+        missing
+            .entry(item.key_name)
+            .or_default()
+            .push(item.missing_lang);
+    }
+    missing
+        .into_iter()
+        .map(|(key, locales)| format!("key \"{}\" is missing locales: {}", key, locales.join(", ")))
+        .collect()
+}
+
+/// Checks that every printf placeholder is valid according to C `printf` rules, i.e. its flag,
+/// precision and length modifier are allowed for the conversion type, and that positional
+/// and non-positional placeholders are not mixed in a value
+pub fn format_string_errors(file: &File) -> Vec<String> {
+    lazy_static! {
+        // `%%` goes first so that escaped percent isn't taken as the start of a placeholder
+        static ref FORMAT_RE: Regex = Regex::new(
+            r"%%|%(\d+\$)?([-+0#,])?(\d+|\*)?(\.(\d+|\*))?(hh?|ll?|L|z|j|t|q)?([diufFeEgGxXoscpaA@])"
+        )
+        .unwrap();
+    }
+    let mut result: Vec<String> = Vec::new();
+    for key in file.sections.iter().flat_map(|section| &section.keys) {
+        for localization in &key.localizations {
+            for (name, text) in named_texts(localization) {
+                let mut positional = (false, false);
+                for caps in FORMAT_RE.captures_iter(text) {
+                    let Some(conversion) = caps.get(7) else {
+                        continue;
+                    };
+                    let conversion = conversion.as_str();
+                    if caps.get(1).is_some() {
+                        positional.0 = true;
+                    } else {
+                        positional.1 = true;
+                    }
+                    let problem = placeholder_problem(
+                        caps.get(2).map(|flag| flag.as_str()),
+                        caps.get(4).is_some(),
+                        caps.get(6).map(|length| length.as_str()),
+                        conversion,
+                    );
+                    if let Some(problem) = problem {
+                        result.push(format!(
+                            "\"{}\" of key \"{}\": \"{}\" {}",
+                            name, key.name, &caps[0], problem
+                        ));
+                    }
+                }
+                if positional == (true, true) {
+                    result.push(format!(
+                        "\"{}\" of key \"{}\" mixes positional and non-positional placeholders",
+                        name, key.name
+                    ));
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Describes why the combination of placeholder parts is invalid
+fn placeholder_problem(
+    flag: Option<&str>,
+    has_precision: bool,
+    length: Option<&str>,
+    conversion: &str,
+) -> Option<String> {
+    let is_integer = INTEGER_TYPES.contains(conversion);
+    let is_float = FLOAT_TYPES.contains(conversion);
+    let flag_allowed = match flag {
+        None | Some("-") => true,
+        Some("#") => "oxX".contains(conversion) || is_float,
+        Some(",") => "diu".contains(conversion) || "fFgG".contains(conversion),
+        Some("+") => "di".contains(conversion) || is_float,
+        // `0` padding
+        Some(_) => is_integer || is_float,
+    };
+    if !flag_allowed {
+        return Some(format!(
+            "has \"{}\" flag which is not allowed for \"{}\"",
+            flag.unwrap_or_default(),
+            conversion
+        ));
+    }
+    if has_precision && "cp@".contains(conversion) {
+        return Some(format!(
+            "has precision which is not allowed for \"{}\"",
+            conversion
+        ));
+    }
+    let length_allowed = match length {
+        None => true,
+        Some("L") => is_float,
+        // `%ls` and `%lc` are wide strings and characters, `l` is ignored for floating point
+        Some("l") => is_integer || is_float || "sc".contains(conversion),
+        Some(_) => is_integer,
+    };
+    if !length_allowed {
+        return Some(format!(
+            "has \"{}\" length modifier which is not allowed for \"{}\"",
+            length.unwrap_or_default(),
+            conversion
+        ));
+    }
+    None
+}
+
+/// Tags which have no content and closing tag
+const VOID_HTML_TAGS: &[&str] = &["br"];
+
+/// Checks HTML markup of values: tags are allowed and properly nested, and `<a>` tags
+/// link to an absolute http(s), mailto or tel URL
+pub fn html_tag_warnings(file: &File, allowed_tags: &[String]) -> Vec<String> {
+    lazy_static! {
+        static ref TAG_RE: Regex =
+            Regex::new(r"<(/?)([a-zA-Z][a-zA-Z0-9]*)([^<>]*?)(/?)>").unwrap();
+        static ref HREF_RE: Regex = Regex::new(r#"\bhref\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+        static ref URL_RE: Regex = Regex::new(r"^(https?://|mailto:|tel:)\S+$").unwrap();
+    }
+    let mut result: Vec<String> = Vec::new();
+    for key in file.sections.iter().flat_map(|section| &section.keys) {
+        for localization in &key.localizations {
+            for (name, text) in named_texts(localization) {
+                let text = unescape_android(text);
+                let mut warn = |message: String| {
+                    result.push(format!("\"{}\" of key \"{}\": {}", name, key.name, message))
+                };
+                let mut open_tags: Vec<String> = Vec::new();
+                for caps in TAG_RE.captures_iter(&text) {
+                    let is_closing = !caps[1].is_empty();
+                    let tag = caps[2].to_lowercase();
+                    if is_closing {
+                        match open_tags.iter().rposition(|open| *open == tag) {
+                            Some(index) if index + 1 == open_tags.len() => {
+                                open_tags.pop();
+                            }
+                            Some(index) => {
+                                warn(format!(
+                                    "</{}> closes <{}> before <{}> is closed",
+                                    tag,
+                                    tag,
+                                    open_tags[open_tags.len() - 1]
+                                ));
+                                open_tags.truncate(index);
+                            }
+                            None => warn(format!("</{}> has no opening tag", tag)),
+                        }
+                        continue;
+                    }
+                    if !allowed_tags.contains(&tag) {
+                        warn(format!("<{}> tag is not allowed", tag));
+                    }
+                    if tag == "a" {
+                        let href = HREF_RE
+                            .captures(&caps[3])
+                            .and_then(|href| href.get(1).or_else(|| href.get(2)));
+                        match href {
+                            None => warn("<a> tag has no href".to_string()),
+                            Some(href) if !URL_RE.is_match(href.as_str()) => {
+                                warn(format!("<a> tag has invalid href \"{}\"", href.as_str()))
+                            }
+                            Some(_) => {}
+                        }
+                    }
+                    let is_self_closing = !caps[4].is_empty();
+                    if !is_self_closing && !VOID_HTML_TAGS.contains(&tag.as_str()) {
+                        open_tags.push(tag);
+                    }
+                }
+                for tag in open_tags {
+                    warn(format!("<{}> is not closed", tag));
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Localization of a key which placeholders differ from the ones of the first localization
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct PlaceholderMismatch {
+    pub key_name: String,
+    pub lang: String,
+    /// Language of the first localization which placeholders are expected
+    pub expected_lang: String,
+    pub expected_count: usize,
+    pub actual_count: usize,
+    /// Conversions of placeholders ordered by position, e.g. `sd` for `%1$s` and `%2$d`
+    pub expected_types: String,
+    pub actual_types: String,
+}
+
+impl fmt::Display for PlaceholderMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "\"{}\" of key \"{}\" has {} placeholders of types \"{}\", \"{}\" has {} of types \"{}\"",
+            self.lang,
+            self.key_name,
+            self.actual_count,
+            self.actual_types,
+            self.expected_lang,
+            self.expected_count,
+            self.expected_types
+        )
+    }
+}
+
+/// Compares placeholders of every localization of the key with the first one, so that
+/// formatting a translation with arguments of the source doesn't crash at runtime.
+/// Placeholders of all quantities of plurals are combined since forms like `one` may
+/// omit the count. `%i` is taken as `%d` and `%@` as `%s`.
+pub fn validate_placeholder_consistency(key: &Key) -> Vec<PlaceholderMismatch> {
+    let Some((first, others)) = key.localizations.split_first() else {
+        return Vec::new();
+    };
+    let expected = placeholder_types(first);
+    others
+        .iter()
+        .filter_map(|localization| {
+            let actual = placeholder_types(localization);
+            (actual != expected).then(|| PlaceholderMismatch {
+                key_name: key.name.clone(),
+                lang: localization.language_code.clone(),
+                expected_lang: first.language_code.clone(),
+                expected_count: expected.len(),
+                actual_count: actual.len(),
+                expected_types: expected.values().collect(),
+                actual_types: actual.values().collect(),
+            })
+        })
+        .collect()
+}
+
+/// Conversions of placeholders keyed by their positions, placeholders without positions
+/// take them in order of appearance
+fn placeholder_types(localization: &LocalizedString) -> BTreeMap<usize, char> {
+    let mut result: BTreeMap<usize, char> = BTreeMap::new();
+    for (_, text) in named_texts(localization) {
+        let mut next = 1;
+        for caps in PLACEHOLDER_OR_PERCENT_RE.captures_iter(text) {
+            if &caps[0] == "%%" {
+                continue;
+            }
+            let position = match caps.get(1) {
+                Some(position) => position.as_str().trim_end_matches('$').parse().unwrap_or(0),
+                None => next,
+            };
+            next = position + 1;
+            let conversion = match caps[0].chars().last().unwrap_or_default() {
+                'i' => 'd',
+                '@' => 's',
+                conversion => conversion,
+            };
+            result.insert(position, conversion);
+        }
+    }
+    result
+}
+
+/// Problem of a localization which doesn't prevent generating resources
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ValidationWarning {
+    pub key: String,
+    pub lang: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "\"{}\" of key \"{}\": {}",
+            self.lang, self.key, self.message
+        )
+    }
+}
+
+/// Checks quantities of the plural of `lang` against CLDR plural categories of the language,
+/// e.g. `few` is not used by English. Languages missing in `CldrCategory::for_language`
+/// are only checked for unknown quantities and the mandatory `other`.
+pub fn validate_plural_quantities(key: &Key, lang: &str) -> Vec<ValidationWarning> {
+    let quantities = key
+        .localizations
+        .iter()
+        .filter(|localization| localization.language_code == lang)
+        .filter_map(|localization| match &localization.value {
+            StringValue::Plural { quantities } => Some(quantities),
+            _ => None,
+        });
+    let categories = CldrCategory::for_language(lang);
+    let mut result: Vec<ValidationWarning> = Vec::new();
+    for quantities in quantities {
+        let mut warn = |message: String| {
+            result.push(ValidationWarning {
+                key: key.name.clone(),
+                lang: lang.to_string(),
+                message,
+            })
+        };
+        for item in quantities {
+            match (item.quantity_as_cldr_category(), categories) {
+                (None, _) => warn(format!(
+                    "\"{}\" is not a CLDR plural category",
+                    item.quantity
+                )),
+                (Some(category), Some(categories)) if !categories.contains(&category) => warn(
+                    format!("\"{}\" is not used by \"{}\" in CLDR", item.quantity, lang),
+                ),
+                _ => {}
+            }
+        }
+        if !quantities.iter().any(|item| item.quantity == "other") {
+            warn("\"other\" quantity is missing".to_string());
+        }
+    }
+    result
+}
+
+/// Texts of the localization named by language code, plurals are named by language and quantity
+/// and arrays by language and index
+fn named_texts(localization: &LocalizedString) -> Vec<(String, &str)> {
+    match &localization.value {
+        StringValue::Single(text) => vec![(localization.language_code.clone(), text)],
+        StringValue::Plural { quantities } => quantities
+            .iter()
+            .map(|item| {
+                (
+                    format!("{}:{}", localization.language_code, item.quantity),
+                    item.text.as_str(),
+                )
+            })
+            .collect(),
+        StringValue::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                (
+                    format!("{}:{}", localization.language_code, index),
+                    item.as_str(),
+                )
+            })
+            .collect(),
+    }
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn finds_values_exceeding_max_length() {
+    let file = File {
+        sections: vec![Section {
+            keys: vec![
+                Key {
+                    name: "login".to_string(),
+                    localizations: vec![
+                        LocalizedString {
+                            language_code: "en".to_string(),
+                            value: StringValue::Single("Don\\'t log in".to_string()),
+                        },
+                        LocalizedString {
+                            language_code: "de".to_string(),
+                            value: StringValue::Plural {
+                                quantities: vec![PluralValue {
+                                    quantity: "other".to_string(),
+                                    text: "Nicht anmelden".to_string(),
+                                }],
+                            },
+                        },
+                    ],
+                    max_length: Some(12),
+                    comment: None,
+                    translatable: true,
+                },
+                Key {
+                    name: "logout".to_string(),
+                    localizations: vec![LocalizedString {
+                        language_code: "en".to_string(),
+                        value: StringValue::Single("Log out of the account".to_string()),
+                    }],
+                    max_length: None,
+                    comment: None,
+                    translatable: true,
+                },
+            ],
+        }],
+    };
+
+    assert_eq!(
+        max_length_violations(&file),
+        vec!["\"de:other\" of key \"login\" is 14 characters long, max_length is 12".to_string()]
+    );
+}
+
+#[test]
+fn finds_keys_missing_locales() {
+    let key = |name: &str, langs: &[&str]| Key {
+        name: name.to_string(),
+        localizations: langs
+            .iter()
+            .map(|lang| LocalizedString {
+                language_code: lang.to_string(),
+                value: StringValue::Single("text".to_string()),
+            })
+            .collect(),
+        max_length: None,
+        comment: None,
+        translatable: true,
+    };
+    let file = File {
+        sections: vec![
+            Section {
+                keys: vec![key("login", &["en", "de"]), key("logout", &["en", "fr"])],
+            },
+            Section {
+                keys: vec![key("cancel", &["en", "de", "fr"])],
+            },
+        ],
+    };
+
+    assert_eq!(
+        missing_locale_warnings(&file),
+        vec![
+            "key \"login\" is missing locales: fr".to_string(),
+            "key \"logout\" is missing locales: de".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn finds_plural_quantities_not_in_cldr() {
+    let key = Key {
+        name: "apples".to_string(),
+        localizations: vec![
+            LocalizedString {
+                language_code: "en".to_string(),
+                value: StringValue::plural()
+                    .quantity("zero", "No apples")
+                    .quantity("one", "%d apple")
+                    .quantity("few", "%d apples")
+                    .build(),
+            },
+            LocalizedString {
+                language_code: "ru".to_string(),
+                value: StringValue::plural()
+                    .quantity("one", "%d яблоко")
+                    .quantity("few", "%d яблока")
+                    .quantity("many", "%d яблок")
+                    .quantity("other", "%d яблока")
+                    .build(),
+            },
+        ],
+        max_length: None,
+        comment: None,
+        translatable: true,
+    };
+
+    let messages: Vec<String> = validate_plural_quantities(&key, "en")
+        .iter()
+        .map(|warning| warning.to_string())
+        .collect();
+    assert_eq!(
+        messages,
+        vec![
+            "\"en\" of key \"apples\": \"zero\" is not used by \"en\" in CLDR".to_string(),
+            "\"en\" of key \"apples\": \"few\" is not used by \"en\" in CLDR".to_string(),
+            "\"en\" of key \"apples\": \"other\" quantity is missing".to_string(),
+        ]
+    );
+    assert!(validate_plural_quantities(&key, "ru").is_empty());
+}
+
+#[test]
+fn finds_placeholder_mismatches() {
+    let single = |language_code: &str, text: &str| LocalizedString {
+        language_code: language_code.to_string(),
+        value: StringValue::Single(text.to_string()),
+    };
+    let key = Key {
+        name: "greeting".to_string(),
+        localizations: vec![
+            single("en", "Hello %s, you have %d messages"),
+            single("de", "%2$d Nachrichten für %1$s"),
+            single("fr", "Bonjour %@, %i messages, 100%%"),
+            single("ru", "Привет %s"),
+            single("es", "Hola %d, %d mensajes"),
+        ],
+        max_length: None,
+        comment: None,
+        translatable: true,
+    };
+
+    let mismatches = validate_placeholder_consistency(&key);
+    assert_eq!(
+        mismatches
+            .iter()
+            .map(|mismatch| mismatch.to_string())
+            .collect::<Vec<_>>(),
+        vec![
+            "\"ru\" of key \"greeting\" has 1 placeholders of types \"s\", \"en\" has 2 of types \"sd\"",
+            "\"es\" of key \"greeting\" has 2 placeholders of types \"dd\", \"en\" has 2 of types \"sd\"",
+        ]
+    );
+    assert_eq!(mismatches[0].expected_count, 2);
+    assert_eq!(mismatches[0].actual_count, 1);
+
+    let plural = Key {
+        name: "days".to_string(),
+        localizations: vec![
+            LocalizedString {
+                language_code: "en".to_string(),
+                value: StringValue::plural()
+                    .quantity("one", "One day")
+                    .quantity("other", "%d days")
+                    .build(),
+            },
+            single("ja", "%d日"),
+        ],
+        max_length: None,
+        comment: None,
+        translatable: true,
+    };
+    assert!(validate_placeholder_consistency(&plural).is_empty());
+}
+
+#[test]
+fn finds_invalid_format_strings() {
+    let single = |language_code: &str, text: &str| LocalizedString {
+        language_code: language_code.to_string(),
+        value: StringValue::Single(text.to_string()),
+    };
+    let file = File {
+        sections: vec![Section {
+            keys: vec![
+                Key {
+                    name: "valid".to_string(),
+                    localizations: vec![single(
+                        "en",
+                        "%lld of %,d %#x %08.3f %-5s %ls %Lf 100%%d %.2d",
+                    )],
+                    max_length: None,
+                    comment: None,
+                    translatable: true,
+                },
+                Key {
+                    name: "invalid".to_string(),
+                    localizations: vec![
+                        single("en", "%#s %,o %0s %.2c %Lf %hf %+u"),
+                        single("ru", "%1$s и %d"),
+                    ],
+                    max_length: None,
+                    comment: None,
+                    translatable: true,
+                },
+            ],
+        }],
+    };
+
+    assert_eq!(
+        format_string_errors(&file),
+        vec![
+            "\"en\" of key \"invalid\": \"%#s\" has \"#\" flag which is not allowed for \"s\"",
+            "\"en\" of key \"invalid\": \"%,o\" has \",\" flag which is not allowed for \"o\"",
+            "\"en\" of key \"invalid\": \"%0s\" has \"0\" flag which is not allowed for \"s\"",
+            "\"en\" of key \"invalid\": \"%.2c\" has precision which is not allowed for \"c\"",
+            "\"en\" of key \"invalid\": \"%hf\" has \"h\" length modifier which is not allowed for \"f\"",
+            "\"en\" of key \"invalid\": \"%+u\" has \"+\" flag which is not allowed for \"u\"",
+            "\"ru\" of key \"invalid\" mixes positional and non-positional placeholders",
+        ]
+    );
+}
+
+#[test]
+fn finds_malformed_html_tags() {
+    let single = |language_code: &str, text: &str| LocalizedString {
+        language_code: language_code.to_string(),
+        value: StringValue::Single(text.to_string()),
+    };
+    let file = File {
+        sections: vec![Section {
+            keys: vec![Key {
+                name: "terms".to_string(),
+                localizations: vec![
+                    single(
+                        "en",
+                        "<b>Bold</b>, <i>italic</i><br>and <a href=\\\"https://example.com\\\">link</a>",
+                    ),
+                    single("de", "<b><i>Fett</b></i> <u>offen"),
+                    single("ru", "<a href='javascript:alert()'>ссылка</a> <a>без</a> <font>шрифт</font>"),
+                ],
+                max_length: None,
+                comment: None,
+                translatable: true,
+            }],
+        }],
+    };
+    let allowed: Vec<String> = ["b", "i", "u", "br", "a"].map(String::from).to_vec();
+
+    assert_eq!(
+        html_tag_warnings(&file, &allowed),
+        vec![
+            "\"de\" of key \"terms\": </b> closes <b> before <i> is closed",
+            "\"de\" of key \"terms\": </i> has no opening tag",
+            "\"de\" of key \"terms\": <u> is not closed",
+            "\"ru\" of key \"terms\": <a> tag has invalid href \"javascript:alert()\"",
+            "\"ru\" of key \"terms\": <a> tag has no href",
+            "\"ru\" of key \"terms\": <font> tag is not allowed",
+        ]
+    );
+}
@@ -0,0 +1,38 @@
+use notify::{RecursiveMode, Watcher};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// How often `watch_dir` checks whether it has to stop while there are no events
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Calls `on_change` whenever files of `dir` are modified, events which come less than
+/// `debounce` apart are handled once. Returns when `stop` is set, e.g. by a Ctrl-C handler.
+pub fn watch_dir(
+    dir: &Path,
+    debounce: Duration,
+    stop: &AtomicBool,
+    mut on_change: impl FnMut(),
+) -> notify::Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+    while !stop.load(Ordering::SeqCst) {
+        let event = match receiver.recv_timeout(STOP_CHECK_INTERVAL) {
+            Ok(event) => event?,
+            Err(mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        };
+        if event.kind.is_access() {
+            continue;
+        }
+        // Editors often write files in several steps, e.g. truncate and write
+        while receiver.recv_timeout(debounce).is_ok() {}
+        if !stop.load(Ordering::SeqCst) {
+            on_change();
+        }
+    }
+    Ok(())
+}
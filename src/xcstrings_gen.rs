@@ -0,0 +1,289 @@
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+use crate::gen_error::GenError;
+use crate::parse::{array_text, File, PluralValue, StringValue};
+#[cfg(test)]
+use crate::parse::Section;
+use crate::platform::to_ios_text;
+use crate::sink::WriteSink;
+#[cfg(test)]
+use crate::test_tools::{key, plain_str, plurals};
+
+const DEFAULT_SOURCE_LANGUAGE: &str = "en";
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Localization {
+    lang: String,
+    value: StringValue,
+}
+
+/// Localizations of all keys from all source files, key names are unique
+pub struct GenResult {
+    value: IndexMap<String, Vec<Localization>>,
+    /// Comments of keys which have one, the first comment is taken if a key is in several files
+    comments: IndexMap<String, String>,
+}
+
+impl GenResult {
+    /// Writes all languages into a single Localizable.xcstrings string catalog
+    pub fn write(
+        &self,
+        dir: impl AsRef<Path>,
+        default_lang: &Option<String>,
+        sink: &mut dyn WriteSink,
+    ) -> Result<(), GenError> {
+        let source_language = default_lang.as_deref().unwrap_or(DEFAULT_SOURCE_LANGUAGE);
+        let path = dir.as_ref().join("Localizable.xcstrings");
+        self.write_to_path(&path, source_language, sink)
+    }
+
+    fn write_to_path(
+        &self,
+        path: &Path,
+        source_language: &str,
+        sink: &mut dyn WriteSink,
+    ) -> Result<(), GenError> {
+        let content = self.to_json(source_language) + "\n";
+        sink.write(path, content.as_bytes())?;
+        Ok(())
+    }
+
+    fn to_json(&self, source_language: &str) -> String {
+        let mut names: Vec<&String> = self.value.keys().collect();
+        names.sort();
+
+        let mut strings = Map::new();
+        for name in names {
+            let mut localizations: Vec<&Localization> = self.value[name].iter().collect();
+            localizations.sort_by(|left, right| left.lang.cmp(&right.lang));
+
+            let mut localizations_json = Map::new();
+            for localization in localizations {
+                localizations_json.insert(
+                    localization.lang.clone(),
+                    generate_localization_value(&localization.value),
+                );
+            }
+            let mut entry = json!({ "localizations": localizations_json });
+            if let Some(comment) = self.comments.get(name) {
+                entry["comment"] = json!(comment);
+            }
+            strings.insert(name.clone(), entry);
+        }
+
+        let catalog = json!({
+            "sourceLanguage": source_language,
+            "strings": strings,
+            "version": "1.0",
+        });
+        // Serializing of serde_json::Value can't fail
+        serde_json::to_string_pretty(&catalog).unwrap()
+    }
+}
+
+/// Writes all languages of the file into a single string catalog at `output_path`,
+/// e.g. `Localizable.xcstrings`. English is taken as the source language.
+pub fn write_xcstrings(
+    file: &File,
+    output_path: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<(), GenError> {
+    generate_for_files(&[file])?.write_to_path(output_path, DEFAULT_SOURCE_LANGUAGE, sink)
+}
+
+pub fn generate(sources: Vec<File>) -> Result<GenResult, GenError> {
+    generate_for_files(&sources.iter().collect::<Vec<_>>())
+}
+
+fn generate_for_files(sources: &[&File]) -> Result<GenResult, GenError> {
+    if sources.is_empty() {
+        return Err(GenError::EmptyFile);
+    }
+
+    let mut result: IndexMap<String, Vec<Localization>> = IndexMap::new();
+    let mut comments: IndexMap<String, String> = IndexMap::new();
+    for source in sources {
+        if source.sections.is_empty() {
+            return Err(GenError::EmptyFile);
+        }
+        for key in source.sections.iter().flat_map(|section| &section.keys) {
+            if let Some(comment) = &key.comment {
+                comments
+                    .entry(key.name.clone())
+                    .or_insert_with(|| comment.clone());
+            }
+            let current = result.entry(key.name.clone()).or_default();
+            for str in &key.localizations {
+                if let StringValue::Plural { quantities } = &str.value {
+                    if let Some(item) = quantities
+                        .iter()
+                        .find(|item| item.quantity_as_cldr_category().is_none())
+                    {
+                        return Err(GenError::UnsupportedPluralCategory(item.quantity.clone()));
+                    }
+                }
+                current.push(Localization {
+                    lang: str.language_code.clone(),
+                    value: str.value.clone(),
+                })
+            }
+        }
+    }
+
+    Ok(GenResult {
+        value: result,
+        comments,
+    })
+}
+
+fn generate_localization_value(value: &StringValue) -> Value {
+    match value {
+        StringValue::Single(text) => generate_string_unit(text),
+        StringValue::Array(items) => generate_string_unit(&array_text(items)),
+        StringValue::Plural { quantities } => {
+            let mut items: Vec<&PluralValue> = quantities.iter().collect();
+            items.sort_by_key(|item| item.quantity_as_cldr_category());
+            let mut plural = Map::new();
+            for item in items {
+                plural.insert(item.quantity.clone(), generate_string_unit(&item.text));
+            }
+            json!({ "variations": { "plural": plural } })
+        }
+    }
+}
+
+fn generate_string_unit(text: &str) -> Value {
+    json!({
+        "stringUnit": {
+            "state": "translated",
+            "value": to_ios_text(text),
+        }
+    })
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn generate_keys_from_2_files() -> Result<()> {
+    let first = File {
+        sections: vec![Section {
+            keys: vec![key(
+                "kek",
+                vec![plain_str("ru", "Кек"), plain_str("en", "Kek")],
+            )],
+        }],
+    };
+    let second = File {
+        sections: vec![Section {
+            keys: vec![key("lil", vec![plain_str("ru", "Лил")])],
+        }],
+    };
+
+    let actual = generate(vec![first, second])?;
+    assert_eq!(actual.value.keys().collect::<Vec<_>>(), vec!["kek", "lil"]);
+    assert_eq!(actual.value["kek"].len(), 2);
+    assert_eq!(actual.value["lil"].len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn generate_json_with_single_and_plural_values() -> Result<()> {
+    let keys = vec![
+        key("chicken", vec![plain_str("en", "Chicken &amp; %1$s")]),
+        key(
+            "cows",
+            vec![plurals(
+                "en",
+                vec![PluralValue::new("other", "%d cows"), PluralValue::new("one", "%d cow")],
+            )],
+        ),
+    ];
+    let source = File {
+        sections: vec![Section { keys }],
+    };
+
+    let actual = generate(vec![source])?.to_json("en");
+    let expected = r#"{
+  "sourceLanguage": "en",
+  "strings": {
+    "chicken": {
+      "localizations": {
+        "en": {
+          "stringUnit": {
+            "state": "translated",
+            "value": "Chicken & %1$@"
+          }
+        }
+      }
+    },
+    "cows": {
+      "localizations": {
+        "en": {
+          "variations": {
+            "plural": {
+              "one": {
+                "stringUnit": {
+                  "state": "translated",
+                  "value": "%d cow"
+                }
+              },
+              "other": {
+                "stringUnit": {
+                  "state": "translated",
+                  "value": "%d cows"
+                }
+              }
+            }
+          }
+        }
+      }
+    }
+  },
+  "version": "1.0"
+}"#;
+    assert_eq!(expected, actual);
+
+    Ok(())
+}
+
+#[test]
+fn generate_error_if_empty_sections() -> Result<()> {
+    let source = File { sections: vec![] };
+
+    let actual = generate(vec![source]);
+    assert!(actual.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn write_catalog_with_comments() -> Result<()> {
+    let mut chicken = key(
+        "chicken",
+        vec![plain_str("en", "Chicken"), plain_str("ru", "Курица")],
+    );
+    chicken.comment = Some("Name of the bird".to_string());
+    let source = File {
+        sections: vec![Section {
+            keys: vec![chicken, key("cow", vec![plain_str("en", "Cow")])],
+        }],
+    };
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("Localizable.xcstrings");
+
+    write_xcstrings(&source, &path, &mut crate::sink::FsSink::default())?;
+
+    let actual: Value = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+    assert_eq!(actual["sourceLanguage"], "en");
+    assert_eq!(actual["strings"]["chicken"]["comment"], "Name of the bird");
+    assert_eq!(
+        actual["strings"]["chicken"]["localizations"]["ru"]["stringUnit"]["value"],
+        "Курица"
+    );
+    assert!(actual["strings"]["cow"].get("comment").is_none());
+
+    Ok(())
+}
@@ -0,0 +1,312 @@
+use quick_xml::escape::escape;
+use std::io::Write;
+use std::path::Path;
+
+use crate::gen_error::GenError;
+use crate::parse::{array_text, CldrCategory, File, Key, PluralValue, StringValue};
+#[cfg(test)]
+use crate::parse::Section;
+use crate::platform::unescape_android;
+use crate::sink::WriteSink;
+#[cfg(test)]
+use crate::test_tools::key;
+
+const XLIFF_2_0_NAMESPACE: &str = "urn:oasis:names:tc:xliff:document:2.0";
+
+/// Writes XLIFF 2.0 document translating `source_lang` into `target_lang` to `output_path`
+pub fn write_xliff_2_0(
+    file: &File,
+    source_lang: &str,
+    target_lang: &str,
+    output_path: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<(), GenError> {
+    let mut buffer: Vec<u8> = Vec::new();
+    generate_xliff_2_0_to_writer(file, source_lang, target_lang, &mut buffer)?;
+    sink.write(output_path, &buffer)?;
+    Ok(())
+}
+
+/// Writes `<target_lang>.xliff` in XLIFF 2.0 into `output_dir` for every language of the file
+/// except `source_lang`
+pub fn write_xliff_2_0_all(
+    file: &File,
+    source_lang: &str,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<(), GenError> {
+    let mut target_langs: Vec<&str> = Vec::new();
+    for str in file
+        .sections
+        .iter()
+        .flat_map(|section| &section.keys)
+        .flat_map(|key| &key.localizations)
+    {
+        if str.language_code != source_lang && !target_langs.contains(&str.language_code.as_str()) {
+            target_langs.push(&str.language_code);
+        }
+    }
+    for target_lang in target_langs {
+        let output_path = output_dir.join(format!("{}.xliff", target_lang));
+        write_xliff_2_0(file, source_lang, target_lang, &output_path, sink)?;
+    }
+    Ok(())
+}
+
+/// Generates XLIFF 2.0 document and streams it into the writer. Every key with a value in
+/// `source_lang` becomes a `<unit>` with a single `<segment>`. XLIFF 2.0 has no notion of
+/// plurals, so plural keys become a `<group>` of units per quantity, the quantity is kept
+/// in `name` of the unit.
+pub fn generate_xliff_2_0_to_writer<W: Write>(
+    file: &File,
+    source_lang: &str,
+    target_lang: &str,
+    writer: &mut W,
+) -> Result<(), GenError> {
+    if file.sections.is_empty() {
+        return Err(GenError::EmptyFile);
+    }
+    let keys: Vec<&Key> = file
+        .sections
+        .iter()
+        .flat_map(|section| &section.keys)
+        .filter(|key| value_of(key, source_lang).is_some())
+        .collect();
+    if keys.is_empty() {
+        return Err(GenError::UnknownLanguage(source_lang.to_string()));
+    }
+
+    writeln!(writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        writer,
+        "<xliff xmlns=\"{}\" version=\"2.0\" srcLang=\"{}\" trgLang=\"{}\">",
+        XLIFF_2_0_NAMESPACE,
+        escape(source_lang),
+        escape(target_lang)
+    )?;
+    writeln!(writer, "  <file id=\"strings\">")?;
+    for key in keys {
+        for line in format_key(key, source_lang, target_lang) {
+            writeln!(writer, "    {}", line)?;
+        }
+    }
+    writeln!(writer, "  </file>")?;
+    writeln!(writer, "</xliff>")?;
+    Ok(())
+}
+
+fn value_of<'a>(key: &'a Key, lang: &str) -> Option<&'a StringValue> {
+    key.localizations
+        .iter()
+        .find(|localization| localization.language_code == lang)
+        .map(|localization| &localization.value)
+}
+
+fn format_key(key: &Key, source_lang: &str, target_lang: &str) -> Vec<String> {
+    let target = value_of(key, target_lang);
+    match value_of(key, source_lang) {
+        None => Vec::new(),
+        Some(StringValue::Single(source)) => {
+            let target = match target {
+                Some(StringValue::Single(text)) => Some(text.as_str()),
+                _ => None,
+            };
+            format_unit(&key.name, None, key.comment.as_deref(), source, target)
+        }
+        Some(StringValue::Array(items)) => {
+            let target = match target {
+                Some(StringValue::Array(items)) => Some(array_text(items)),
+                _ => None,
+            };
+            let source = array_text(items);
+            let comment = key.comment.as_deref();
+            format_unit(&key.name, None, comment, &source, target.as_deref())
+        }
+        Some(StringValue::Plural { quantities }) => {
+            let target: &[PluralValue] = match target {
+                Some(StringValue::Plural { quantities }) => quantities,
+                _ => &[],
+            };
+            let mut categories: Vec<&str> = quantities
+                .iter()
+                .chain(target)
+                .map(|item| item.quantity.as_str())
+                .collect();
+            categories.sort_by_key(|quantity| CldrCategory::from_quantity(quantity));
+            categories.dedup();
+
+            let mut result = vec![format!(
+                "<group id=\"{}\" type=\"utas:plural\">",
+                escape(&key.name)
+            )];
+            if let Some(comment) = &key.comment {
+                result.extend(
+                    format_notes(comment)
+                        .into_iter()
+                        .map(|line| format!("  {}", line)),
+                );
+            }
+            let text_of = |items: &[PluralValue], quantity: &str| {
+                items
+                    .iter()
+                    .find(|item| item.quantity == quantity)
+                    .map(|item| item.text.clone())
+            };
+            for category in categories {
+                // Source language may have fewer categories than the target one
+                let Some(source) =
+                    text_of(quantities, category).or_else(|| text_of(quantities, "other"))
+                else {
+                    continue;
+                };
+                let target = text_of(target, category);
+                let id = format!("{}.{}", key.name, category);
+                let unit = format_unit(&id, Some(category), None, &source, target.as_deref());
+                result.extend(unit.into_iter().map(|line| format!("  {}", line)));
+            }
+            result.push("</group>".to_string());
+            result
+        }
+    }
+}
+
+fn format_unit(
+    id: &str,
+    name: Option<&str>,
+    comment: Option<&str>,
+    source: &str,
+    target: Option<&str>,
+) -> Vec<String> {
+    let name = name
+        .map(|name| format!(" name=\"{}\"", escape(name)))
+        .unwrap_or_default();
+    let mut result = vec![format!("<unit id=\"{}\"{}>", escape(id), name)];
+    if let Some(comment) = comment {
+        result.extend(
+            format_notes(comment)
+                .into_iter()
+                .map(|line| format!("  {}", line)),
+        );
+    }
+    result.push("  <segment>".to_string());
+    result.push(format!("    <source>{}</source>", to_xliff_text(source)));
+    if let Some(target) = target {
+        result.push(format!("    <target>{}</target>", to_xliff_text(target)));
+    }
+    result.push("  </segment>".to_string());
+    result.push("</unit>".to_string());
+    result
+}
+
+fn format_notes(comment: &str) -> Vec<String> {
+    vec![
+        "<notes>".to_string(),
+        format!(
+            "  <note category=\"description\">{}</note>",
+            escape(comment)
+        ),
+        "</notes>".to_string(),
+    ]
+}
+
+/// Reverts Android specific escaping done while parsing and escapes text for XML
+fn to_xliff_text(text: &str) -> String {
+    escape(unescape_android(text).as_str()).to_string()
+}
+
+// -----------------------------  test tools ------------------------------
+#[cfg(test)]
+fn generate_to_string(file: &File, source_lang: &str, target_lang: &str) -> String {
+    let mut buffer: Vec<u8> = Vec::new();
+    generate_xliff_2_0_to_writer(file, source_lang, target_lang, &mut buffer).unwrap();
+    String::from_utf8(buffer).unwrap()
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn generate_xliff_2_0_for_2_langs() {
+    let single = |text: &str| StringValue::Single(text.to_string());
+    let mut login = key(
+        "login",
+        vec![
+            ("en", single("Log in &amp; go")),
+            ("de", single("Anmelden")),
+        ],
+    );
+    login.comment = Some("Button <title>".to_string());
+    let keys = vec![
+        login,
+        key("untranslated", vec![("en", single("Don\\'t"))]),
+        key("german_only", vec![("de", single("Nur Deutsch"))]),
+        key(
+            "days",
+            vec![
+                (
+                    "en",
+                    StringValue::plural()
+                        .quantity("one", "%d day")
+                        .quantity("other", "%d days")
+                        .build(),
+                ),
+                (
+                    "de",
+                    StringValue::plural().quantity("one", "%d Tag").build(),
+                ),
+            ],
+        ),
+    ];
+    let source = File {
+        sections: vec![Section { keys }],
+    };
+
+    let actual = generate_to_string(&source, "en", "de");
+
+    let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff xmlns="urn:oasis:names:tc:xliff:document:2.0" version="2.0" srcLang="en" trgLang="de">
+  <file id="strings">
+    <unit id="login">
+      <notes>
+        <note category="description">Button &lt;title&gt;</note>
+      </notes>
+      <segment>
+        <source>Log in &amp; go</source>
+        <target>Anmelden</target>
+      </segment>
+    </unit>
+    <unit id="untranslated">
+      <segment>
+        <source>Don&apos;t</source>
+      </segment>
+    </unit>
+    <group id="days" type="utas:plural">
+      <unit id="days.one" name="one">
+        <segment>
+          <source>%d day</source>
+          <target>%d Tag</target>
+        </segment>
+      </unit>
+      <unit id="days.other" name="other">
+        <segment>
+          <source>%d days</source>
+        </segment>
+      </unit>
+    </group>
+  </file>
+</xliff>
+"#;
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn generate_xliff_2_0_error_if_no_such_source_lang() {
+    let keys = vec![key(
+        "chicken",
+        vec![("en", StringValue::Single("Chicken".to_string()))],
+    )];
+    let source = File {
+        sections: vec![Section { keys }],
+    };
+
+    let actual = generate_xliff_2_0_to_writer(&source, "mn", "en", &mut Vec::new());
+    assert!(matches!(actual, Err(GenError::UnknownLanguage(lang)) if lang == "mn"));
+}
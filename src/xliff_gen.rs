@@ -0,0 +1,391 @@
+use quick_xml::escape::escape;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::gen_error::GenError;
+use crate::parse::{array_text, File, Key, PluralValue, StringValue};
+#[cfg(test)]
+use crate::parse::Section;
+use crate::platform::unescape_android;
+use crate::sink::WriteSink;
+#[cfg(test)]
+use crate::test_tools::key;
+
+const XLIFF_1_2_NAMESPACE: &str = "urn:oasis:names:tc:xliff:document:1.2";
+
+/// Generates XLIFF 1.2 document translating `source_lang` into `target_lang` and returns
+/// it as a String
+pub fn generate_xliff_to_string(
+    file: &File,
+    source_lang: &str,
+    target_lang: &str,
+) -> Result<String, GenError> {
+    let mut buffer: Vec<u8> = Vec::new();
+    generate_xliff_to_writer(file, source_lang, target_lang, &mut buffer)?;
+    String::from_utf8(buffer)
+        .map_err(|err| GenError::IoError(io::Error::new(io::ErrorKind::InvalidData, err)))
+}
+
+/// Generates XLIFF 1.2 document and streams it into the writer. Every key with a value in
+/// `source_lang` becomes a `<trans-unit>`, plural keys become a `<group>` of units per quantity.
+/// `<target>` is left out if the key has no value in `target_lang`.
+pub fn generate_xliff_to_writer<W: Write>(
+    file: &File,
+    source_lang: &str,
+    target_lang: &str,
+    writer: &mut W,
+) -> Result<(), GenError> {
+    if file.sections.is_empty() {
+        return Err(GenError::EmptyFile);
+    }
+    let keys: Vec<&Key> = file
+        .sections
+        .iter()
+        .flat_map(|section| &section.keys)
+        .filter(|key| value_of(key, source_lang).is_some())
+        .collect();
+    if keys.is_empty() {
+        return Err(GenError::UnknownLanguage(source_lang.to_string()));
+    }
+
+    writer.write_all("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n".as_bytes())?;
+    writer.write_all(
+        format!(
+            "<xliff version=\"1.2\" xmlns=\"{}\">\n",
+            XLIFF_1_2_NAMESPACE
+        )
+        .as_bytes(),
+    )?;
+    writer.write_all(
+        format!(
+            "  <file original=\"strings\" datatype=\"plaintext\" source-language=\"{}\" target-language=\"{}\">\n",
+            escape(source_lang),
+            escape(target_lang)
+        )
+        .as_bytes(),
+    )?;
+    writer.write_all("    <body>\n".as_bytes())?;
+    for key in keys {
+        for line in format_key(key, source_lang, target_lang) {
+            writer.write_all(format!("      {}\n", line).as_bytes())?;
+        }
+    }
+    writer.write_all("    </body>\n".as_bytes())?;
+    writer.write_all("  </file>\n".as_bytes())?;
+    writer.write_all("</xliff>\n".as_bytes())?;
+    Ok(())
+}
+
+/// Writes XLIFF 1.2 document translating `source_lang` into `target_lang` to `output_path`
+pub fn write_xliff_1_2(
+    file: &File,
+    source_lang: &str,
+    target_lang: &str,
+    output_path: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<(), GenError> {
+    // Generated in memory first so that nothing is written if generation fails
+    let xliff = generate_xliff_to_string(file, source_lang, target_lang)?;
+    sink.write(output_path, xliff.as_bytes())?;
+    Ok(())
+}
+
+/// Writes `<target_lang>.xliff` into `output_dir` for every language of the file
+/// except `source_lang`
+pub fn write_xliff_all(
+    file: &File,
+    source_lang: &str,
+    output_dir: &Path,
+    sink: &mut dyn WriteSink,
+) -> Result<(), GenError> {
+    let mut target_langs: Vec<&str> = Vec::new();
+    for str in file
+        .sections
+        .iter()
+        .flat_map(|section| &section.keys)
+        .flat_map(|key| &key.localizations)
+    {
+        if str.language_code != source_lang && !target_langs.contains(&str.language_code.as_str()) {
+            target_langs.push(&str.language_code);
+        }
+    }
+    for target_lang in target_langs {
+        let output_path = output_dir.join(format!("{}.xliff", target_lang));
+        write_xliff_1_2(file, source_lang, target_lang, &output_path, sink)?;
+    }
+    Ok(())
+}
+
+fn value_of<'a>(key: &'a Key, lang: &str) -> Option<&'a StringValue> {
+    key.localizations
+        .iter()
+        .find(|localization| localization.language_code == lang)
+        .map(|localization| &localization.value)
+}
+
+fn format_key(key: &Key, source_lang: &str, target_lang: &str) -> Vec<String> {
+    let Some(source) = value_of(key, source_lang) else {
+        return Vec::new();
+    };
+    let target = value_of(key, target_lang);
+    match (source, target) {
+        (StringValue::Single(source), None | Some(StringValue::Single(_))) => {
+            let target = match target {
+                Some(StringValue::Single(text)) => Some(text.as_str()),
+                _ => None,
+            };
+            format_unit(&key.name, key, source, target)
+        }
+        _ => {
+            let source = quantities(source);
+            let target = target.map(quantities).unwrap_or_default();
+            let mut categories: Vec<&PluralValue> = source.iter().chain(&target).collect();
+            categories.sort_by_key(|item| item.quantity_as_cldr_category());
+            categories.dedup_by(|left, right| left.quantity == right.quantity);
+
+            let mut result = vec![format!(
+                "<group id=\"{}\" restype=\"x-gettext-plurals\">",
+                escape(&key.name)
+            )];
+            for category in categories {
+                let text_of = |items: &[PluralValue], quantity: &str| {
+                    items
+                        .iter()
+                        .find(|item| item.quantity == quantity)
+                        .map(|item| item.text.clone())
+                };
+                // Source language may have fewer categories than the target one
+                let Some(source_text) =
+                    text_of(&source, &category.quantity).or_else(|| text_of(&source, "other"))
+                else {
+                    continue;
+                };
+                let target_text = text_of(&target, &category.quantity);
+                let id = format!("{}[{}]", key.name, category.quantity);
+                let unit = format_unit(&id, key, &source_text, target_text.as_deref());
+                result.extend(unit.into_iter().map(|line| format!("  {}", line)));
+            }
+            result.push("</group>".to_string());
+            result
+        }
+    }
+}
+
+fn format_unit(id: &str, key: &Key, source: &str, target: Option<&str>) -> Vec<String> {
+    let max_width = key
+        .max_length
+        .map(|max_length| format!(" maxwidth=\"{}\" size-unit=\"char\"", max_length))
+        .unwrap_or_default();
+    let mut result = vec![format!("<trans-unit id=\"{}\"{}>", escape(id), max_width)];
+    result.push(format!("  <source>{}</source>", to_xliff_text(source)));
+    if let Some(target) = target {
+        result.push(format!("  <target>{}</target>", to_xliff_text(target)));
+    }
+    if let Some(comment) = &key.comment {
+        result.push(format!("  <note>{}</note>", escape(comment)));
+    }
+    result.push("</trans-unit>".to_string());
+    result
+}
+
+/// Single and array values are taken as the only `other` quantity
+fn quantities(value: &StringValue) -> Vec<PluralValue> {
+    match value {
+        StringValue::Single(text) => vec![PluralValue::new("other", text.clone())],
+        StringValue::Plural { quantities } => quantities.clone(),
+        StringValue::Array(items) => vec![PluralValue::new("other", array_text(items))],
+    }
+}
+
+/// Reverts Android specific escaping done while parsing and escapes text for XML
+fn to_xliff_text(text: &str) -> String {
+    escape(unescape_android(text).as_str()).to_string()
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn generate_xliff_for_2_langs() -> Result<(), GenError> {
+    use quick_xml::events::Event;
+    use quick_xml::Reader;
+
+    let single = |text: &str| StringValue::Single(text.to_string());
+    let mut login = key(
+        "login",
+        vec![
+            ("en", single("Log in &amp; go")),
+            ("de", single("Anmelden")),
+        ],
+    );
+    login.max_length = Some(12);
+    login.comment = Some("Button <title>".to_string());
+    let keys = vec![
+        login,
+        key("untranslated", vec![("en", single("Don\\'t"))]),
+        key("german_only", vec![("de", single("Nur Deutsch"))]),
+        key(
+            "days",
+            vec![
+                (
+                    "en",
+                    StringValue::plural()
+                        .quantity("one", "%d day")
+                        .quantity("other", "%d days")
+                        .build(),
+                ),
+                (
+                    "de",
+                    StringValue::plural()
+                        .quantity("other", "%d Tage")
+                        .quantity("one", "%d Tag")
+                        .build(),
+                ),
+            ],
+        ),
+    ];
+    let source = File {
+        sections: vec![Section { keys }],
+    };
+
+    let xliff = generate_xliff_to_string(&source, "en", "de")?;
+
+    // (element path, attributes) of every start tag and (element path, text) of every text
+    let mut elements: Vec<(String, Vec<(String, String)>)> = Vec::new();
+    let mut texts: Vec<(String, String)> = Vec::new();
+    let mut path: Vec<String> = Vec::new();
+    let mut reader = Reader::from_str(&xliff);
+    loop {
+        match reader.read_event().expect("generated XLIFF is valid XML") {
+            Event::Start(start) => {
+                path.push(String::from_utf8(start.name().as_ref().to_vec()).unwrap());
+                let attributes = start
+                    .attributes()
+                    .map(|attribute| {
+                        let attribute = attribute.unwrap();
+                        (
+                            String::from_utf8(attribute.key.as_ref().to_vec()).unwrap(),
+                            attribute.unescape_value().unwrap().to_string(),
+                        )
+                    })
+                    .collect();
+                elements.push((path.join("/"), attributes));
+            }
+            Event::End(_) => {
+                path.pop();
+            }
+            Event::Text(text) if !text.unescape().unwrap().trim().is_empty() => {
+                texts.push((path.join("/"), text.unescape().unwrap().to_string()));
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    assert!(path.is_empty());
+
+    let attribute = |element: &(String, Vec<(String, String)>), name: &str| {
+        element
+            .1
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+    };
+    assert_eq!(elements[0].0, "xliff");
+    assert_eq!(attribute(&elements[0], "version").as_deref(), Some("1.2"));
+    assert_eq!(
+        attribute(&elements[0], "xmlns").as_deref(),
+        Some(XLIFF_1_2_NAMESPACE)
+    );
+    assert_eq!(elements[1].0, "xliff/file");
+    assert_eq!(
+        attribute(&elements[1], "source-language").as_deref(),
+        Some("en")
+    );
+    assert_eq!(
+        attribute(&elements[1], "target-language").as_deref(),
+        Some("de")
+    );
+
+    let unit_ids: Vec<String> = elements
+        .iter()
+        .filter(|(path, _)| path.ends_with("trans-unit"))
+        .filter_map(|element| attribute(element, "id"))
+        .collect();
+    assert_eq!(
+        unit_ids,
+        vec!["login", "untranslated", "days[one]", "days[other]"]
+    );
+    let login = elements
+        .iter()
+        .find(|element| attribute(element, "id").as_deref() == Some("login"))
+        .unwrap();
+    assert_eq!(attribute(login, "maxwidth").as_deref(), Some("12"));
+    let group = elements
+        .iter()
+        .find(|(path, _)| path == "xliff/file/body/group")
+        .unwrap();
+    assert_eq!(attribute(group, "id").as_deref(), Some("days"));
+
+    let text = |path: &str, text: &str| (path.to_string(), text.to_string());
+    let unit = "xliff/file/body/trans-unit";
+    let group_unit = "xliff/file/body/group/trans-unit";
+    assert_eq!(
+        texts,
+        vec![
+            text(&format!("{unit}/source"), "Log in & go"),
+            text(&format!("{unit}/target"), "Anmelden"),
+            text(&format!("{unit}/note"), "Button <title>"),
+            text(&format!("{unit}/source"), "Don't"),
+            text(&format!("{group_unit}/source"), "%d day"),
+            text(&format!("{group_unit}/target"), "%d Tag"),
+            text(&format!("{group_unit}/source"), "%d days"),
+            text(&format!("{group_unit}/target"), "%d Tage"),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn generate_xliff_error_if_no_such_source_lang() {
+    let keys = vec![key(
+        "chicken",
+        vec![("en", StringValue::Single("Chicken".to_string()))],
+    )];
+    let source = File {
+        sections: vec![Section { keys }],
+    };
+
+    let actual = generate_xliff_to_string(&source, "mn", "en");
+    assert!(matches!(actual, Err(GenError::UnknownLanguage(lang)) if lang == "mn"));
+}
+
+#[test]
+fn write_xliff_file_per_target_lang() -> Result<(), GenError> {
+    let single = |text: &str| StringValue::Single(text.to_string());
+    let keys = vec![key(
+        "chicken",
+        vec![
+            ("en", single("Chicken")),
+            ("de", single("Huhn")),
+            ("fr", single("Poulet")),
+        ],
+    )];
+    let source = File {
+        sections: vec![Section { keys }],
+    };
+    let dir = tempfile::tempdir()?;
+
+    let mut sink = crate::sink::FsSink::default();
+    write_xliff_all(&source, "en", dir.path(), &mut sink)?;
+
+    let mut names: Vec<String> = std::fs::read_dir(dir.path())?
+        .map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().to_string()))
+        .collect::<Result<_, _>>()?;
+    names.sort();
+    assert_eq!(names, vec!["de.xliff", "fr.xliff"]);
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("fr.xliff"))?,
+        generate_xliff_to_string(&source, "en", "fr")?
+    );
+
+    Ok(())
+}
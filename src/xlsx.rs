@@ -0,0 +1,531 @@
+use anyhow::{anyhow, Result};
+use calamine::{open_workbook, Reader, Xlsx};
+use indexmap::IndexMap;
+use rust_xlsxwriter::{Color, ConditionalFormatBlank, Format, Workbook};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::parse::KEY_ATTRIBUTES;
+use crate::twine_text::{raw_sections, set_localizations, RawSection};
+
+const DEFAULT_SHEET_NAME: &str = "Strings";
+const SUMMARY_SHEET_NAME: &str = "Summary";
+/// Excel limit for worksheet names
+const MAX_SHEET_NAME_LENGTH: usize = 31;
+/// Characters which Excel doesn't allow in worksheet names
+const INVALID_SHEET_NAME_CHARS: [char; 7] = ['[', ']', ':', '*', '?', '/', '\\'];
+/// Columns preceding language columns
+const KEY_COLUMN_HEADERS: [&str; 2] = ["key", "quantity"];
+
+/// Row of exported sheet, plural keys take a row per quantity
+#[derive(PartialEq, Eq, Debug)]
+struct Row {
+    key: String,
+    quantity: Option<String>,
+    /// Values in order of sheet languages
+    values: Vec<Option<String>>,
+}
+
+#[derive(PartialEq, Eq, Debug)]
+struct Sheet {
+    name: String,
+    rows: Vec<Row>,
+}
+
+/// Writes twine file content into Excel workbook with a sheet per section.
+/// Sheets have a row per key and a column per language, missing translations are
+/// highlighted and key columns are protected from editing.
+/// `Summary` sheet lists completeness of each language.
+pub fn export_xlsx(content: &str, path: impl AsRef<Path>) -> Result<()> {
+    let sections = raw_sections(content);
+    let langs = languages(&sections);
+    let mut sheets: Vec<Sheet> = sections
+        .iter()
+        .map(|section| build_sheet(section, &langs))
+        .collect();
+    // Excel compares worksheet names case-insensitively
+    let mut used_names: HashSet<String> = HashSet::from([SUMMARY_SHEET_NAME.to_lowercase()]);
+    for sheet in &mut sheets {
+        sheet.name = unique_sheet_name(&sheet.name, &mut used_names);
+    }
+
+    let header_format = Format::new().set_bold();
+    let value_format = Format::new().set_unlocked();
+    let missing_format = Format::new().set_background_color(Color::RGB(0xFFC7CE));
+
+    let mut workbook = Workbook::new();
+    for sheet in &sheets {
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(&sheet.name)?;
+        worksheet.protect();
+
+        let headers = KEY_COLUMN_HEADERS
+            .iter()
+            .copied()
+            .chain(langs.iter().copied());
+        for (col, header) in headers.enumerate() {
+            worksheet.write_string_with_format(0, col as u16, header, &header_format)?;
+        }
+        let first_lang_col = KEY_COLUMN_HEADERS.len() as u16;
+        for (index, row) in sheet.rows.iter().enumerate() {
+            let row_number = index as u32 + 1;
+            worksheet.write_string(row_number, 0, &row.key)?;
+            if let Some(quantity) = &row.quantity {
+                worksheet.write_string(row_number, 1, quantity)?;
+            }
+            for (lang_index, value) in row.values.iter().enumerate() {
+                let col = first_lang_col + lang_index as u16;
+                match value {
+                    Some(value) => {
+                        worksheet.write_string_with_format(row_number, col, value, &value_format)?
+                    }
+                    None => worksheet.write_blank(row_number, col, &value_format)?,
+                };
+            }
+        }
+        if !sheet.rows.is_empty() && !langs.is_empty() {
+            worksheet.add_conditional_format(
+                1,
+                first_lang_col,
+                sheet.rows.len() as u32,
+                first_lang_col + langs.len() as u16 - 1,
+                &ConditionalFormatBlank::new().set_format(&missing_format),
+            )?;
+        }
+    }
+
+    let summary = workbook.add_worksheet();
+    summary.set_name(SUMMARY_SHEET_NAME)?;
+    for (col, header) in ["language", "translated", "total", "percent"]
+        .iter()
+        .enumerate()
+    {
+        summary.write_string_with_format(0, col as u16, *header, &header_format)?;
+    }
+    for (index, (lang, translated, total)) in
+        completeness(&sections, &langs).into_iter().enumerate()
+    {
+        let row_number = index as u32 + 1;
+        let percent = if total == 0 {
+            100.0
+        } else {
+            translated as f64 * 100.0 / total as f64
+        };
+        summary.write_string(row_number, 0, lang)?;
+        summary.write_number(row_number, 1, translated as f64)?;
+        summary.write_number(row_number, 2, total as f64)?;
+        summary.write_number(row_number, 3, (percent * 10.0).round() / 10.0)?;
+    }
+
+    workbook.save(path)?;
+    Ok(())
+}
+
+/// Value of Excel workbook which differs from existing localization of twine file
+#[derive(PartialEq, Eq, Debug)]
+pub struct Conflict {
+    pub key: String,
+    /// Localization name, e.g. `de` or `de:one`
+    pub name: String,
+    pub old: String,
+    pub new: String,
+}
+
+pub struct ImportResult {
+    /// Updated twine file content
+    pub content: String,
+    /// Keys of workbook which are missing in twine file
+    pub unknown_keys: Vec<String>,
+    /// Existing localizations which were replaced with workbook values
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Merges localizations of Excel workbook in `export_xlsx` format into twine file content.
+/// All sheets except `Summary` are imported unless `sheet_name` is set.
+pub fn import_xlsx(
+    path: impl AsRef<Path>,
+    twine_content: &str,
+    sheet_name: Option<&str>,
+) -> Result<ImportResult> {
+    let mut sheets = read_workbook(path)?;
+    sheets.retain(|(name, _)| match sheet_name {
+        Some(sheet_name) => name == sheet_name,
+        None => name != SUMMARY_SHEET_NAME,
+    });
+    if let Some(sheet_name) = sheet_name {
+        if sheets.is_empty() {
+            return Err(anyhow!("Sheet \"{}\" is not found", sheet_name));
+        }
+    }
+
+    let sections = raw_sections(twine_content);
+    let existing: HashMap<(&str, &str), &str> = sections
+        .iter()
+        .flat_map(|section| &section.keys)
+        .flat_map(|key| {
+            key.entries.iter().map(move |entry| {
+                (
+                    (key.name.as_str(), entry.name.as_str()),
+                    entry.value.as_str(),
+                )
+            })
+        })
+        .collect();
+    let mut values: IndexMap<String, Vec<(String, String)>> = IndexMap::new();
+    let mut conflicts: Vec<Conflict> = Vec::new();
+    for (_, rows) in &sheets {
+        let Some((header, rows)) = rows.split_first() else {
+            continue;
+        };
+        let langs = header.get(KEY_COLUMN_HEADERS.len()..).unwrap_or_default();
+        for row in rows {
+            let Some(key) = row.first().filter(|key| !key.is_empty()) else {
+                continue;
+            };
+            let quantity = row.get(1).filter(|quantity| !quantity.is_empty());
+            for (lang, value) in langs
+                .iter()
+                .zip(&row[KEY_COLUMN_HEADERS.len().min(row.len())..])
+            {
+                if lang.is_empty() || value.is_empty() {
+                    continue;
+                }
+                let name = match quantity {
+                    Some(quantity) => format!("{}:{}", lang, quantity),
+                    None => lang.clone(),
+                };
+                match existing.get(&(key.as_str(), name.as_str())) {
+                    Some(old) if old == value => continue,
+                    Some(old) => conflicts.push(Conflict {
+                        key: key.clone(),
+                        name: name.clone(),
+                        old: old.to_string(),
+                        new: value.clone(),
+                    }),
+                    None => {}
+                }
+                values
+                    .entry(key.clone())
+                    .or_default()
+                    .push((name, value.clone()));
+            }
+        }
+    }
+
+    let (content, unknown_keys) = set_localizations(twine_content, &values);
+    Ok(ImportResult {
+        content,
+        unknown_keys,
+        conflicts,
+    })
+}
+
+/// Reads names and cell values of all worksheets, cells missing in a row are empty strings
+fn read_workbook(path: impl AsRef<Path>) -> Result<Vec<(String, Vec<Vec<String>>)>> {
+    let mut workbook: Xlsx<_> = open_workbook(path)?;
+    let mut result = Vec::new();
+    for name in workbook.sheet_names() {
+        let range = workbook.worksheet_range(&name)?;
+        // Range starts at the first used cell, leading empty columns are kept for positions
+        let first_column = range.start().map_or(0, |(_, column)| column as usize);
+        let rows = range
+            .rows()
+            .map(|row| {
+                let cells = row.iter().map(|cell| cell.to_string());
+                vec![String::new(); first_column].into_iter().chain(cells).collect()
+            })
+            .collect();
+        result.push((name, rows));
+    }
+    Ok(result)
+}
+
+/// Languages of all keys in order of their first appearance
+fn languages(sections: &[RawSection]) -> Vec<&str> {
+    let mut result: Vec<&str> = Vec::new();
+    let entries = sections
+        .iter()
+        .flat_map(|section| &section.keys)
+        .flat_map(|key| &key.entries);
+    for entry in entries {
+        if KEY_ATTRIBUTES.contains(&entry.name.as_str()) {
+            continue;
+        }
+        let lang = entry_lang(&entry.name);
+        if !result.contains(&lang) {
+            result.push(lang);
+        }
+    }
+    result
+}
+
+fn entry_lang(entry_name: &str) -> &str {
+    entry_name
+        .split_once(':')
+        .map(|(lang, _)| lang)
+        .unwrap_or(entry_name)
+}
+
+fn build_sheet(section: &RawSection, langs: &[&str]) -> Sheet {
+    let mut rows: Vec<Row> = Vec::new();
+    for key in &section.keys {
+        // Rows by quantity, `None` for single values
+        let mut key_rows: IndexMap<Option<&str>, Vec<Option<String>>> = IndexMap::new();
+        for entry in &key.entries {
+            if KEY_ATTRIBUTES.contains(&entry.name.as_str()) {
+                continue;
+            }
+            let quantity = entry.name.split_once(':').map(|(_, quantity)| quantity);
+            let Some(lang_index) = langs
+                .iter()
+                .position(|lang| *lang == entry_lang(&entry.name))
+            else {
+                continue;
+            };
+            let values = key_rows
+                .entry(quantity)
+                .or_insert_with(|| vec![None; langs.len()]);
+            values[lang_index] = Some(entry.value.clone());
+        }
+        if key_rows.is_empty() {
+            key_rows.insert(None, vec![None; langs.len()]);
+        }
+        for (quantity, values) in key_rows {
+            rows.push(Row {
+                key: key.name.clone(),
+                quantity: quantity.map(|quantity| quantity.to_string()),
+                values,
+            });
+        }
+    }
+
+    let name = if section.name.is_empty() {
+        DEFAULT_SHEET_NAME
+    } else {
+        &section.name
+    };
+    Sheet {
+        name: name
+            .chars()
+            .map(|char| {
+                if INVALID_SHEET_NAME_CHARS.contains(&char) {
+                    '_'
+                } else {
+                    char
+                }
+            })
+            .take(MAX_SHEET_NAME_LENGTH)
+            .collect(),
+        rows,
+    }
+}
+
+/// `name` or `name (2)`, `name (3)`, etc. which is not in `used_names` yet, shortened to
+/// fit Excel limit. The returned name is added to `used_names` in lowercase.
+fn unique_sheet_name(name: &str, used_names: &mut HashSet<String>) -> String {
+    let mut result = name.to_string();
+    let mut number = 1;
+    while used_names.contains(&result.to_lowercase()) {
+        number += 1;
+        let suffix = format!(" ({})", number);
+        let base: String = name
+            .chars()
+            .take(MAX_SHEET_NAME_LENGTH - suffix.len())
+            .collect();
+        result = format!("{}{}", base, suffix);
+    }
+    used_names.insert(result.to_lowercase());
+    result
+}
+
+/// (language, number of keys localized to it, number of keys)
+fn completeness<'a>(sections: &[RawSection], langs: &[&'a str]) -> Vec<(&'a str, usize, usize)> {
+    let keys: Vec<_> = sections.iter().flat_map(|section| &section.keys).collect();
+    langs
+        .iter()
+        .map(|lang| {
+            let translated = keys
+                .iter()
+                .filter(|key| {
+                    key.entries
+                        .iter()
+                        .any(|entry| entry_lang(&entry.name) == *lang)
+                })
+                .count();
+            (*lang, translated, keys.len())
+        })
+        .collect()
+}
+
+// ------------------------------- tests -----------------------------------
+#[test]
+fn builds_sheet_rows_per_key_and_quantity() {
+    let content = "[[Auth]]
+  [login]
+    en = Login
+    comment = Button title
+    ru = Вход
+
+  [days]
+    en:one = %d day
+    en:other = %d days
+    ru:other = %d дней
+";
+    let sections = raw_sections(content);
+    let langs = languages(&sections);
+    assert_eq!(langs, vec!["en", "ru"]);
+
+    let sheet = build_sheet(&sections[0], &langs);
+    let row = |key: &str, quantity: Option<&str>, values: [Option<&str>; 2]| Row {
+        key: key.to_string(),
+        quantity: quantity.map(|quantity| quantity.to_string()),
+        values: values
+            .iter()
+            .map(|value| value.map(|value| value.to_string()))
+            .collect(),
+    };
+    assert_eq!(
+        sheet,
+        Sheet {
+            name: "Auth".to_string(),
+            rows: vec![
+                row("login", None, [Some("Login"), Some("Вход")]),
+                row("days", Some("one"), [Some("%d day"), None]),
+                row("days", Some("other"), [Some("%d days"), Some("%d дней")]),
+            ],
+        }
+    );
+    assert_eq!(
+        completeness(&sections, &langs),
+        vec![("en", 2, 2), ("ru", 2, 2)]
+    );
+}
+
+#[test]
+fn imports_exported_workbook() -> Result<()> {
+    let exported = "[[Auth]]
+  [login]
+    en = Login
+    ru = Вход
+
+  [days]
+    en:one = %d day
+    en:other = %d days
+    ru:other = %d дней
+
+  [removed]
+    en = Removed
+";
+    let current = "[[Auth]]
+  [login]
+    en = Login
+    ru = Логин
+
+  [days]
+    en:one = %d day
+    en:other = %d days
+";
+    let workbook = tempfile::NamedTempFile::new()?;
+    export_xlsx(exported, workbook.path())?;
+
+    let actual = import_xlsx(workbook.path(), current, None)?;
+    let expected = "[[Auth]]
+  [login]
+    en = Login
+    ru = Вход
+
+  [days]
+    en:one = %d day
+    en:other = %d days
+    ru:other = %d дней
+";
+    assert_eq!(expected, actual.content);
+    assert_eq!(actual.unknown_keys, vec!["removed".to_string()]);
+    assert_eq!(
+        actual.conflicts,
+        vec![Conflict {
+            key: "login".to_string(),
+            name: "ru".to_string(),
+            old: "Логин".to_string(),
+            new: "Вход".to_string(),
+        }]
+    );
+    assert!(import_xlsx(workbook.path(), current, Some("Missing")).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn makes_sheet_names_valid_and_unique() -> Result<()> {
+    let content = "[[Settings: Main]]
+  [title]
+    en = Settings
+
+[[Summary]]
+  [total]
+    en = Total
+
+[[settings_ main]]
+  [subtitle]
+    en = Main
+
+[[A very long section name exceeding the limit]]
+  [first]
+    en = First
+
+[[A very long section name exceeding it too]]
+  [second]
+    en = Second
+";
+    let sections = raw_sections(content);
+    let langs = languages(&sections);
+    let mut used_names = HashSet::from([SUMMARY_SHEET_NAME.to_lowercase()]);
+    let names: Vec<String> = sections
+        .iter()
+        .map(|section| unique_sheet_name(&build_sheet(section, &langs).name, &mut used_names))
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "Settings_ Main",
+            "Summary (2)",
+            "settings_ main (2)",
+            "A very long section name exceed",
+            "A very long section name ex (2)",
+        ]
+    );
+
+    let workbook = tempfile::NamedTempFile::new()?;
+    export_xlsx(content, workbook.path())?;
+    Ok(())
+}
+
+#[test]
+fn imports_rich_text_values() -> Result<()> {
+    let current = "[[Auth]]
+  [login]
+    en = Login
+";
+    let path = tempfile::Builder::new().suffix(".xlsx").tempfile()?;
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("Auth")?;
+    for (col, header) in ["key", "quantity", "en", "de"].iter().enumerate() {
+        worksheet.write_string(0, col as u16, *header)?;
+    }
+    worksheet.write_string(1, 0, "login")?;
+    worksheet.write_string(1, 2, "Login")?;
+    let bold = Format::new().set_bold();
+    let default = Format::default();
+    worksheet.write_rich_string(1, 3, &[(&bold, "Anmel"), (&default, "dung")])?;
+    workbook.save(path.path())?;
+
+    let actual = import_xlsx(path.path(), current, None)?;
+    let expected = "[[Auth]]
+  [login]
+    en = Login
+    de = Anmeldung
+";
+    assert_eq!(expected, actual.content);
+    Ok(())
+}
@@ -0,0 +1,12 @@
+import androidx.compose.runtime.Composable
+import androidx.compose.ui.res.pluralStringResource
+import androidx.compose.ui.res.stringResource
+
+@Composable
+fun loginButtonString(): String = stringResource(R.string.login_button)
+
+@Composable
+fun daysLeftString(count: Int): String = pluralStringResource(R.plurals.days_left, count, count)
+
+@Composable
+fun greenlandString(): String = stringResource(R.string.Greenland)
@@ -69,10 +69,246 @@ fn case_android_13() -> Result<(), Box<dyn Error>> {
     basic_test_case("android", "case13", None)
 }
 
+#[test]
+fn case_android_14() -> Result<(), Box<dyn Error>> {
+    basic_test_case("android", "case14", None)
+}
+
+#[test]
+fn case_android_15() -> Result<(), Box<dyn Error>> {
+    basic_test_case("android", "case15", None)
+}
+
+#[test]
+fn case_android_16() -> Result<(), Box<dyn Error>> {
+    basic_test_case("android", "case16", None)
+}
+
+#[test]
+fn case_android_17() -> Result<(), Box<dyn Error>> {
+    basic_test_case("android", "case17", None)
+}
+
+#[test]
+fn case_android_18() -> Result<(), Box<dyn Error>> {
+    basic_test_case("android", "case18", None)
+}
+
+#[test]
+fn case_android_19() -> Result<(), Box<dyn Error>> {
+    basic_test_case("android", "case19", Some("en".to_string()))
+}
+
+#[test]
+fn case_android_20() -> Result<(), Box<dyn Error>> {
+    basic_test_case("android", "case20", None)
+}
+
+#[test]
+fn case_xcstrings_1() -> Result<(), Box<dyn Error>> {
+    basic_test_case("xcstrings", "case1", None)
+}
+
+#[test]
+fn case_xcstrings_2() -> Result<(), Box<dyn Error>> {
+    basic_test_case("xcstrings", "case2", Some("ru".to_string()))
+}
+
+#[test]
+fn case_compose_wrappers_1() -> Result<(), Box<dyn Error>> {
+    basic_test_case("compose-wrappers", "case1", None)
+}
+
+#[test]
+fn case_compose_multiplatform_1() -> Result<(), Box<dyn Error>> {
+    basic_test_case("compose-multiplatform", "case1", Some("en".to_string()))
+}
+
+#[test]
+fn case_kmp_moko_1() -> Result<(), Box<dyn Error>> {
+    basic_test_case("kmp-moko", "case1", Some("en".to_string()))
+}
+
+#[test]
+fn case_android_flatten_plurals_1() -> Result<(), Box<dyn Error>> {
+    test_case_with_args(
+        "android",
+        "flatten_plurals_case1",
+        Some("en".to_string()),
+        &["--android-flatten-plurals"],
+    )
+}
+
+#[test]
+fn case_android_only_languages_1() -> Result<(), Box<dyn Error>> {
+    // Keys missing in "de" are absent from values-de, Android falls back to values
+    test_case_with_args(
+        "android",
+        "only_languages_case1",
+        Some("en".to_string()),
+        &["--only-languages", "en,de"],
+    )
+}
+
+#[test]
+fn filter_lang_fails_on_languages_missing_in_source() -> Result<(), Box<dyn Error>> {
+    let case = Path::new("tests")
+        .join("cases")
+        .join("android")
+        .join("only_languages_case1");
+    let output = assert_fs::TempDir::new()?;
+    let mut cmd = Command::cargo_bin("utas")?;
+    cmd.arg("android")
+        .arg(case.join("input"))
+        .arg(output.path())
+        .args(["en", "--filter-lang", "en,pt"]);
+    cmd.assert().failure().stderr(predicates::str::contains(
+        "not present in the source file: pt",
+    ));
+    Ok(())
+}
+
+#[test]
+fn case_export_unity_1() -> Result<(), Box<dyn Error>> {
+    test_case_with_args("export-unity", "case1", None, &["--target", "unity-csv"])
+}
+
+#[test]
+fn all_platforms_into_subdirectories() -> Result<(), Box<dyn Error>> {
+    let temp = assert_fs::TempDir::new()?;
+    let case = Path::new("tests")
+        .join("cases")
+        .join("android")
+        .join("case19");
+    let mut cmd = Command::cargo_bin("utas")?;
+    cmd.arg("all")
+        .arg(case.join("input"))
+        .arg(temp.path())
+        .arg("en");
+    cmd.assert().success();
+
+    let result = file::compare_dirs_content(case.join("output"), temp.path().join("android"))?;
+    assert!(
+        CompareDirsContentResult::Eq == result,
+        "{}",
+        format_result(result)
+    );
+    assert!(temp.path().join("ios").join("de.lproj").is_dir());
+    assert!(temp.path().join("json").join("de.json").is_file());
+    Ok(())
+}
+
+#[test]
+fn test_subcommand_compares_with_expected_output() -> Result<(), Box<dyn Error>> {
+    let case = Path::new("tests")
+        .join("cases")
+        .join("android")
+        .join("case19");
+    let mut cmd = Command::cargo_bin("utas")?;
+    cmd.arg("test")
+        .arg("android")
+        .arg(case.join("input"))
+        .arg(case.join("output"))
+        .arg("en");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("utas")?;
+    cmd.arg("test")
+        .arg("android")
+        .arg(case.join("input"))
+        .arg(case.join("output"))
+        .arg("de");
+    cmd.assert()
+        .failure()
+        .stderr(predicates::str::contains("values/strings.xml"));
+    Ok(())
+}
+
+#[test]
+fn check_subcommand_reports_problems() -> Result<(), Box<dyn Error>> {
+    let input = Path::new("tests")
+        .join("cases")
+        .join("android")
+        .join("case19")
+        .join("input")
+        .join("strings.txt");
+    let mut cmd = Command::cargo_bin("utas")?;
+    cmd.arg("check").arg(&input);
+    cmd.assert()
+        .success()
+        .stdout(predicates::str::contains("No problems found"));
+
+    let temp = assert_fs::TempDir::new()?;
+    let input = temp.path().join("strings.txt");
+    std::fs::write(&input, "[[Login]]\n  [title]\n    eng = Login\n")?;
+    let mut cmd = Command::cargo_bin("utas")?;
+    cmd.arg("check").arg(&input).args(["--format", "json"]);
+    cmd.assert().failure().stdout(predicates::str::contains(
+        "\"kind\":\"invalid_language_code\"",
+    ));
+    Ok(())
+}
+
+#[test]
+fn merges_source_files_into_one() -> Result<(), Box<dyn Error>> {
+    let input = assert_fs::TempDir::new()?;
+    let output = assert_fs::TempDir::new()?;
+    std::fs::write(
+        input.path().join("a.txt"),
+        "[[Login]]\n  [title]\n    en = Login\n",
+    )?;
+    std::fs::write(
+        input.path().join("b.txt"),
+        "[[Login]]\n  [title]\n    en = Sign in\n",
+    )?;
+    let mut cmd = Command::cargo_bin("utas")?;
+    cmd.arg("android")
+        .arg(input.path())
+        .arg(output.path())
+        .args(["en", "--merge-into", "strings"]);
+    cmd.assert().failure().stderr(predicates::str::contains(
+        "Key \"title\" is defined in several source files",
+    ));
+
+    let mut cmd = Command::cargo_bin("utas")?;
+    cmd.arg("android")
+        .arg(input.path())
+        .arg(output.path())
+        .args(["en", "--merge-into", "strings", "--allow-merge-overwrite"]);
+    cmd.assert().success();
+    let strings = std::fs::read_to_string(output.path().join("values/strings.xml"))?;
+    assert!(strings.contains("Sign in"));
+    assert!(!output.path().join("values/a.xml").exists());
+    Ok(())
+}
+
+#[test]
+fn version_in_json_format() -> Result<(), Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin("utas")?;
+    cmd.args(["version", "--format", "json"]);
+    cmd.assert()
+        .success()
+        .stdout(predicates::str::contains(format!(
+            "\"version\":\"{}\"",
+            env!("CARGO_PKG_VERSION")
+        )))
+        .stdout(predicates::str::contains("\"git_sha\":"));
+    Ok(())
+}
+
 fn basic_test_case(
     platform: &str,
     case_rel_path: &str,
     default_lang: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    test_case_with_args(platform, case_rel_path, default_lang, &[])
+}
+
+fn test_case_with_args(
+    platform: &str,
+    case_rel_path: &str,
+    default_lang: Option<String>,
+    extra_args: &[&str],
 ) -> Result<(), Box<dyn Error>> {
     let mut cmd = Command::cargo_bin("utas")?;
 
@@ -81,13 +317,13 @@ fn basic_test_case(
 
     let input = Path::new("tests")
         .join("cases")
-        .join("android")
+        .join(platform)
         .join(case_rel_path)
         .join("input");
     let output = temp.path();
     let expected = Path::new("tests")
         .join("cases")
-        .join("android")
+        .join(platform)
         .join(case_rel_path)
         .join("output");
 
@@ -97,6 +333,7 @@ fn basic_test_case(
     if default_lang.is_some() {
         cmd.arg(default_lang.unwrap());
     }
+    cmd.args(extra_args);
     cmd.assert().success();
     let result = file::compare_dirs_content(expected, output)?;
     match &result {
@@ -161,3 +398,206 @@ fn format_file_diffs(diffs: &Vec<Diff>) -> String {
 
     result
 }
+
+#[test]
+fn dry_run_writes_nothing() -> Result<(), Box<dyn Error>> {
+    let output = assert_fs::TempDir::new()?;
+    let output_dir = output.path().join("res");
+    let mut cmd = Command::cargo_bin("utas")?;
+    cmd.arg("android")
+        .arg("tests/cases/android/case1/input")
+        .arg(&output_dir)
+        .args(["en", "--dry-run"]);
+    cmd.assert()
+        .success()
+        .stdout(predicates::str::contains(format!(
+            "created {}",
+            output_dir.join("values").join("src1.xml").display()
+        )))
+        .stdout(predicates::str::contains("<string name=\"general\""));
+    assert!(!output_dir.exists());
+    Ok(())
+}
+
+#[test]
+fn combines_source_files_of_single_file_platforms() -> Result<(), Box<dyn Error>> {
+    let input = assert_fs::TempDir::new()?;
+    let output = assert_fs::TempDir::new()?;
+    std::fs::write(
+        input.path().join("a.txt"),
+        "[[Login]]\n  [title]\n    en = Login\n",
+    )?;
+    std::fs::write(
+        input.path().join("b.txt"),
+        "[[Profile]]\n  [name]\n    en = Name\n",
+    )?;
+    let mut cmd = Command::cargo_bin("utas")?;
+    cmd.arg("json").arg(input.path()).arg(output.path());
+    cmd.assert().success();
+    let json = std::fs::read_to_string(output.path().join("en.json"))?;
+    assert!(json.contains("\"title\": \"Login\""));
+    assert!(json.contains("\"name\": \"Name\""));
+
+    std::fs::write(
+        input.path().join("c.txt"),
+        "[[Login]]\n  [title]\n    en = Sign in\n",
+    )?;
+    let mut cmd = Command::cargo_bin("utas")?;
+    cmd.arg("json").arg(input.path()).arg(output.path());
+    cmd.assert().failure().stderr(predicates::str::contains(
+        "Key \"title\" is defined in several source files",
+    ));
+
+    let mut cmd = Command::cargo_bin("utas")?;
+    cmd.arg("json")
+        .arg(input.path())
+        .arg(output.path())
+        .arg("--allow-merge-overwrite");
+    cmd.assert().success();
+    let json = std::fs::read_to_string(output.path().join("en.json"))?;
+    assert!(json.contains("\"title\": \"Sign in\""));
+    Ok(())
+}
+
+#[test]
+fn ios_platforms_fail_on_placeholder_mismatch() -> Result<(), Box<dyn Error>> {
+    let input = assert_fs::TempDir::new()?;
+    std::fs::write(
+        input.path().join("a.txt"),
+        "[[Login]]\n  [title]\n    en = Login\n",
+    )?;
+    std::fs::write(
+        input.path().join("b.txt"),
+        "[[Profile]]\n  [greeting]\n    en = Hello, %s\n    de = Hallo\n",
+    )?;
+    for platform in ["ios", "xcstrings"] {
+        let output = assert_fs::TempDir::new()?;
+        let mut cmd = Command::cargo_bin("utas")?;
+        cmd.arg(platform).arg(input.path()).arg(output.path());
+        cmd.assert().failure().stderr(predicates::str::contains(
+            "1 placeholder mismatches found",
+        ));
+    }
+    Ok(())
+}
+
+#[test]
+fn ios_fails_on_keys_defined_in_several_files() -> Result<(), Box<dyn Error>> {
+    let input = duplicate_greet_sources()?;
+    let output = assert_fs::TempDir::new()?;
+    let mut cmd = Command::cargo_bin("utas")?;
+    cmd.arg("ios").arg(input.path()).arg(output.path());
+    cmd.assert().failure().stderr(predicates::str::contains(
+        "Key \"greet\" is defined in several source files",
+    ));
+
+    let mut cmd = Command::cargo_bin("utas")?;
+    cmd.arg("ios")
+        .arg(input.path())
+        .arg(output.path())
+        .arg("--allow-merge-overwrite");
+    cmd.assert().success();
+    let strings =
+        std::fs::read_to_string(output.path().join("en.lproj/Localizable.strings"))?;
+    assert_eq!(strings.matches("\"greet\"").count(), 1);
+    assert!(strings.contains("\"greet\" = \"Hi\";"));
+    Ok(())
+}
+
+#[test]
+fn xcstrings_fails_on_keys_defined_in_several_files() -> Result<(), Box<dyn Error>> {
+    let input = duplicate_greet_sources()?;
+    let output = assert_fs::TempDir::new()?;
+    let mut cmd = Command::cargo_bin("utas")?;
+    cmd.arg("xcstrings").arg(input.path()).arg(output.path());
+    cmd.assert().failure().stderr(predicates::str::contains(
+        "Key \"greet\" is defined in several source files",
+    ));
+    assert!(!output.path().join("Localizable.xcstrings").exists());
+
+    let mut cmd = Command::cargo_bin("utas")?;
+    cmd.arg("xcstrings")
+        .arg(input.path())
+        .arg(output.path())
+        .arg("--allow-merge-overwrite");
+    cmd.assert().success();
+    let catalog = std::fs::read_to_string(output.path().join("Localizable.xcstrings"))?;
+    assert!(catalog.contains("\"Hi\""));
+    Ok(())
+}
+
+fn duplicate_greet_sources() -> Result<assert_fs::TempDir, Box<dyn Error>> {
+    let input = assert_fs::TempDir::new()?;
+    std::fs::write(
+        input.path().join("a.txt"),
+        "[[Greeting]]\n  [greet]\n    en = Hello\n",
+    )?;
+    std::fs::write(
+        input.path().join("b.txt"),
+        "[[Greeting]]\n  [greet]\n    en = Hi\n",
+    )?;
+    Ok(input)
+}
+
+#[test]
+fn selects_platform_with_target_option() -> Result<(), Box<dyn Error>> {
+    let case = Path::new("tests")
+        .join("cases")
+        .join("compose-wrappers")
+        .join("case1");
+    let output = assert_fs::TempDir::new()?;
+    let mut cmd = Command::cargo_bin("utas")?;
+    cmd.arg("--target")
+        .arg("compose-wrappers")
+        .arg(case.join("input"))
+        .arg(output.path());
+    cmd.assert().success();
+    let result = file::compare_dirs_content(case.join("output"), output.path())?;
+    assert!(CompareDirsContentResult::Eq == result);
+    Ok(())
+}
+
+#[test]
+fn selects_platform_with_format_option() -> Result<(), Box<dyn Error>> {
+    let input = assert_fs::TempDir::new()?;
+    let output = assert_fs::TempDir::new()?;
+    std::fs::write(
+        input.path().join("strings.txt"),
+        "[[Login]]\n  [title]\n    en = Terms & Conditions\n",
+    )?;
+    let mut cmd = Command::cargo_bin("utas")?;
+    cmd.arg(input.path())
+        .arg(output.path())
+        .arg("--format=ios");
+    cmd.assert().success();
+    let strings =
+        std::fs::read_to_string(output.path().join("en.lproj/Localizable.strings"))?;
+    assert!(strings.contains("\"title\" = \"Terms & Conditions\";"));
+    Ok(())
+}
+
+#[test]
+fn selects_xliff_version_with_format_option() -> Result<(), Box<dyn Error>> {
+    let input = assert_fs::TempDir::new()?;
+    std::fs::write(
+        input.path().join("strings.txt"),
+        "[[Login]]\n  [title]\n    en = Login\n    de = Anmelden\n",
+    )?;
+    for (format, namespace) in [
+        ("xliff1", "urn:oasis:names:tc:xliff:document:1.2"),
+        ("xliff2", "urn:oasis:names:tc:xliff:document:2.0"),
+    ] {
+        let output = assert_fs::TempDir::new()?;
+        let mut cmd = Command::cargo_bin("utas")?;
+        cmd.args(["--format", format])
+            .arg(input.path())
+            .arg(output.path());
+        cmd.assert().success();
+        let generated = std::fs::read_dir(output.path())?
+            .map(|entry| std::fs::read_to_string(entry?.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(generated.len(), 1);
+        assert!(generated[0].contains(namespace));
+    }
+    Ok(())
+}